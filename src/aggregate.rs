@@ -0,0 +1,615 @@
+//! Pairwise preferences and consensus aggregation over several rankings of the same
+//! candidates.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::rank::Rank;
+use crate::ranking::Ranking;
+
+/// How a pair of candidates tied within one ranking is counted in a [`PreferenceMatrix`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TieCounting {
+    /// Each of the tied candidates gets half a win over the other.
+    #[default]
+    Half,
+    /// The tie expresses no preference either way.
+    Ignore,
+}
+
+/// Pairwise preferences between candidates, counted over several rankings.
+///
+/// Candidates are identified by an id function and are kept in the order they are
+/// first seen across the input rankings. Built with [`preference_matrix`].
+#[derive(Clone, Debug)]
+pub struct PreferenceMatrix<Id> {
+    candidates: Vec<Id>,
+    index: HashMap<Id, usize>,
+    prefs: Vec<f64>,
+}
+
+impl<Id> PreferenceMatrix<Id>
+where
+    Id: Hash + Eq + Clone,
+{
+    /// Counts, for every ordered pair `(a, b)`, the rankings that put `a` above `b`.
+    ///
+    /// A candidate missing from a ranking expresses no preference in it, and a candidate
+    /// appearing more than once in the same ranking is taken at its best rank.
+    pub(crate) fn build<T, F>(rankings: &[Ranking<T>], mut id_fn: F, ties: TieCounting) -> Self
+    where
+        F: FnMut(&T) -> Id,
+    {
+        let mut candidates = Vec::new();
+        let mut index = HashMap::new();
+        let ranks_per_ranking: Vec<Vec<(usize, Rank)>> = rankings
+            .iter()
+            .map(|ranking| {
+                ranking
+                    .iter()
+                    .map(|(rank, item)| {
+                        let id = id_fn(item);
+                        let next = candidates.len();
+                        let i = *index.entry(id.clone()).or_insert(next);
+                        if i == next {
+                            candidates.push(id);
+                        }
+                        (i, rank)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let tie_weight = match ties {
+            TieCounting::Half => 0.5,
+            TieCounting::Ignore => 0.0,
+        };
+        let n = candidates.len();
+        let mut prefs = vec![0.0; n * n];
+        let mut rank_of: Vec<Option<Rank>> = vec![None; n];
+        for ranks in &ranks_per_ranking {
+            rank_of.iter_mut().for_each(|r| *r = None);
+            for &(i, rank) in ranks {
+                rank_of[i].get_or_insert(rank);
+            }
+            for a in 0..n {
+                let Some(ra) = rank_of[a] else { continue };
+                for b in 0..n {
+                    if a == b {
+                        continue;
+                    }
+                    let Some(rb) = rank_of[b] else { continue };
+                    if ra < rb {
+                        prefs[a * n + b] += 1.0;
+                    } else if ra == rb {
+                        prefs[a * n + b] += tie_weight;
+                    }
+                }
+            }
+        }
+
+        PreferenceMatrix {
+            candidates,
+            index,
+            prefs,
+        }
+    }
+
+    /// Returns the candidates in the order they were first seen.
+    pub fn candidates(&self) -> &[Id] {
+        &self.candidates
+    }
+
+    /// Returns how many rankings put `a` above `b`, counting ties per the
+    /// [`TieCounting`] the matrix was built with.
+    ///
+    /// Returns 0.0 if either id is not a candidate.
+    pub fn wins(&self, a: &Id, b: &Id) -> f64 {
+        match (self.index.get(a), self.index.get(b)) {
+            (Some(&a), Some(&b)) if a != b => self.pref(a, b),
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the candidate that beats every other candidate head-to-head, if any.
+    ///
+    /// A candidate beats another when strictly more rankings prefer it than the other
+    /// way around. A single candidate is trivially the Condorcet winner.
+    pub fn condorcet_winner(&self) -> Option<&Id> {
+        let n = self.len();
+        (0..n)
+            .find(|&a| (0..n).all(|b| a == b || self.beats(a, b)))
+            .map(|a| &self.candidates[a])
+    }
+
+    /// Returns `true` if the head-to-head majority relation contains a cycle, such as
+    /// A beating B, B beating C and C beating A.
+    pub fn has_cycle(&self) -> bool {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            OnPath,
+            Done,
+        }
+
+        let n = self.len();
+        let mut state = vec![State::Unvisited; n];
+        for root in 0..n {
+            if state[root] != State::Unvisited {
+                continue;
+            }
+            state[root] = State::OnPath;
+            let mut path = vec![(root, 0)];
+            while let Some((a, next)) = path.last_mut() {
+                let a = *a;
+                match (*next..n).find(|&b| self.beats(a, b)) {
+                    Some(b) => {
+                        *next = b + 1;
+                        match state[b] {
+                            State::OnPath => return true,
+                            State::Unvisited => {
+                                state[b] = State::OnPath;
+                                path.push((b, 0));
+                            }
+                            State::Done => {}
+                        }
+                    }
+                    None => {
+                        state[a] = State::Done;
+                        path.pop();
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn beats(&self, a: usize, b: usize) -> bool {
+        self.pref(a, b) > self.pref(b, a)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Returns the preference count of `a` over `b` by candidate index.
+    pub(crate) fn pref(&self, a: usize, b: usize) -> f64 {
+        self.prefs[a * self.len() + b]
+    }
+
+    /// Returns the total disagreement of a strict order of candidate indices with the
+    /// counted preferences.
+    pub(crate) fn disagreement(&self, order: &[usize]) -> f64 {
+        let mut total = 0.0;
+        for (i, &a) in order.iter().enumerate() {
+            for &b in &order[i + 1..] {
+                total += self.pref(b, a);
+            }
+        }
+        total
+    }
+
+    /// Returns the candidate indices in descending order of Borda score.
+    pub(crate) fn borda_order(&self) -> Vec<usize> {
+        let n = self.len();
+        let scores: Vec<f64> = (0..n)
+            .map(|a| (0..n).map(|b| self.pref(a, b)).sum())
+            .collect();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        order
+    }
+}
+
+/// Builds the pairwise preference matrix of several rankings.
+///
+/// Candidates are matched across rankings by `id_fn`, and a pair tied within a ranking is
+/// counted according to `ties`.
+///
+/// # Examples
+///
+/// ```
+/// use librank::aggregate::{TieCounting, preference_matrix};
+/// use librank::{Rank, Ranking};
+///
+/// let ballot = |ids: &[char]| -> Ranking<char> {
+///     ids.iter().enumerate().map(|(i, &c)| (Rank(i + 1), c)).collect()
+/// };
+/// let rankings = vec![ballot(&['a', 'b', 'c']), ballot(&['b', 'a', 'c']), ballot(&['a', 'c', 'b'])];
+///
+/// let matrix = preference_matrix(&rankings, |&c| c, TieCounting::Half);
+/// assert_eq!(matrix.wins(&'a', &'b'), 2.0);
+/// assert_eq!(matrix.condorcet_winner(), Some(&'a'));
+/// assert!(!matrix.has_cycle());
+/// ```
+pub fn preference_matrix<T, Id, F>(
+    rankings: &[Ranking<T>],
+    id_fn: F,
+    ties: TieCounting,
+) -> PreferenceMatrix<Id>
+where
+    Id: Hash + Eq + Clone,
+    F: FnMut(&T) -> Id,
+{
+    PreferenceMatrix::build(rankings, id_fn, ties)
+}
+
+/// The hard upper bound on the number of candidates searched exactly.
+const EXACT_LIMIT: usize = 16;
+
+/// The heuristic used by [`kemeny_aggregate`] when there are too many candidates for an
+/// exact search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KemenyHeuristic {
+    /// Orders the candidates by their Borda score, the total number of pairwise
+    /// preferences they win.
+    Borda,
+    /// Starts from the best of the Borda order and every input ranking, then repeatedly
+    /// moves single candidates to the position that lowers the distance most, until no
+    /// move helps. The result is never worse than any of the input rankings.
+    LocalSearch,
+}
+
+/// Configuration for [`kemeny_aggregate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KemenyConfig {
+    /// The largest number of candidates for which the exact optimum is searched.
+    ///
+    /// The exact search takes O(2^n · n²) time and O(2^n) memory, so values above 16
+    /// are treated as 16.
+    pub max_exact: usize,
+    /// The heuristic used beyond `max_exact` candidates.
+    pub heuristic: KemenyHeuristic,
+}
+
+impl Default for KemenyConfig {
+    fn default() -> Self {
+        KemenyConfig {
+            max_exact: 12,
+            heuristic: KemenyHeuristic::LocalSearch,
+        }
+    }
+}
+
+/// The result of [`kemeny_aggregate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct KemenyConsensus<Id> {
+    /// The consensus ranking of the candidate ids, without ties.
+    pub ranking: Ranking<Id>,
+    /// The total number of pairwise disagreements between the consensus and the inputs.
+    pub distance: f64,
+}
+
+/// Computes a Kemeny-Young consensus of several rankings.
+///
+/// The consensus is a strict ordering of every candidate that appears in any input,
+/// chosen to minimize the total number of pairwise disagreements with the inputs (the
+/// Kemeny distance). A pair tied within an input ranking counts as half a disagreement
+/// whichever way the consensus orders it, and a pair with a candidate missing from an
+/// input ranking doesn't count for that input.
+///
+/// With at most `config.max_exact` candidates the optimum is found exactly; beyond that
+/// `config.heuristic` is used. Among equally good orderings, candidates first seen
+/// earlier in the inputs are placed first.
+///
+/// # Examples
+///
+/// ```
+/// use librank::aggregate::{KemenyConfig, kemeny_aggregate};
+/// use librank::{RankedExt, Ranking};
+///
+/// let rankings: Vec<Ranking<char>> = ["abc", "abc", "bca"]
+///     .iter()
+///     .map(|s| s.chars().enumerate().rank_by(|&(i, _)| i).map(|(r, (_, c))| (r, c)).collect())
+///     .collect();
+///
+/// let consensus = kemeny_aggregate(&rankings, |&c| c, KemenyConfig::default());
+/// assert_eq!(consensus.ranking.into_items(), vec!['a', 'b', 'c']);
+/// assert_eq!(consensus.distance, 2.0);
+/// ```
+pub fn kemeny_aggregate<T, Id, F>(
+    rankings: &[Ranking<T>],
+    mut id_fn: F,
+    config: KemenyConfig,
+) -> KemenyConsensus<Id>
+where
+    Id: Hash + Eq + Clone,
+    F: FnMut(&T) -> Id,
+{
+    let matrix = PreferenceMatrix::build(rankings, &mut id_fn, TieCounting::Half);
+    let order = if matrix.len() <= config.max_exact.min(EXACT_LIMIT) {
+        exact_order(&matrix)
+    } else {
+        match config.heuristic {
+            KemenyHeuristic::Borda => matrix.borda_order(),
+            KemenyHeuristic::LocalSearch => {
+                let starts = rankings.iter().map(|ranking| {
+                    input_order(&matrix, ranking.iter().map(|(_, item)| id_fn(item)))
+                });
+                local_search(&matrix, starts)
+            }
+        }
+    };
+
+    let distance = matrix.disagreement(&order);
+    let starts = (0..order.len()).collect();
+    let ids = order
+        .into_iter()
+        .map(|i| matrix.candidates[i].clone())
+        .collect();
+    KemenyConsensus {
+        ranking: Ranking::from_parts(ids, starts),
+        distance,
+    }
+}
+
+/// Finds an optimal order by dynamic programming over subsets of placed candidates.
+fn exact_order<Id>(matrix: &PreferenceMatrix<Id>) -> Vec<usize>
+where
+    Id: Hash + Eq + Clone,
+{
+    let n = matrix.len();
+    let full = (1usize << n) - 1;
+    let mut best = vec![f64::INFINITY; full + 1];
+    let mut last = vec![0; full + 1];
+    best[0] = 0.0;
+    for set in 0..full {
+        if best[set].is_infinite() {
+            continue;
+        }
+        for c in (0..n).filter(|&c| set & (1 << c) == 0) {
+            let cost: f64 = (0..n)
+                .filter(|&a| set & (1 << a) != 0)
+                .map(|a| matrix.pref(c, a))
+                .sum();
+            let next = set | (1 << c);
+            if best[set] + cost < best[next] {
+                best[next] = best[set] + cost;
+                last[next] = c;
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut set = full;
+    while set != 0 {
+        order.push(last[set]);
+        set &= !(1 << last[set]);
+    }
+    order.reverse();
+    order
+}
+
+/// Linearizes an input ranking into candidate indices, appending the candidates it
+/// doesn't mention in Borda order.
+fn input_order<Id>(matrix: &PreferenceMatrix<Id>, ids: impl Iterator<Item = Id>) -> Vec<usize>
+where
+    Id: Hash + Eq + Clone,
+{
+    let mut seen = vec![false; matrix.len()];
+    let mut order = Vec::with_capacity(matrix.len());
+    for id in ids {
+        let i = matrix.index[&id];
+        if !seen[i] {
+            seen[i] = true;
+            order.push(i);
+        }
+    }
+    order.extend(matrix.borda_order().into_iter().filter(|&i| !seen[i]));
+    order
+}
+
+/// Improves the best of the starting orders by single-candidate moves.
+fn local_search<Id>(
+    matrix: &PreferenceMatrix<Id>,
+    starts: impl Iterator<Item = Vec<usize>>,
+) -> Vec<usize>
+where
+    Id: Hash + Eq + Clone,
+{
+    let mut order = matrix.borda_order();
+    let mut distance = matrix.disagreement(&order);
+    for start in starts {
+        let d = matrix.disagreement(&start);
+        if d < distance {
+            order = start;
+            distance = d;
+        }
+    }
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..order.len() {
+            let x = order[i];
+            let (mut best_delta, mut best_j) = (0.0, i);
+            let mut delta = 0.0;
+            for j in (0..i).rev() {
+                delta += matrix.pref(order[j], x) - matrix.pref(x, order[j]);
+                if delta < best_delta {
+                    (best_delta, best_j) = (delta, j);
+                }
+            }
+            delta = 0.0;
+            for (j, &y) in order.iter().enumerate().skip(i + 1) {
+                delta += matrix.pref(x, y) - matrix.pref(y, x);
+                if delta < best_delta {
+                    (best_delta, best_j) = (delta, j);
+                }
+            }
+            if best_j != i {
+                let x = order.remove(i);
+                order.insert(best_j, x);
+                improved = true;
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+    use crate::test_support::lcg;
+
+    fn ranking_of(ids: &[u32]) -> Ranking<u32> {
+        ids.iter()
+            .copied()
+            .enumerate()
+            .rank_by(|&(i, _)| i)
+            .map(|(r, (_, id))| (r, id))
+            .collect()
+    }
+
+    /// Counts disagreements of a strict order with strict input orders pair by pair.
+    fn brute_force_distance(inputs: &[Vec<u32>], order: &[u32]) -> f64 {
+        let pos = |v: &[u32], x: u32| v.iter().position(|&y| y == x).unwrap();
+        let mut total = 0.0;
+        for input in inputs {
+            for (i, &a) in order.iter().enumerate() {
+                for &b in &order[i + 1..] {
+                    if pos(input, a) > pos(input, b) {
+                        total += 1.0;
+                    }
+                }
+            }
+        }
+        total
+    }
+
+    fn permutations(items: &[u32]) -> Vec<Vec<u32>> {
+        if items.len() <= 1 {
+            return vec![items.to_vec()];
+        }
+        let mut result = Vec::new();
+        for i in 0..items.len() {
+            let mut rest = items.to_vec();
+            let first = rest.remove(i);
+            for mut perm in permutations(&rest) {
+                perm.insert(0, first);
+                result.push(perm);
+            }
+        }
+        result
+    }
+
+    fn lcg_shuffle(items: &mut [u32], states: &mut impl Iterator<Item = u64>) {
+        for i in (1..items.len()).rev() {
+            let j = (states.next().unwrap() >> 33) as usize % (i + 1);
+            items.swap(i, j);
+        }
+    }
+
+    #[test]
+    fn test_kemeny_three_candidates_known_optimum() {
+        let rankings = vec![
+            ranking_of(&[1, 2, 3]),
+            ranking_of(&[1, 2, 3]),
+            ranking_of(&[2, 3, 1]),
+        ];
+        let consensus = kemeny_aggregate(&rankings, |&id| id, KemenyConfig::default());
+        assert_eq!(consensus.ranking.into_items(), vec![1, 2, 3]);
+        assert_eq!(consensus.distance, 2.0);
+    }
+
+    #[test]
+    fn test_kemeny_exact_matches_brute_force() {
+        let mut states = lcg(7);
+        let mut inputs = Vec::new();
+        for _ in 0..5 {
+            let mut ids: Vec<u32> = (0..6).collect();
+            lcg_shuffle(&mut ids, &mut states);
+            inputs.push(ids);
+        }
+        let rankings: Vec<Ranking<u32>> = inputs.iter().map(|ids| ranking_of(ids)).collect();
+        let consensus = kemeny_aggregate(&rankings, |&id| id, KemenyConfig::default());
+
+        let optimum = permutations(&[0, 1, 2, 3, 4, 5])
+            .iter()
+            .map(|order| brute_force_distance(&inputs, order))
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(consensus.distance, optimum);
+        assert_eq!(
+            brute_force_distance(&inputs, consensus.ranking.items()),
+            consensus.distance
+        );
+    }
+
+    #[test]
+    fn test_kemeny_heuristic_no_worse_than_inputs() {
+        let mut states = lcg(42);
+        let mut inputs = Vec::new();
+        for _ in 0..7 {
+            let mut ids: Vec<u32> = (0..30).collect();
+            lcg_shuffle(&mut ids, &mut states);
+            inputs.push(ids);
+        }
+        let rankings: Vec<Ranking<u32>> = inputs.iter().map(|ids| ranking_of(ids)).collect();
+        let consensus = kemeny_aggregate(&rankings, |&id| id, KemenyConfig::default());
+
+        assert_eq!(consensus.ranking.len(), 30);
+        assert_eq!(
+            brute_force_distance(&inputs, consensus.ranking.items()),
+            consensus.distance
+        );
+        for input in &inputs {
+            assert!(consensus.distance <= brute_force_distance(&inputs, input));
+        }
+    }
+
+    #[test]
+    fn test_kemeny_ties_count_half() {
+        let tied: Ranking<u32> = vec![(Rank(1), 1), (Rank(1), 2)].into_iter().collect();
+        let consensus = kemeny_aggregate(&[tied], |&id| id, KemenyConfig::default());
+        assert_eq!(consensus.ranking.into_items(), vec![1, 2]);
+        assert_eq!(consensus.distance, 0.5);
+    }
+
+    #[test]
+    fn test_preference_matrix_rock_paper_scissors() {
+        let (rock, paper, scissors) = (0, 1, 2);
+        let rankings = vec![
+            ranking_of(&[rock, scissors, paper]),
+            ranking_of(&[scissors, paper, rock]),
+            ranking_of(&[paper, rock, scissors]),
+        ];
+        let matrix = preference_matrix(&rankings, |&id| id, TieCounting::Half);
+        assert_eq!(matrix.wins(&rock, &scissors), 2.0);
+        assert_eq!(matrix.wins(&scissors, &paper), 2.0);
+        assert_eq!(matrix.wins(&paper, &rock), 2.0);
+        assert_eq!(matrix.condorcet_winner(), None);
+        assert!(matrix.has_cycle());
+    }
+
+    #[test]
+    fn test_preference_matrix_condorcet_winner() {
+        let rankings = vec![
+            ranking_of(&[1, 2, 3]),
+            ranking_of(&[2, 1, 3]),
+            ranking_of(&[1, 3, 2]),
+        ];
+        let matrix = preference_matrix(&rankings, |&id| id, TieCounting::Half);
+        assert_eq!(matrix.candidates(), &[1, 2, 3]);
+        assert_eq!(matrix.condorcet_winner(), Some(&1));
+        assert!(!matrix.has_cycle());
+        assert_eq!(matrix.wins(&1, &4), 0.0);
+    }
+
+    #[test]
+    fn test_preference_matrix_tie_counting() {
+        let tied: Ranking<u32> = vec![(Rank(1), 1), (Rank(1), 2), (Rank(2), 3)]
+            .into_iter()
+            .collect();
+        let rankings = [tied];
+        let half = preference_matrix(&rankings, |&id| id, TieCounting::Half);
+        assert_eq!(half.wins(&1, &2), 0.5);
+        assert_eq!(half.wins(&2, &1), 0.5);
+        assert_eq!(half.wins(&1, &3), 1.0);
+        assert_eq!(half.condorcet_winner(), None);
+
+        let ignored = preference_matrix(&rankings, |&id| id, TieCounting::Ignore);
+        assert_eq!(ignored.wins(&1, &2), 0.0);
+        assert_eq!(ignored.wins(&2, &3), 1.0);
+    }
+}
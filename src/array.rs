@@ -0,0 +1,169 @@
+//! Ranking fixed-size arrays without allocating.
+//!
+//! The functions here sort in place with an insertion sort, which is quick for the small
+//! arrays they are meant for (a few dozen items) and needs no heap. Ties keep their
+//! input order and ranks are dense, as with [`RankedExt::rank_by`](crate::RankedExt::rank_by).
+
+use crate::rank::Rank;
+
+/// Ranks the items of an array by a key, returning them sorted and paired with their
+/// ranks.
+///
+/// `key_fn` is called once per item.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::array::rank_array_by;
+///
+/// let ranked = rank_array_by(["bb", "a", "cc"], |s| s.len());
+///
+/// assert_eq!(ranked, [(Rank(1), "a"), (Rank(2), "bb"), (Rank(2), "cc")]);
+/// ```
+pub fn rank_array_by<T, K, F, const N: usize>(arr: [T; N], mut key_fn: F) -> [(Rank, T); N]
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    let mut keyed = arr.map(|item| (key_fn(&item), item));
+    insertion_sort_by(&mut keyed, |a, b| a.0 > b.0);
+    let mut rank = Rank(0);
+    let mut prev_key = None;
+    keyed.map(|(key, item)| {
+        if prev_key.as_ref() != Some(&key) {
+            rank = Rank(rank.0 + 1);
+            prev_key = Some(key);
+        }
+        (rank, item)
+    })
+}
+
+/// Ranks the items of an array by a key, returning the rank of each item at its
+/// position in the input.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::array::rank_array_ref_by;
+///
+/// let ranks = rank_array_ref_by(&[30, 10, 30, 20], |&x| x);
+///
+/// assert_eq!(ranks, [Rank(3), Rank(1), Rank(3), Rank(2)]);
+/// ```
+pub fn rank_array_ref_by<T, K, F, const N: usize>(arr: &[T; N], key_fn: F) -> [Rank; N]
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    let keys = arr.each_ref().map(key_fn);
+    let mut order: [usize; N] = std::array::from_fn(|i| i);
+    insertion_sort_by(&mut order, |&a, &b| keys[a] > keys[b]);
+    let mut ranks = [Rank(0); N];
+    let mut rank = Rank(0);
+    for (i, &index) in order.iter().enumerate() {
+        if i == 0 || keys[index] != keys[order[i - 1]] {
+            rank = Rank(rank.0 + 1);
+        }
+        ranks[index] = rank;
+    }
+    ranks
+}
+
+/// Like [`rank_array_ref_by`] for integer keys, but usable in constant expressions, for
+/// example to build lookup tables at compile time.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::array::rank_array_const;
+///
+/// const RANKS: [Rank; 4] = rank_array_const([30, -10, 30, 20]);
+///
+/// assert_eq!(RANKS, [Rank(3), Rank(1), Rank(3), Rank(2)]);
+/// ```
+pub const fn rank_array_const<const N: usize>(keys: [i64; N]) -> [Rank; N] {
+    let mut sorted = keys;
+    let mut i = 1;
+    while i < N {
+        let mut j = i;
+        while j > 0 && sorted[j - 1] > sorted[j] {
+            let tmp = sorted[j - 1];
+            sorted[j - 1] = sorted[j];
+            sorted[j] = tmp;
+            j -= 1;
+        }
+        i += 1;
+    }
+    // Deduplicate, so that the rank of a key is its index among the distinct keys.
+    let mut distinct = 0;
+    let mut i = 0;
+    while i < N {
+        if i == 0 || sorted[i] != sorted[distinct - 1] {
+            sorted[distinct] = sorted[i];
+            distinct += 1;
+        }
+        i += 1;
+    }
+    let mut ranks = [Rank(0); N];
+    let mut i = 0;
+    while i < N {
+        let mut j = 0;
+        while sorted[j] != keys[i] {
+            j += 1;
+        }
+        ranks[i] = Rank(j + 1);
+        i += 1;
+    }
+    ranks
+}
+
+/// A stable insertion sort, where `greater(a, b)` tells whether `a` goes after `b`.
+fn insertion_sort_by<T>(items: &mut [T], mut greater: impl FnMut(&T, &T) -> bool) {
+    for i in 1..items.len() {
+        let mut j = i;
+        while j > 0 && greater(&items[j - 1], &items[j]) {
+            items.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+    use crate::test_support::lcg;
+
+    /// Pseudo-random keys from a linear congruential generator.
+    fn keys<const N: usize>(seed: u64) -> [i64; N] {
+        let mut states = lcg(seed);
+        std::array::from_fn(|_| (states.next().unwrap() >> 33) as i64 % 5)
+    }
+
+    fn check<const N: usize>(seed: u64) {
+        let keys: [i64; N] = keys(seed);
+        let items: [(usize, i64); N] = std::array::from_fn(|i| (i, keys[i]));
+        let expected: Vec<(Rank, (usize, i64))> = items.into_iter().rank_by(|&(_, k)| k).collect();
+        assert_eq!(rank_array_by(items, |&(_, k)| k).to_vec(), expected);
+
+        let mut aligned = [Rank(0); N];
+        for &(rank, (i, _)) in &expected {
+            aligned[i] = rank;
+        }
+        assert_eq!(rank_array_ref_by(&items, |&(_, k)| k), aligned);
+        assert_eq!(rank_array_const(keys), aligned);
+    }
+
+    #[test]
+    fn test_rank_array_matches_rank_by() {
+        check::<0>(1);
+        check::<1>(2);
+        check::<2>(3);
+        check::<7>(4);
+        check::<16>(5);
+        check::<32>(6);
+    }
+}
@@ -0,0 +1,213 @@
+//! Ranking the values of an Apache Arrow array.
+
+use std::cmp::Ordering;
+
+use arrow_array::types::ArrowPrimitiveType;
+use arrow_array::{
+    Array, ArrowNativeTypeOp, PrimitiveArray, UInt64Array, downcast_primitive_array,
+};
+use arrow_schema::ArrowError;
+
+use crate::rank::{RankingStrategy, strategy_ranks};
+
+/// How null values are ranked by [`rank_array`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullRanking {
+    /// Null values get a null rank.
+    #[default]
+    Null,
+    /// Null values are tied with each other after all other values, in either
+    /// direction.
+    Last,
+}
+
+/// The options of [`rank_array`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RankArrayOptions {
+    /// Whether the largest value gets the first rank.
+    pub descending: bool,
+    /// How tied values are numbered. [`RankingStrategy::Fractional`] is not supported,
+    /// since the ranks are integers.
+    pub strategy: RankingStrategy,
+    /// How null values are ranked.
+    pub nulls: NullRanking,
+}
+
+/// Ranks the values of a primitive array, returning the ranks aligned with the input.
+///
+/// Any primitive type is supported: integers, floats, decimals, dates and times. Floats
+/// are compared by their IEEE 754 total order, so `-0.0` ranks before `0.0` and NaN
+/// after infinity. Tied values that need distinct ranks, as with
+/// [`RankingStrategy::Ordinal`], are numbered by their position in the array.
+///
+/// # Errors
+///
+/// Returns [`ArrowError::NotYetImplemented`] for non-primitive arrays and
+/// [`ArrowError::InvalidArgumentError`] for [`RankingStrategy::Fractional`].
+///
+/// # Examples
+///
+/// ```
+/// use arrow_array::{Int64Array, UInt64Array};
+/// use librank::arrow::{RankArrayOptions, rank_array};
+///
+/// let values = Int64Array::from(vec![Some(30), None, Some(10), Some(30)]);
+/// let ranks = rank_array(&values, &RankArrayOptions::default()).unwrap();
+///
+/// assert_eq!(ranks, UInt64Array::from(vec![Some(2), None, Some(1), Some(2)]));
+/// ```
+pub fn rank_array(
+    array: &dyn Array,
+    options: &RankArrayOptions,
+) -> Result<UInt64Array, ArrowError> {
+    if options.strategy == RankingStrategy::Fractional {
+        return Err(ArrowError::InvalidArgumentError(
+            "fractional ranks can't be stored in a UInt64Array".to_string(),
+        ));
+    }
+    downcast_primitive_array!(
+        array => Ok(rank_primitive(array, options)),
+        t => Err(ArrowError::NotYetImplemented(format!("ranking arrays of type {t}")))
+    )
+}
+
+fn rank_primitive<T: ArrowPrimitiveType>(
+    array: &PrimitiveArray<T>,
+    options: &RankArrayOptions,
+) -> UInt64Array {
+    let values = array.values();
+    let compare = |a: &usize, b: &usize| {
+        let ordering = values[*a].compare(values[*b]);
+        if options.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+    let (mut order, nulls): (Vec<usize>, Vec<usize>) =
+        (0..array.len()).partition(|&i| array.is_valid(i));
+    order.sort_by(compare);
+    // Nulls ranked last form one more tie group after the valid values.
+    if options.nulls == NullRanking::Last {
+        order.extend(nulls);
+    }
+    let tied = |a: &usize, b: &usize| match (array.is_valid(*a), array.is_valid(*b)) {
+        (true, true) => compare(a, b) == Ordering::Equal,
+        (a_valid, b_valid) => a_valid == b_valid,
+    };
+
+    let mut ranks = vec![None; array.len()];
+    for (rank, i) in strategy_ranks(&order, options.strategy, tied) {
+        ranks[order[i]] = Some(rank as u64);
+    }
+    UInt64Array::from(ranks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Reverse;
+
+    use arrow_array::{Float64Array, Int64Array, StringArray};
+
+    use super::*;
+    use crate::RankedExt;
+
+    /// Ranks the valid values with the iterator API and scatters the ranks back.
+    fn expected_ranks<V: Copy, K: Ord>(values: &[Option<V>], key: impl Fn(V) -> K) -> UInt64Array {
+        let mut ranks = vec![None; values.len()];
+        let valid = values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| v.map(|v| (i, v)));
+        for (rank, (i, _)) in valid.rank_by(|&(_, v)| key(v)) {
+            ranks[i] = Some(rank.0 as u64);
+        }
+        UInt64Array::from(ranks)
+    }
+
+    fn float_key(v: f64) -> impl Ord {
+        struct TotalOrder(f64);
+        impl PartialEq for TotalOrder {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.total_cmp(&other.0).is_eq()
+            }
+        }
+        impl Eq for TotalOrder {}
+        impl PartialOrd for TotalOrder {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for TotalOrder {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+        TotalOrder(v)
+    }
+
+    #[test]
+    fn test_rank_array_int64_matches_rank_by() {
+        let values = vec![Some(5), None, Some(-2), Some(5), Some(9), None, Some(-2)];
+        let array = Int64Array::from(values.clone());
+        let ranks = rank_array(&array, &RankArrayOptions::default()).unwrap();
+        assert_eq!(ranks, expected_ranks(&values, |v| v));
+
+        let descending = RankArrayOptions {
+            descending: true,
+            ..Default::default()
+        };
+        let ranks = rank_array(&array, &descending).unwrap();
+        assert_eq!(ranks, expected_ranks(&values, Reverse));
+    }
+
+    #[test]
+    fn test_rank_array_float64_matches_rank_by() {
+        let values = vec![
+            Some(1.5),
+            Some(f64::NAN),
+            None,
+            Some(-0.5),
+            Some(1.5),
+            Some(f64::INFINITY),
+        ];
+        let array = Float64Array::from(values.clone());
+        let ranks = rank_array(&array, &RankArrayOptions::default()).unwrap();
+        assert_eq!(ranks, expected_ranks(&values, float_key));
+        assert_eq!(
+            ranks,
+            UInt64Array::from(vec![Some(2), Some(4), None, Some(1), Some(2), Some(3)])
+        );
+    }
+
+    #[test]
+    fn test_rank_array_competition_with_nulls_last() {
+        let array = Int64Array::from(vec![None, Some(7), Some(3), Some(7), None, Some(1)]);
+        let options = RankArrayOptions {
+            descending: true,
+            strategy: RankingStrategy::Competition,
+            nulls: NullRanking::Last,
+        };
+        let ranks = rank_array(&array, &options).unwrap();
+        let expected = UInt64Array::from(vec![5, 1, 3, 1, 5, 4]);
+        assert_eq!(ranks, expected);
+    }
+
+    #[test]
+    fn test_rank_array_rejects_unsupported_input() {
+        let strings = StringArray::from(vec!["a", "b"]);
+        assert!(matches!(
+            rank_array(&strings, &RankArrayOptions::default()),
+            Err(ArrowError::NotYetImplemented(_))
+        ));
+        let fractional = RankArrayOptions {
+            strategy: RankingStrategy::Fractional,
+            ..Default::default()
+        };
+        let ints = Int64Array::from(vec![1, 2]);
+        assert!(matches!(
+            rank_array(&ints, &fractional),
+            Err(ArrowError::InvalidArgumentError(_))
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+//! Ranking the same items several times without recomputing their keys.
+
+use std::cmp::Reverse;
+
+use crate::rank::{Rank, RankedBy};
+
+/// The keys of a slice of items, computed once and reused by every ranking.
+///
+/// The key of each item is stored at the item's index, so expensive key functions run
+/// once per item however many times the items are ranked. Each ranking only sorts the
+/// indices. Ties keep their input order, as with [`rank_by`](crate::RankedExt::rank_by).
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::cache::RankingKeyCache;
+///
+/// let words = ["kiwi", "fig", "banana", "plum"];
+/// let cache = RankingKeyCache::new(&words, |w| w.len());
+///
+/// let shortest: Vec<(Rank, &&str)> = cache.rank().take(2).collect();
+/// let longest: Vec<(Rank, &&str)> = cache.rank_desc().take(2).collect();
+///
+/// assert_eq!(shortest, vec![(Rank(1), &"fig"), (Rank(2), &"kiwi")]);
+/// assert_eq!(longest, vec![(Rank(1), &"banana"), (Rank(2), &"kiwi")]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RankingKeyCache<'a, T, K> {
+    items: &'a [T],
+    keys: Vec<K>,
+}
+
+impl<'a, T, K> RankingKeyCache<'a, T, K>
+where
+    K: Ord,
+{
+    /// Computes the key of every item, calling `key_fn` once per item.
+    pub fn new<F>(items: &'a [T], key_fn: F) -> Self
+    where
+        F: FnMut(&T) -> K,
+    {
+        RankingKeyCache {
+            items,
+            keys: items.iter().map(key_fn).collect(),
+        }
+    }
+
+    /// Returns the items, in their original order.
+    pub fn items(&self) -> &'a [T] {
+        self.items
+    }
+
+    /// Returns the cached keys, aligned with [`items`](RankingKeyCache::items).
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Ranks the items by their cached keys, smallest first.
+    pub fn rank(&self) -> impl Iterator<Item = (Rank, &'a T)> {
+        self.rank_indices_by(|key| key)
+    }
+
+    /// Ranks the items by their cached keys, largest first.
+    pub fn rank_desc(&self) -> impl Iterator<Item = (Rank, &'a T)> {
+        self.rank_indices_by(Reverse)
+    }
+
+    /// Ranks the items by a key derived from each cached key, smallest first.
+    ///
+    /// This suits rankings by a part of an expensive key, like one field of a computed
+    /// tuple. `f` is called a few times per item, so it should be cheap.
+    pub fn rank_with<F, K2>(&self, f: F) -> impl Iterator<Item = (Rank, &'a T)>
+    where
+        F: FnMut(&K) -> K2,
+        K2: Ord,
+    {
+        self.rank_indices_by(f)
+    }
+
+    fn rank_indices_by<'s, F, K2>(&'s self, mut f: F) -> impl Iterator<Item = (Rank, &'a T)>
+    where
+        F: FnMut(&'s K) -> K2,
+        K2: Ord,
+    {
+        let keys = &self.keys;
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by_key(|&i| f(&keys[i]));
+        RankedBy::new(order.into_iter(), move |&i| f(&keys[i]))
+            .map(|(rank, i)| (rank, &self.items[i]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::RankedExt;
+
+    #[test]
+    fn test_cached_rankings_match_uncached() {
+        let data: Vec<(u32, &str)> = vec![(3, "c"), (1, "a"), (3, "d"), (2, "b"), (1, "e")];
+        let calls = Cell::new(0);
+        let cache = RankingKeyCache::new(&data, |&(score, name)| {
+            calls.set(calls.get() + 1);
+            (score, name.len())
+        });
+
+        for _ in 0..3 {
+            let cached: Vec<(Rank, &(u32, &str))> = cache.rank().collect();
+            let uncached: Vec<(Rank, &(u32, &str))> = data
+                .iter()
+                .rank_by(|&&(score, name)| (score, name.len()))
+                .collect();
+            assert_eq!(cached, uncached);
+
+            let cached: Vec<(Rank, &(u32, &str))> = cache.rank_desc().collect();
+            let uncached: Vec<(Rank, &(u32, &str))> = data
+                .iter()
+                .rank_by_desc(|&&(score, name)| (score, name.len()))
+                .collect();
+            assert_eq!(cached, uncached);
+
+            let cached: Vec<(Rank, &(u32, &str))> = cache.rank_with(|&(_, len)| len).collect();
+            let uncached: Vec<(Rank, &(u32, &str))> =
+                data.iter().rank_by(|&&(_, name)| name.len()).collect();
+            assert_eq!(cached, uncached);
+        }
+        assert_eq!(calls.get(), data.len());
+        assert_eq!(cache.keys()[2], (3, 1));
+    }
+}
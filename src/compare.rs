@@ -0,0 +1,406 @@
+//! Comparisons between two rankings of overlapping item sets.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::rank::Rank;
+use crate::ranking::Ranking;
+
+/// The result of [`inversions_between`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Inversions {
+    /// The number of pairs of common items ranked in opposite order by the two rankings.
+    pub inversions: u64,
+    /// The number of items present in both rankings.
+    pub common: usize,
+    /// The number of items present only in the old ranking.
+    pub only_in_old: usize,
+    /// The number of items present only in the new ranking.
+    pub only_in_new: usize,
+}
+
+/// Counts the pairwise inversions between two rankings of the same items.
+///
+/// Items are matched by `id_fn`; items present in only one ranking are left out and
+/// counted separately. A pair is an inversion when one ranking puts `a` strictly above
+/// `b` and the other puts `b` strictly above `a`; pairs tied in either ranking are not
+/// inversions. This is the numerator of the Kendall tau distance.
+///
+/// The count uses a merge sort and runs in O(n log n). If an id appears more than once
+/// in a ranking, its first occurrence is used.
+///
+/// # Examples
+///
+/// ```
+/// use librank::compare::inversions_between;
+/// use librank::{Rank, Ranking};
+///
+/// let ranking = |ids: &[char]| -> Ranking<char> {
+///     ids.iter().enumerate().map(|(i, &c)| (Rank(i + 1), c)).collect()
+/// };
+/// let old = ranking(&['a', 'b', 'c', 'd']);
+/// let new = ranking(&['b', 'a', 'd', 'e']);
+///
+/// let result = inversions_between(&old, &new, |&c| c);
+/// assert_eq!(result.inversions, 1);
+/// assert_eq!((result.only_in_old, result.only_in_new), (1, 1));
+/// ```
+pub fn inversions_between<T, Id, F>(old: &Ranking<T>, new: &Ranking<T>, mut id_fn: F) -> Inversions
+where
+    Id: Hash + Eq,
+    F: FnMut(&T) -> Id,
+{
+    let mut old_ranks: HashMap<Id, Rank> = HashMap::with_capacity(old.len());
+    for (rank, item) in old.iter() {
+        old_ranks.entry(id_fn(item)).or_insert(rank);
+    }
+    let old_distinct = old_ranks.len();
+
+    let mut new_distinct = 0;
+    let mut pairs: Vec<(Rank, Rank)> = Vec::with_capacity(new.len().min(old_distinct));
+    let mut seen = HashSet::with_capacity(new.len());
+    for (rank, item) in new.iter() {
+        let id = id_fn(item);
+        let old_rank = old_ranks.get(&id).copied();
+        if !seen.insert(id) {
+            continue;
+        }
+        match old_rank {
+            Some(old_rank) => pairs.push((rank, old_rank)),
+            None => new_distinct += 1,
+        }
+    }
+
+    // Sorting ties in the new ranking by their old rank keeps them from being counted.
+    pairs.sort_unstable();
+    let mut old_order: Vec<Rank> = pairs.iter().map(|&(_, old_rank)| old_rank).collect();
+    let mut buffer = old_order.clone();
+    let inversions = count_inversions(&mut old_order, &mut buffer);
+
+    Inversions {
+        inversions,
+        common: pairs.len(),
+        only_in_old: old_distinct - pairs.len(),
+        only_in_new: new_distinct,
+    }
+}
+
+/// Which of two rankings an item of [`symmetric_difference`] comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// The item is only in the first ranking.
+    First,
+    /// The item is only in the second ranking.
+    Second,
+}
+
+/// Returns the items present in both rankings, with their rank in each, in the first
+/// ranking's order.
+///
+/// Items are matched by `id_fn` and the item returned is the one from `first`. If an id
+/// appears more than once in a ranking, its first occurrence is used and the others are
+/// ignored, as in the other functions of this module.
+///
+/// # Examples
+///
+/// ```
+/// use librank::compare::{common, only_in_first, only_in_second};
+/// use librank::{Rank, RankedExt, Ranking};
+///
+/// let last_week: Ranking<&str> = vec!["ann", "bob", "cy"].into_iter().rank_by(|&n| n).collect();
+/// let this_week: Ranking<&str> = vec!["bob", "dee", "cy"].into_iter().rank_by(|&n| n).collect();
+///
+/// let stayed: Vec<(Rank, Rank, &str)> = common(&this_week, &last_week, |&n| n)
+///     .into_iter()
+///     .map(|(now, before, &name)| (now, before, name))
+///     .collect();
+/// assert_eq!(stayed, vec![(Rank(1), Rank(2), "bob"), (Rank(2), Rank(3), "cy")]);
+///
+/// let new_entries: Vec<&str> = only_in_first(&this_week, &last_week, |&n| n)
+///     .into_iter()
+///     .map(|(_, &name)| name)
+///     .collect();
+/// let dropped_out: Vec<&str> = only_in_second(&this_week, &last_week, |&n| n)
+///     .into_iter()
+///     .map(|(_, &name)| name)
+///     .collect();
+/// assert_eq!(new_entries, vec!["dee"]);
+/// assert_eq!(dropped_out, vec!["ann"]);
+/// ```
+pub fn common<'a, T, Id, F>(
+    first: &'a Ranking<T>,
+    second: &Ranking<T>,
+    mut id_fn: F,
+) -> Vec<(Rank, Rank, &'a T)>
+where
+    Id: Hash + Eq,
+    F: FnMut(&T) -> Id,
+{
+    let (_, second_ranks) = first_occurrences(second, &mut id_fn);
+    let (firsts, _) = first_occurrences(first, &mut id_fn);
+    firsts
+        .into_iter()
+        .filter_map(|(rank, item)| {
+            let &other = second_ranks.get(&id_fn(item))?;
+            Some((rank, other, item))
+        })
+        .collect()
+}
+
+/// Returns the items of `first` whose id is not in `second`, in rank order.
+///
+/// Duplicate ids are handled as in [`common`].
+pub fn only_in_first<'a, T, Id, F>(
+    first: &'a Ranking<T>,
+    second: &Ranking<T>,
+    mut id_fn: F,
+) -> Vec<(Rank, &'a T)>
+where
+    Id: Hash + Eq,
+    F: FnMut(&T) -> Id,
+{
+    let (_, second_ranks) = first_occurrences(second, &mut id_fn);
+    let (firsts, _) = first_occurrences(first, &mut id_fn);
+    firsts
+        .into_iter()
+        .filter(|&(_, item)| !second_ranks.contains_key(&id_fn(item)))
+        .collect()
+}
+
+/// Returns the items of `second` whose id is not in `first`, in the rank order of
+/// `second`.
+///
+/// Duplicate ids are handled as in [`common`].
+pub fn only_in_second<'a, T, Id, F>(
+    first: &Ranking<T>,
+    second: &'a Ranking<T>,
+    id_fn: F,
+) -> Vec<(Rank, &'a T)>
+where
+    Id: Hash + Eq,
+    F: FnMut(&T) -> Id,
+{
+    only_in_first(second, first, id_fn)
+}
+
+/// Returns the items present in only one of the rankings, each with the ranking it
+/// comes from and its rank there.
+///
+/// The items of [`only_in_first`] come first, in the first ranking's order, followed by
+/// those of [`only_in_second`], in the second ranking's order. Duplicate ids are handled
+/// as in [`common`].
+pub fn symmetric_difference<'a, T, Id, F>(
+    first: &'a Ranking<T>,
+    second: &'a Ranking<T>,
+    mut id_fn: F,
+) -> Vec<(Side, Rank, &'a T)>
+where
+    Id: Hash + Eq,
+    F: FnMut(&T) -> Id,
+{
+    let mut difference: Vec<(Side, Rank, &'a T)> = only_in_first(first, second, &mut id_fn)
+        .into_iter()
+        .map(|(rank, item)| (Side::First, rank, item))
+        .collect();
+    difference.extend(
+        only_in_second(first, second, &mut id_fn)
+            .into_iter()
+            .map(|(rank, item)| (Side::Second, rank, item)),
+    );
+    difference
+}
+
+/// Returns the first occurrence of every id in a ranking, in rank order, along with
+/// the rank of each id.
+fn first_occurrences<'a, T, Id, F>(
+    ranking: &'a Ranking<T>,
+    id_fn: &mut F,
+) -> (Vec<(Rank, &'a T)>, HashMap<Id, Rank>)
+where
+    Id: Hash + Eq,
+    F: FnMut(&T) -> Id,
+{
+    let mut firsts = Vec::with_capacity(ranking.len());
+    let mut ranks = HashMap::with_capacity(ranking.len());
+    for (rank, item) in ranking.iter() {
+        if let Entry::Vacant(entry) = ranks.entry(id_fn(item)) {
+            entry.insert(rank);
+            firsts.push((rank, item));
+        }
+    }
+    (firsts, ranks)
+}
+
+/// Sorts `v` and returns the number of pairs `i < j` with `v[i] > v[j]`.
+fn count_inversions<K: Ord + Copy>(v: &mut [K], buffer: &mut [K]) -> u64 {
+    let n = v.len();
+    if n < 2 {
+        return 0;
+    }
+    let mid = n / 2;
+    let mut count = count_inversions(&mut v[..mid], &mut buffer[..mid])
+        + count_inversions(&mut v[mid..], &mut buffer[mid..]);
+    let (mut i, mut j) = (0, mid);
+    for slot in buffer.iter_mut().take(n) {
+        if j == n || (i < mid && v[i] <= v[j]) {
+            *slot = v[i];
+            i += 1;
+        } else {
+            *slot = v[j];
+            count += (mid - i) as u64;
+            j += 1;
+        }
+    }
+    v.copy_from_slice(&buffer[..n]);
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lcg;
+
+    fn ranking_of(ranks: &[(usize, u32)]) -> Ranking<u32> {
+        ranks.iter().map(|&(r, id)| (Rank(r), id)).collect()
+    }
+
+    fn brute_force(old: &Ranking<u32>, new: &Ranking<u32>) -> u64 {
+        let old: HashMap<u32, Rank> = old.iter().map(|(r, &id)| (id, r)).collect();
+        let new: Vec<(u32, Rank)> = new.iter().map(|(r, &id)| (id, r)).collect();
+        let mut count = 0;
+        for (i, &(a, ra)) in new.iter().enumerate() {
+            for &(b, rb) in &new[i + 1..] {
+                if let (Some(&oa), Some(&ob)) = (old.get(&a), old.get(&b))
+                    && ((ra < rb && oa > ob) || (ra > rb && oa < ob))
+                {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_inversions_between_reversed() {
+        let old = ranking_of(&[(1, 1), (2, 2), (3, 3), (4, 4)]);
+        let new = ranking_of(&[(1, 4), (2, 3), (3, 2), (4, 1)]);
+        let result = inversions_between(&old, &new, |&id| id);
+        assert_eq!(result.inversions, 6);
+        assert_eq!(result.common, 4);
+    }
+
+    #[test]
+    fn test_inversions_between_ties_are_not_inversions() {
+        let old = ranking_of(&[(1, 1), (1, 2), (2, 3)]);
+        let new = ranking_of(&[(1, 2), (2, 1), (2, 3)]);
+        let result = inversions_between(&old, &new, |&id| id);
+        assert_eq!(result.inversions, 0);
+    }
+
+    #[test]
+    fn test_inversions_between_reports_unmatched_items() {
+        let old = ranking_of(&[(1, 1), (2, 2), (3, 9)]);
+        let new = ranking_of(&[(1, 2), (2, 7), (2, 8), (3, 1)]);
+        let result = inversions_between(&old, &new, |&id| id);
+        assert_eq!(result.inversions, 1);
+        assert_eq!(result.common, 2);
+        assert_eq!(result.only_in_old, 1);
+        assert_eq!(result.only_in_new, 2);
+    }
+
+    #[test]
+    fn test_inversions_between_matches_brute_force() {
+        let mut states = lcg(1);
+        let mut next = move |bound: u64| (states.next().unwrap() >> 33) % bound;
+        for _ in 0..50 {
+            let mut random_ranking = || {
+                let mut entries: Vec<(usize, u32)> = Vec::new();
+                for id in 0..20 {
+                    if next(4) != 0 {
+                        entries.push((next(8) as usize + 1, id));
+                    }
+                }
+                entries.sort();
+                ranking_of(&entries)
+            };
+            let (old, new) = (random_ranking(), random_ranking());
+            let result = inversions_between(&old, &new, |&id| id);
+            assert_eq!(result.inversions, brute_force(&old, &new));
+        }
+    }
+
+    fn ids<T: Copy>(items: Vec<(Rank, &T)>) -> Vec<(usize, T)> {
+        items.into_iter().map(|(rank, &id)| (rank.0, id)).collect()
+    }
+
+    #[test]
+    fn test_set_operations_with_partial_overlap() {
+        let first = ranking_of(&[(1, 10), (2, 20), (2, 30), (3, 40)]);
+        let second = ranking_of(&[(1, 40), (2, 50), (3, 10), (4, 60)]);
+
+        let both: Vec<(usize, usize, u32)> = common(&first, &second, |&id| id)
+            .into_iter()
+            .map(|(a, b, &id)| (a.0, b.0, id))
+            .collect();
+        assert_eq!(both, vec![(1, 3, 10), (3, 1, 40)]);
+        assert_eq!(
+            ids(only_in_first(&first, &second, |&id| id)),
+            vec![(2, 20), (2, 30)]
+        );
+        assert_eq!(
+            ids(only_in_second(&first, &second, |&id| id)),
+            vec![(2, 50), (4, 60)]
+        );
+
+        let difference: Vec<(Side, usize, u32)> = symmetric_difference(&first, &second, |&id| id)
+            .into_iter()
+            .map(|(side, rank, &id)| (side, rank.0, id))
+            .collect();
+        assert_eq!(
+            difference,
+            vec![
+                (Side::First, 2, 20),
+                (Side::First, 2, 30),
+                (Side::Second, 2, 50),
+                (Side::Second, 4, 60),
+            ]
+        );
+
+        let empty = ranking_of(&[]);
+        assert!(common(&first, &empty, |&id| id).is_empty());
+        assert_eq!(only_in_first(&first, &empty, |&id| id).len(), 4);
+    }
+
+    #[test]
+    fn test_set_operations_use_first_occurrence_of_duplicate_ids() {
+        // Items are (id, payload) pairs, matched by id.
+        let first: Ranking<(char, u32)> =
+            [(1, ('a', 1)), (2, ('b', 2)), (3, ('a', 3)), (4, ('c', 4))]
+                .into_iter()
+                .map(|(r, item)| (Rank(r), item))
+                .collect();
+        let second: Ranking<(char, u32)> =
+            [(1, ('c', 5)), (2, ('a', 6)), (2, ('c', 7)), (3, ('d', 8))]
+                .into_iter()
+                .map(|(r, item)| (Rank(r), item))
+                .collect();
+
+        let both: Vec<(usize, usize, u32)> = common(&first, &second, |&(id, _)| id)
+            .into_iter()
+            .map(|(a, b, &(_, payload))| (a.0, b.0, payload))
+            .collect();
+        assert_eq!(both, vec![(1, 2, 1), (4, 1, 4)]);
+
+        let only_first: Vec<u32> = only_in_first(&first, &second, |&(id, _)| id)
+            .into_iter()
+            .map(|(_, &(_, payload))| payload)
+            .collect();
+        assert_eq!(only_first, vec![2]);
+        let only_second: Vec<u32> = only_in_second(&first, &second, |&(id, _)| id)
+            .into_iter()
+            .map(|(_, &(_, payload))| payload)
+            .collect();
+        assert_eq!(only_second, vec![8]);
+    }
+}
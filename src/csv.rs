@@ -0,0 +1,361 @@
+//! Ranking the rows of a CSV file by one of its columns.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::rank::{RankingStrategy, strategy_ranks};
+
+/// The column to rank the rows by.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyColumn {
+    /// The column with this header.
+    Name(String),
+    /// The column at this 0-based index.
+    Index(usize),
+}
+
+/// Where rows whose float key is NaN are placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// Reject the input with [`RankCsvError::NanKey`].
+    #[default]
+    Error,
+    /// Place NaN rows before all others, in either direction, tied with each other.
+    First,
+    /// Place NaN rows after all others, in either direction, tied with each other.
+    Last,
+}
+
+/// How the key column is parsed and compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyType {
+    /// Signed integers.
+    Integer,
+    /// Floating-point numbers, with NaN handled as given.
+    Float(NanPolicy),
+    /// The raw field, compared byte-wise.
+    String,
+}
+
+/// The options of [`rank_csv`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RankCsvOptions {
+    /// The column to rank the rows by.
+    pub key_column: KeyColumn,
+    /// How the key column is parsed.
+    pub key_type: KeyType,
+    /// Whether the largest key gets the first rank.
+    pub descending: bool,
+    /// How tied rows are numbered.
+    pub strategy: RankingStrategy,
+    /// The header of the inserted rank column.
+    pub rank_header: String,
+}
+
+impl RankCsvOptions {
+    /// Creates options ranking by `key_column` parsed as `key_type`, in ascending order,
+    /// with dense ranks in a column named `rank`.
+    pub fn new(key_column: KeyColumn, key_type: KeyType) -> Self {
+        RankCsvOptions {
+            key_column,
+            key_type,
+            descending: false,
+            strategy: RankingStrategy::Dense,
+            rank_header: "rank".to_string(),
+        }
+    }
+}
+
+/// The error returned by [`rank_csv`].
+#[derive(Debug)]
+pub enum RankCsvError {
+    /// Reading or writing the CSV data failed, or a row is malformed.
+    Csv(::csv::Error),
+    /// No column has the requested header.
+    MissingColumn(String),
+    /// The requested column index is past the last column.
+    ColumnOutOfRange {
+        /// The requested index.
+        index: usize,
+        /// The number of columns.
+        columns: usize,
+    },
+    /// A key field couldn't be parsed as the requested type.
+    InvalidKey {
+        /// The 1-based line of the row in the input.
+        line: u64,
+        /// The field that failed to parse.
+        value: String,
+    },
+    /// A float key is NaN and the policy is [`NanPolicy::Error`].
+    NanKey {
+        /// The 1-based line of the row in the input.
+        line: u64,
+    },
+}
+
+impl fmt::Display for RankCsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RankCsvError::Csv(error) => write!(f, "{error}"),
+            RankCsvError::MissingColumn(name) => write!(f, "no column named {name:?}"),
+            RankCsvError::ColumnOutOfRange { index, columns } => {
+                write!(f, "column {index} is out of range for {columns} columns")
+            }
+            RankCsvError::InvalidKey { line, value } => {
+                write!(f, "line {line}: invalid key {value:?}")
+            }
+            RankCsvError::NanKey { line } => write!(f, "line {line}: key is NaN"),
+        }
+    }
+}
+
+impl Error for RankCsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RankCsvError::Csv(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<::csv::Error> for RankCsvError {
+    fn from(error: ::csv::Error) -> Self {
+        RankCsvError::Csv(error)
+    }
+}
+
+impl From<io::Error> for RankCsvError {
+    fn from(error: io::Error) -> Self {
+        RankCsvError::Csv(error.into())
+    }
+}
+
+/// A parsed key field.
+enum Key {
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Key {
+    fn parse(field: &str, key_type: KeyType, line: u64) -> Result<Key, RankCsvError> {
+        let invalid = || RankCsvError::InvalidKey {
+            line,
+            value: field.to_string(),
+        };
+        match key_type {
+            KeyType::Integer => field
+                .trim()
+                .parse()
+                .map(Key::Integer)
+                .map_err(|_| invalid()),
+            KeyType::Float(policy) => {
+                let value: f64 = field.trim().parse().map_err(|_| invalid())?;
+                if value.is_nan() && policy == NanPolicy::Error {
+                    return Err(RankCsvError::NanKey { line });
+                }
+                Ok(Key::Float(value))
+            }
+            KeyType::String => Ok(Key::String(field.to_string())),
+        }
+    }
+
+    fn is_nan(&self) -> bool {
+        matches!(self, Key::Float(value) if value.is_nan())
+    }
+
+    /// Compares two keys of the same type; NaN is handled by the caller.
+    fn cmp_value(&self, other: &Key) -> Ordering {
+        match (self, other) {
+            (Key::Integer(a), Key::Integer(b)) => a.cmp(b),
+            (Key::Float(a), Key::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Key::String(a), Key::String(b)) => a.cmp(b),
+            _ => unreachable!("keys of one column share a type"),
+        }
+    }
+}
+
+/// Reads CSV rows, ranks them by a column, and writes them out with a rank column
+/// prepended.
+///
+/// The input must have a header row, which is written out with
+/// [`rank_header`](RankCsvOptions::rank_header) inserted in front. All rows are read
+/// before any is written, since the last row may rank first; the output rows are then
+/// streamed in rank order, with tied rows in input order. The first error is returned:
+/// a malformed row or an unparsable key is reported with its line in the input.
+///
+/// # Examples
+///
+/// ```
+/// use librank::csv::{KeyColumn, KeyType, RankCsvOptions, rank_csv};
+///
+/// let input = "name,score\nann,7\nbob,9\ncy,7\n";
+/// let mut options = RankCsvOptions::new(KeyColumn::Name("score".into()), KeyType::Integer);
+/// options.descending = true;
+///
+/// let mut output = Vec::new();
+/// rank_csv(input.as_bytes(), &mut output, &options).unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     "rank,name,score\n1,bob,9\n2,ann,7\n2,cy,7\n"
+/// );
+/// ```
+pub fn rank_csv<R, W>(reader: R, writer: W, options: &RankCsvOptions) -> Result<(), RankCsvError>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    let mut reader = ::csv::Reader::from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let column = match &options.key_column {
+        KeyColumn::Name(name) => headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| RankCsvError::MissingColumn(name.clone()))?,
+        &KeyColumn::Index(index) if index < headers.len() => index,
+        &KeyColumn::Index(index) => {
+            return Err(RankCsvError::ColumnOutOfRange {
+                index,
+                columns: headers.len(),
+            });
+        }
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+        // The reader rejects rows whose length differs from the header's.
+        let key = Key::parse(&record[column], options.key_type, line)?;
+        rows.push((key, record));
+    }
+
+    let nan_order = match options.key_type {
+        KeyType::Float(NanPolicy::First) => Ordering::Less,
+        _ => Ordering::Greater,
+    };
+    let compare = |a: &Key, b: &Key| match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => nan_order,
+        (false, true) => nan_order.reverse(),
+        (false, false) if options.descending => b.cmp_value(a),
+        (false, false) => a.cmp_value(b),
+    };
+    rows.sort_by(|(a, _), (b, _)| compare(a, b));
+
+    let mut writer = ::csv::Writer::from_writer(writer);
+    writer.write_record(std::iter::once(options.rank_header.as_str()).chain(headers.iter()))?;
+    let tied = |(a, _): &(Key, _), (b, _): &(Key, _)| compare(a, b) == Ordering::Equal;
+    for (rank, i) in strategy_ranks(&rows, options.strategy, tied) {
+        let record = &rows[i].1;
+        writer.write_record(std::iter::once(rank.to_string().as_str()).chain(record.iter()))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "\
+id,team,time
+a,red,12.5
+b,blue,9.75
+c,red,12.5
+d,blue,NaN
+e,green,11
+";
+
+    fn run(input: &str, options: &RankCsvOptions) -> Result<String, RankCsvError> {
+        let mut output = Vec::new();
+        rank_csv(input.as_bytes(), &mut output, options)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_rank_csv_float_ties_and_nan_last() {
+        let options = RankCsvOptions::new(
+            KeyColumn::Name("time".into()),
+            KeyType::Float(NanPolicy::Last),
+        );
+        let expected = "\
+rank,id,team,time
+1,b,blue,9.75
+2,e,green,11
+3,a,red,12.5
+3,c,red,12.5
+4,d,blue,NaN
+";
+        assert_eq!(run(FIXTURE, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rank_csv_descending_competition_nan_first() {
+        let mut options =
+            RankCsvOptions::new(KeyColumn::Index(2), KeyType::Float(NanPolicy::First));
+        options.descending = true;
+        options.strategy = RankingStrategy::Competition;
+        options.rank_header = "place".into();
+        let expected = "\
+place,id,team,time
+1,d,blue,NaN
+2,a,red,12.5
+2,c,red,12.5
+4,e,green,11
+5,b,blue,9.75
+";
+        assert_eq!(run(FIXTURE, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rank_csv_string_key_fractional() {
+        let mut options = RankCsvOptions::new(KeyColumn::Name("team".into()), KeyType::String);
+        options.strategy = RankingStrategy::Fractional;
+        let expected = "\
+rank,id,team,time
+1.5,b,blue,9.75
+1.5,d,blue,NaN
+3,e,green,11
+4.5,a,red,12.5
+4.5,c,red,12.5
+";
+        assert_eq!(run(FIXTURE, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rank_csv_errors() {
+        let by_time = |key_type| RankCsvOptions::new(KeyColumn::Name("time".into()), key_type);
+
+        let error = run(FIXTURE, &by_time(KeyType::Float(NanPolicy::Error))).unwrap_err();
+        assert!(matches!(error, RankCsvError::NanKey { line: 5 }));
+        assert_eq!(error.to_string(), "line 5: key is NaN");
+
+        let error = run(FIXTURE, &by_time(KeyType::Integer)).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: invalid key \"12.5\"");
+
+        let missing = RankCsvOptions::new(KeyColumn::Name("score".into()), KeyType::Integer);
+        let error = run(FIXTURE, &missing).unwrap_err();
+        assert!(matches!(&error, RankCsvError::MissingColumn(name) if name == "score"));
+
+        let out_of_range = RankCsvOptions::new(KeyColumn::Index(3), KeyType::Integer);
+        let error = run(FIXTURE, &out_of_range).unwrap_err();
+        assert!(matches!(
+            error,
+            RankCsvError::ColumnOutOfRange {
+                index: 3,
+                columns: 3
+            }
+        ));
+
+        let short_row = "id,time\na,1\nb\n";
+        let options = RankCsvOptions::new(KeyColumn::Name("time".into()), KeyType::Integer);
+        let error = run(short_row, &options).unwrap_err();
+        assert!(matches!(error, RankCsvError::Csv(_)));
+        assert!(error.to_string().contains("line: 3"), "{error}");
+    }
+}
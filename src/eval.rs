@@ -0,0 +1,501 @@
+//! Evaluation metrics for ranked retrieval results.
+
+use crate::rank::Rank;
+
+/// The result of [`mrr`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MrrReport {
+    /// The mean reciprocal rank over all queries.
+    pub mrr: f64,
+    /// The number of queries evaluated.
+    pub queries: usize,
+    /// The number of queries without any relevant result, each contributing 0.
+    pub queries_without_relevant: usize,
+}
+
+/// Computes the mean reciprocal rank of several queries.
+///
+/// Each query is a sequence of ranked results; its reciprocal rank is `1 / rank` of the
+/// first result for which `is_relevant` returns `true`, using the `Rank` the result
+/// carries. Queries without a relevant result contribute 0 and are counted in
+/// [`MrrReport::queries_without_relevant`]. With no queries at all the mean is 0.0.
+///
+/// # Examples
+///
+/// ```
+/// use librank::eval::mrr;
+/// use librank::Rank;
+///
+/// let queries = vec![
+///     vec![(Rank(1), "relevant"), (Rank(2), "other")],
+///     vec![(Rank(1), "other"), (Rank(2), "relevant")],
+/// ];
+/// let report = mrr(queries, |&doc| doc == "relevant");
+///
+/// assert_eq!(report.mrr, 0.75);
+/// assert_eq!(report.queries, 2);
+/// ```
+pub fn mrr<Q, D, P>(queries: impl IntoIterator<Item = Q>, mut is_relevant: P) -> MrrReport
+where
+    Q: IntoIterator<Item = (Rank, D)>,
+    P: FnMut(&D) -> bool,
+{
+    let mut total = 0.0;
+    let mut count = 0;
+    let mut misses = 0;
+    for query in queries {
+        count += 1;
+        match query.into_iter().find(|(_, doc)| is_relevant(doc)) {
+            Some((rank, _)) => total += 1.0 / rank.0 as f64,
+            None => misses += 1,
+        }
+    }
+    MrrReport {
+        mrr: if count == 0 {
+            0.0
+        } else {
+            total / count as f64
+        },
+        queries: count,
+        queries_without_relevant: misses,
+    }
+}
+
+/// Computes the discounted cumulative gain of the first `k` ranked results.
+///
+/// Each result's graded relevance, as returned by `relevance_fn`, contributes a gain of
+/// `2^rel - 1` discounted by `log2(position + 1)`. The position is the 1-based emission
+/// position, not the `Rank`, so tied results are discounted by where they appear.
+///
+/// # Examples
+///
+/// ```
+/// use librank::eval::dcg_at_k;
+/// use librank::Rank;
+///
+/// let ranked = vec![(Rank(1), 3.0), (Rank(2), 2.0), (Rank(3), 0.0)];
+/// let dcg = dcg_at_k(ranked, 2, |&rel| rel);
+///
+/// assert!((dcg - (7.0 + 3.0 / 3f64.log2())).abs() < 1e-12);
+/// ```
+pub fn dcg_at_k<T, R>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    k: usize,
+    mut relevance_fn: R,
+) -> f64
+where
+    R: FnMut(&T) -> f64,
+{
+    dcg(ranked
+        .into_iter()
+        .take(k)
+        .map(|(_, item)| relevance_fn(&item)))
+}
+
+/// Computes the normalized discounted cumulative gain of the first `k` ranked results.
+///
+/// This is [`dcg_at_k`] divided by the DCG of the ideal ordering, which is obtained by
+/// sorting the relevances of *all* the results (not only the first `k`) in descending
+/// order and taking the first `k`. If no result has a positive relevance the ideal DCG
+/// is zero and the NDCG is defined as 0.0.
+///
+/// # Examples
+///
+/// ```
+/// use librank::eval::ndcg_at_k;
+/// use librank::Rank;
+///
+/// let ranked = vec![(Rank(1), 0.0), (Rank(2), 1.0)];
+/// let ndcg = ndcg_at_k(ranked, 2, |&rel| rel);
+///
+/// assert!((ndcg - 1.0 / 3f64.log2()).abs() < 1e-12);
+/// ```
+pub fn ndcg_at_k<T, R>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    k: usize,
+    mut relevance_fn: R,
+) -> f64
+where
+    R: FnMut(&T) -> f64,
+{
+    let relevances: Vec<f64> = ranked
+        .into_iter()
+        .map(|(_, item)| relevance_fn(&item))
+        .collect();
+    let actual = dcg(relevances.iter().copied().take(k));
+    let mut ideal = relevances;
+    ideal.sort_by(|a, b| b.total_cmp(a));
+    let ideal = dcg(ideal.into_iter().take(k));
+    if ideal > 0.0 { actual / ideal } else { 0.0 }
+}
+
+/// Computes the fraction of the first `k` ranked results that are relevant.
+///
+/// The cutoff is positional: exactly the first `k` results in emission order are
+/// considered, even when a tie group straddles position `k`. The count is always divided
+/// by `k`, so a list shorter than `k` is penalized for the missing results. Returns 0.0
+/// when `k` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use librank::eval::precision_at_k;
+/// use librank::Rank;
+///
+/// let ranked = vec![(Rank(1), true), (Rank(2), false), (Rank(3), true)];
+/// assert_eq!(precision_at_k(ranked, 2, |&relevant| relevant), 0.5);
+/// ```
+pub fn precision_at_k<T, P>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    k: usize,
+    is_relevant: P,
+) -> f64
+where
+    P: FnMut(&T) -> bool,
+{
+    if k == 0 {
+        return 0.0;
+    }
+    relevant_in_first_k(ranked, k, is_relevant) as f64 / k as f64
+}
+
+/// Computes the fraction of all `total_relevant` relevant results found among the first
+/// `k` ranked results.
+///
+/// The cutoff is positional, as in [`precision_at_k`]. Returns 0.0 when
+/// `total_relevant` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use librank::eval::recall_at_k;
+/// use librank::Rank;
+///
+/// let ranked = vec![(Rank(1), true), (Rank(2), false), (Rank(3), true)];
+/// assert_eq!(recall_at_k(ranked, 2, |&relevant| relevant, 4), 0.25);
+/// ```
+pub fn recall_at_k<T, P>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    k: usize,
+    is_relevant: P,
+    total_relevant: usize,
+) -> f64
+where
+    P: FnMut(&T) -> bool,
+{
+    if total_relevant == 0 {
+        return 0.0;
+    }
+    relevant_in_first_k(ranked, k, is_relevant) as f64 / total_relevant as f64
+}
+
+/// Computes the average precision of a single query.
+///
+/// The precision at the position of every relevant result is summed and divided by
+/// `total_relevant`, so relevant results missing from the list count as zero. Positions
+/// are emission positions, as in [`precision_at_k`]. Returns 0.0 when `total_relevant`
+/// is zero.
+///
+/// # Examples
+///
+/// ```
+/// use librank::eval::average_precision;
+/// use librank::RankedExt;
+///
+/// struct Doc { score: u32, relevant: bool }
+///
+/// let results = vec![
+///     Doc { score: 3, relevant: false },
+///     Doc { score: 9, relevant: true },
+///     Doc { score: 5, relevant: true },
+/// ];
+/// let ranked = results.into_iter().rank_by_desc(|d| d.score);
+///
+/// assert_eq!(average_precision(ranked, |d| d.relevant, 2), 1.0);
+/// ```
+pub fn average_precision<T, P>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    mut is_relevant: P,
+    total_relevant: usize,
+) -> f64
+where
+    P: FnMut(&T) -> bool,
+{
+    if total_relevant == 0 {
+        return 0.0;
+    }
+    let mut hits = 0;
+    let mut sum = 0.0;
+    for (i, (_, item)) in ranked.into_iter().enumerate() {
+        if is_relevant(&item) {
+            hits += 1;
+            sum += hits as f64 / (i + 1) as f64;
+        }
+    }
+    sum / total_relevant as f64
+}
+
+/// How [`mean_average_precision`] treats queries with no relevant results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoRelevantPolicy {
+    /// Leave the query out of the mean.
+    #[default]
+    Exclude,
+    /// Include the query with an average precision of 0.0.
+    CountAsZero,
+}
+
+/// The result of [`mean_average_precision`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapReport {
+    /// The mean of the per-query average precisions.
+    pub map: f64,
+    /// The number of queries included in the mean.
+    pub queries: usize,
+    /// The number of queries with zero relevant results.
+    pub queries_without_relevant: usize,
+}
+
+/// Computes the mean average precision over several queries.
+///
+/// Each query is given as its ranked results together with its total number of relevant
+/// results. Queries whose total is zero are handled according to `policy`, and are
+/// always counted in [`MapReport::queries_without_relevant`]. With no included queries
+/// the mean is 0.0.
+///
+/// # Examples
+///
+/// ```
+/// use librank::eval::{NoRelevantPolicy, mean_average_precision};
+/// use librank::{Rank, RankedExt};
+///
+/// // The (score, relevant) results of each query, with its number of relevant results.
+/// let queries = vec![
+///     (vec![(9, true), (7, false), (4, true)], 2),
+///     (vec![(8, false), (6, true)], 1),
+///     (vec![(5, false)], 0),
+/// ];
+/// let ranked = queries.into_iter().map(|(results, total_relevant)| {
+///     let ranked: Vec<(Rank, (u32, bool))> =
+///         results.into_iter().rank_by_desc(|&(score, _)| score).collect();
+///     (ranked, total_relevant)
+/// });
+/// let report = mean_average_precision(ranked, |&(_, relevant)| relevant, NoRelevantPolicy::Exclude);
+///
+/// // Average precisions of 5/6 and 1/2; the query without relevant results is left out.
+/// assert!((report.map - 2.0 / 3.0).abs() < 1e-12);
+/// assert_eq!(report.queries, 2);
+/// assert_eq!(report.queries_without_relevant, 1);
+/// ```
+pub fn mean_average_precision<Q, T, P>(
+    queries: impl IntoIterator<Item = (Q, usize)>,
+    mut is_relevant: P,
+    policy: NoRelevantPolicy,
+) -> MapReport
+where
+    Q: IntoIterator<Item = (Rank, T)>,
+    P: FnMut(&T) -> bool,
+{
+    let mut total = 0.0;
+    let mut count = 0;
+    let mut without_relevant = 0;
+    for (ranked, total_relevant) in queries {
+        if total_relevant == 0 {
+            without_relevant += 1;
+            if policy == NoRelevantPolicy::Exclude {
+                continue;
+            }
+        }
+        count += 1;
+        total += average_precision(ranked, &mut is_relevant, total_relevant);
+    }
+    MapReport {
+        map: if count == 0 {
+            0.0
+        } else {
+            total / count as f64
+        },
+        queries: count,
+        queries_without_relevant: without_relevant,
+    }
+}
+
+fn relevant_in_first_k<T, P>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    k: usize,
+    mut is_relevant: P,
+) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    ranked
+        .into_iter()
+        .take(k)
+        .filter(|(_, item)| is_relevant(item))
+        .count()
+}
+
+fn dcg(relevances: impl Iterator<Item = f64>) -> f64 {
+    relevances
+        .enumerate()
+        .map(|(i, rel)| (rel.exp2() - 1.0) / ((i + 2) as f64).log2())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+
+    #[test]
+    fn test_mrr_first_result_relevant() {
+        let queries = vec![vec![(Rank(1), true), (Rank(2), false)]];
+        let report = mrr(queries, |&relevant| relevant);
+        assert_eq!(report.mrr, 1.0);
+        assert_eq!(report.queries_without_relevant, 0);
+    }
+
+    #[test]
+    fn test_mrr_relevant_result_deep_in_list() {
+        let scores = vec![9, 7, 5, 3, 1];
+        let query = scores.into_iter().rank_by(|&s| std::cmp::Reverse(s));
+        let report = mrr([query], |&s| s == 1);
+        assert_eq!(report.mrr, 0.2);
+    }
+
+    #[test]
+    fn test_mrr_no_relevant_results() {
+        let queries = vec![
+            vec![(Rank(1), 'a'), (Rank(2), 'b')],
+            vec![(Rank(1), 'x'), (Rank(2), 'c')],
+            vec![],
+        ];
+        let report = mrr(queries, |&doc| doc == 'x');
+        assert_eq!(report.queries, 3);
+        assert_eq!(report.queries_without_relevant, 2);
+        assert!((report.mrr - 1.0 / 3.0).abs() < 1e-12);
+
+        let report = mrr(Vec::<Vec<(Rank, char)>>::new(), |_| true);
+        assert_eq!(report.mrr, 0.0);
+        assert_eq!(report.queries, 0);
+    }
+
+    /// The relevance list of the DCG example on Wikipedia's "Discounted cumulative gain"
+    /// page, scored with the exponential `2^rel - 1` gain.
+    const WIKIPEDIA_RELEVANCES: [f64; 6] = [3.0, 2.0, 3.0, 0.0, 1.0, 2.0];
+
+    fn ranked_relevances() -> Vec<(Rank, f64)> {
+        WIKIPEDIA_RELEVANCES
+            .iter()
+            .enumerate()
+            .map(|(i, &rel)| (Rank(i + 1), rel))
+            .collect()
+    }
+
+    #[test]
+    fn test_dcg_at_k_worked_example() {
+        let dcg = dcg_at_k(ranked_relevances(), 6, |&rel| rel);
+        assert!((dcg - 13.848263629272981).abs() < 1e-9);
+        let dcg = dcg_at_k(ranked_relevances(), 3, |&rel| rel);
+        assert!((dcg - 12.392789260714373).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ndcg_at_k_worked_example() {
+        let ndcg = ndcg_at_k(ranked_relevances(), 6, |&rel| rel);
+        assert!((ndcg - 0.9488107485678985).abs() < 1e-9);
+        let ndcg = ndcg_at_k(ranked_relevances(), 3, |&rel| rel);
+        assert!((ndcg - 0.9594535145926796).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ndcg_at_k_discounts_ties_by_position() {
+        let tied = vec![(Rank(1), 0.0), (Rank(1), 1.0)];
+        let ndcg = ndcg_at_k(tied, 2, |&rel| rel);
+        assert!((ndcg - 1.0 / 3f64.log2()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ndcg_at_k_all_zero_relevance() {
+        let ranked = vec![(Rank(1), 0.0), (Rank(2), 0.0)];
+        assert_eq!(ndcg_at_k(ranked, 2, |&rel| rel), 0.0);
+        assert_eq!(ndcg_at_k(Vec::<(Rank, f64)>::new(), 5, |&rel| rel), 0.0);
+    }
+
+    #[test]
+    fn test_precision_and_recall_at_k_tie_straddling_cutoff() {
+        let ranked = vec![
+            (Rank(1), 'r'),
+            (Rank(2), 'n'),
+            (Rank(2), 'r'),
+            (Rank(2), 'r'),
+            (Rank(3), 'r'),
+        ];
+        let is_relevant = |&c: &char| c == 'r';
+        assert_eq!(precision_at_k(ranked.clone(), 3, is_relevant), 2.0 / 3.0);
+        assert_eq!(recall_at_k(ranked, 3, is_relevant, 4), 0.5);
+    }
+
+    #[test]
+    fn test_precision_and_recall_at_k_beyond_list_length() {
+        let ranked = vec![(Rank(1), true), (Rank(2), true)];
+        assert_eq!(precision_at_k(ranked.clone(), 4, |&r| r), 0.5);
+        assert_eq!(recall_at_k(ranked, 4, |&r| r, 2), 1.0);
+    }
+
+    #[test]
+    fn test_precision_and_recall_at_k_empty_list() {
+        let empty = Vec::<(Rank, bool)>::new();
+        assert_eq!(precision_at_k(empty.clone(), 3, |&r| r), 0.0);
+        assert_eq!(precision_at_k(empty.clone(), 0, |&r| r), 0.0);
+        assert_eq!(recall_at_k(empty.clone(), 3, |&r| r, 5), 0.0);
+        assert_eq!(recall_at_k(empty, 3, |&r| r, 0), 0.0);
+    }
+
+    /// Two queries over 20 results: the first has 5 relevant documents, found at
+    /// positions 1, 3, 6, 10 and 20; the second has 3, found at positions 2, 5 and 7.
+    fn worked_example() -> Vec<(Vec<(Rank, bool)>, usize)> {
+        let query = |hits: &[usize]| -> Vec<(Rank, bool)> {
+            (1..=20).map(|i| (Rank(i), hits.contains(&i))).collect()
+        };
+        vec![(query(&[1, 3, 6, 10, 20]), 5), (query(&[2, 5, 7]), 3)]
+    }
+
+    #[test]
+    fn test_average_precision_worked_example() {
+        let queries = worked_example();
+        let ap1 = (1.0 + 2.0 / 3.0 + 3.0 / 6.0 + 4.0 / 10.0 + 5.0 / 20.0) / 5.0;
+        let ap2 = (1.0 / 2.0 + 2.0 / 5.0 + 3.0 / 7.0) / 3.0;
+        let (ranked, total) = queries[0].clone();
+        assert!((average_precision(ranked, |&r| r, total) - ap1).abs() < 1e-12);
+        let (ranked, total) = queries[1].clone();
+        assert!((average_precision(ranked, |&r| r, total) - ap2).abs() < 1e-12);
+
+        let report = mean_average_precision(queries, |&r| r, NoRelevantPolicy::Exclude);
+        assert!((report.map - (ap1 + ap2) / 2.0).abs() < 1e-12);
+        assert!((report.map - 0.5030).abs() < 1e-4);
+        assert_eq!(report.queries, 2);
+    }
+
+    #[test]
+    fn test_average_precision_missing_relevant_results() {
+        let ranked = vec![(Rank(1), true), (Rank(2), false)];
+        assert_eq!(average_precision(ranked.clone(), |&r| r, 2), 0.5);
+        assert_eq!(average_precision(ranked, |&r| r, 0), 0.0);
+    }
+
+    #[test]
+    fn test_mean_average_precision_no_relevant_policy() {
+        let mut queries = worked_example();
+        queries.push((vec![(Rank(1), false)], 0));
+
+        let excluded = mean_average_precision(queries.clone(), |&r| r, NoRelevantPolicy::Exclude);
+        assert_eq!(excluded.queries, 2);
+        assert_eq!(excluded.queries_without_relevant, 1);
+
+        let zero = mean_average_precision(queries, |&r| r, NoRelevantPolicy::CountAsZero);
+        assert_eq!(zero.queries, 3);
+        assert_eq!(zero.queries_without_relevant, 1);
+        assert!((zero.map - excluded.map * 2.0 / 3.0).abs() < 1e-12);
+    }
+}
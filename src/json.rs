@@ -0,0 +1,166 @@
+//! Writing ranked items as JSON or JSON Lines.
+//!
+//! Each ranked item is written as an object `{"rank": n, "item": ...}`. Output is
+//! streamed one item at a time, so wrap unbuffered writers such as files in a
+//! [`BufWriter`](std::io::BufWriter).
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::rank::Rank;
+
+#[derive(Serialize)]
+struct Entry<'a, T> {
+    rank: usize,
+    item: &'a T,
+}
+
+/// Writes ranked items as a JSON array of `{"rank": n, "item": ...}` objects.
+///
+/// Errors from serializing an item or from the writer are returned as they occur.
+///
+/// # Examples
+///
+/// ```
+/// use librank::RankedExt;
+/// use librank::json::write_json;
+///
+/// let mut out = Vec::new();
+/// write_json(vec!["b", "a"].into_iter().rank_by(|&s| s), &mut out).unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     r#"[{"rank":1,"item":"a"},{"rank":2,"item":"b"}]"#
+/// );
+/// ```
+pub fn write_json<T, W>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    mut writer: W,
+) -> serde_json::Result<()>
+where
+    T: Serialize,
+    W: Write,
+{
+    writer.write_all(b"[").map_err(serde_json::Error::io)?;
+    for (i, (rank, item)) in ranked.into_iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",").map_err(serde_json::Error::io)?;
+        }
+        let entry = Entry {
+            rank: rank.0,
+            item: &item,
+        };
+        serde_json::to_writer(&mut writer, &entry)?;
+    }
+    writer.write_all(b"]").map_err(serde_json::Error::io)
+}
+
+/// Writes ranked items as JSON Lines: one `{"rank": n, "item": ...}` object per line.
+///
+/// Every line, including the last, ends with `\n`. Errors from serializing an item or
+/// from the writer are returned as they occur.
+pub fn write_json_lines<T, W>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    mut writer: W,
+) -> serde_json::Result<()>
+where
+    T: Serialize,
+    W: Write,
+{
+    for (rank, item) in ranked {
+        let entry = Entry {
+            rank: rank.0,
+            item: &item,
+        };
+        serde_json::to_writer(&mut writer, &entry)?;
+        writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::RankedExt;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Player {
+        name: String,
+        score: u32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct OwnedEntry {
+        rank: usize,
+        item: Player,
+    }
+
+    fn players() -> Vec<Player> {
+        [("ann", 7), ("bob", 9), ("cy", 7)]
+            .into_iter()
+            .map(|(name, score)| Player {
+                name: name.to_string(),
+                score,
+            })
+            .collect()
+    }
+
+    fn expected() -> Vec<OwnedEntry> {
+        players()
+            .into_iter()
+            .rank_by(|p| p.score)
+            .map(|(rank, item)| OwnedEntry { rank: rank.0, item })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_json_round_trip() {
+        let mut out = Vec::new();
+        write_json(players().into_iter().rank_by(|p| p.score), &mut out).unwrap();
+        let parsed: Vec<OwnedEntry> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(parsed, expected());
+
+        let mut empty = Vec::new();
+        write_json(Vec::<(Rank, Player)>::new(), &mut empty).unwrap();
+        assert_eq!(empty, b"[]");
+    }
+
+    #[test]
+    fn test_write_json_lines_round_trip() {
+        let mut out = Vec::new();
+        write_json_lines(players().into_iter().rank_by(|p| p.score), &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.ends_with('\n'));
+        let parsed: Vec<OwnedEntry> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(parsed, expected());
+    }
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_json_propagates_writer_errors() {
+        let ranked = || players().into_iter().rank_by(|p| p.score);
+        let error = write_json(ranked(), FailingWriter).unwrap_err();
+        assert!(error.is_io());
+        let error = write_json_lines(ranked(), FailingWriter).unwrap_err();
+        assert_eq!(io::Error::from(error).to_string(), "disk full");
+    }
+}
@@ -0,0 +1,1284 @@
+//! A leaderboard that ranks entries as they are inserted, optionally keeping only the
+//! top positions, and a thread-safe version of it.
+//!
+//! A [`Leaderboard`] can also watch rank thresholds, such as the top 10, and report
+//! which entries crossed them in each mutation.
+//!
+//! With the `serde` feature a [`Leaderboard`] can be saved as a `LeaderboardSnapshot`
+//! and restored from it.
+
+#[cfg(feature = "serde")]
+use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+#[cfg(feature = "serde")]
+use std::error::Error;
+#[cfg(feature = "serde")]
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::order_stat::KeyCounts;
+use crate::rank::Rank;
+use crate::ranking::Ranking;
+
+/// Which entries a capacity-bounded [`Leaderboard`] keeps when entries tie at the
+/// cutoff.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CutoffTies {
+    /// Keep every entry tied with the one at the last position, even if the board then
+    /// holds more entries than its capacity.
+    #[default]
+    KeepAll,
+    /// Never hold more entries than the capacity: among tied entries the ones inserted
+    /// last are evicted, or refused, first.
+    EvictNewest,
+}
+
+/// The result of [`Leaderboard::insert`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Insertion<T> {
+    /// The entry is on the board.
+    Accepted {
+        /// The entry's dense rank on the board.
+        rank: Rank,
+        /// The entries pushed past the cutoff by this one, best first.
+        evicted: Vec<T>,
+    },
+    /// The entry would land past the cutoff and was not inserted.
+    Refused {
+        /// The refused entry.
+        item: T,
+        /// The dense rank the entry would have among the entries on the board.
+        rank: Rank,
+        /// The 1-based position the entry would take, after the entries with equal
+        /// keys.
+        position: usize,
+    },
+}
+
+/// An entry whose rank crossed a threshold watched with
+/// [`Leaderboard::watch_threshold`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RankCrossing<T> {
+    /// The entry.
+    pub item: T,
+    /// The threshold the entry crossed.
+    pub threshold: Rank,
+    /// The entry's rank before the mutation, or `None` if it wasn't on the board.
+    pub old: Option<Rank>,
+    /// The entry's rank after the mutation, or `None` if it left the board.
+    pub new: Option<Rank>,
+}
+
+impl<T> RankCrossing<T> {
+    /// Returns `true` if the entry is now at or above the threshold, and `false` if it
+    /// fell below it.
+    pub fn entered(&self) -> bool {
+        self.new.is_some_and(|rank| rank <= self.threshold)
+    }
+}
+
+/// The threshold crossings caused by one mutation of a [`Leaderboard`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MutationReport<T> {
+    crossings: Vec<RankCrossing<T>>,
+}
+
+impl<T> MutationReport<T> {
+    /// Returns the crossings in order of threshold. For each threshold the mutated entry
+    /// comes first, then the entries it displaced in rank order.
+    pub fn crossings(&self) -> &[RankCrossing<T>] {
+        &self.crossings
+    }
+
+    /// Returns `true` if no entry crossed a threshold.
+    pub fn is_empty(&self) -> bool {
+        self.crossings.is_empty()
+    }
+}
+
+impl<T> Default for MutationReport<T> {
+    /// Returns a report without crossings.
+    fn default() -> Self {
+        MutationReport {
+            crossings: Vec::new(),
+        }
+    }
+}
+
+/// An entry that may cross a threshold in a mutation: the threshold, the entry's rank
+/// and key before the mutation, and the entry.
+type Watched<K, T> = (Rank, Option<Rank>, Option<K>, T);
+
+/// Entries ranked by key, smallest first, as they are inserted.
+///
+/// Entries with equal keys share a dense rank and are kept in insertion order. With
+/// [`with_capacity_by_rank`](Leaderboard::with_capacity_by_rank), the board only keeps
+/// the entries at the first `n` positions: inserts that would land further down are
+/// refused, and inserts that push entries past the cutoff evict them. Next to the
+/// entries the board keeps an order-statistic tree of their keys, so inserting and
+/// [`rank_of`](Leaderboard::rank_of) cost O(log d) for `d` distinct keys, plus the
+/// entries evicted.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Reverse;
+///
+/// use librank::Rank;
+/// use librank::leaderboard::{CutoffTies, Insertion, Leaderboard};
+///
+/// // The two highest scores.
+/// let mut board = Leaderboard::with_capacity_by_rank(2, CutoffTies::EvictNewest);
+/// board.insert("ann", Reverse(70));
+/// board.insert("bob", Reverse(90));
+///
+/// assert_eq!(
+///     board.insert("cy", Reverse(80)),
+///     Insertion::Accepted { rank: Rank(2), evicted: vec!["ann"] }
+/// );
+/// assert_eq!(
+///     board.insert("dee", Reverse(60)),
+///     Insertion::Refused { item: "dee", rank: Rank(3), position: 3 }
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Leaderboard<K, T> {
+    /// The entries with each key, by the order in which they were inserted.
+    groups: BTreeMap<K, BTreeMap<u64, T>>,
+    /// The keys of the entries, one occurrence per entry.
+    counts: KeyCounts<K>,
+    /// The insertion order given to the next entry.
+    next: u64,
+    len: usize,
+    capacity: Option<usize>,
+    ties: CutoffTies,
+    thresholds: BTreeSet<Rank>,
+}
+
+impl<K: Ord, T> Leaderboard<K, T> {
+    /// Creates an empty leaderboard with no limit on its size.
+    pub fn new() -> Self {
+        Leaderboard {
+            groups: BTreeMap::new(),
+            counts: KeyCounts::new(),
+            next: 0,
+            len: 0,
+            capacity: None,
+            ties: CutoffTies::KeepAll,
+            thresholds: BTreeSet::new(),
+        }
+    }
+
+    /// Creates an empty leaderboard that only keeps the entries at the first `n`
+    /// positions, handling ties at the cutoff as given.
+    pub fn with_capacity_by_rank(n: usize, ties: CutoffTies) -> Self {
+        Leaderboard {
+            capacity: Some(n),
+            ties,
+            ..Leaderboard::new()
+        }
+    }
+
+    /// Inserts an entry with its key.
+    ///
+    /// Without a capacity every entry is accepted. With one, the entry is refused if it
+    /// would land past the cutoff: with [`CutoffTies::KeepAll`] that is when `n` entries
+    /// have smaller keys, and with [`CutoffTies::EvictNewest`] when `n` entries have
+    /// smaller or equal keys. Otherwise the entries it pushes past the cutoff are
+    /// evicted and returned.
+    pub fn insert(&mut self, item: T, key: K) -> Insertion<T>
+    where
+        K: Clone,
+    {
+        let rank = Rank(self.counts.count_below(&key) + 1);
+        if let Some(n) = self.capacity {
+            let ahead = self.counts.count_entries_below(&key);
+            let tied = self.counts.count(&key);
+            let refused = match self.ties {
+                CutoffTies::KeepAll => ahead >= n,
+                CutoffTies::EvictNewest => ahead + tied >= n,
+            };
+            if refused {
+                return Insertion::Refused {
+                    item,
+                    rank,
+                    position: ahead + tied + 1,
+                };
+            }
+        }
+        self.counts.insert(key.clone());
+        self.groups.entry(key).or_default().insert(self.next, item);
+        self.next += 1;
+        self.len += 1;
+        Insertion::Accepted {
+            rank,
+            evicted: self.evict(),
+        }
+    }
+
+    /// Removes the entries past the cutoff, returning them best first.
+    fn evict(&mut self) -> Vec<T> {
+        let Some(n) = self.capacity else {
+            return Vec::new();
+        };
+        let mut evicted = Vec::new();
+        match self.ties {
+            CutoffTies::KeepAll => {
+                while let Some(last) = self.groups.last_entry()
+                    && self.len - last.get().len() >= n
+                {
+                    self.len -= last.get().len();
+                    for _ in 0..last.get().len() {
+                        self.counts.remove(last.key());
+                    }
+                    let mut group: Vec<T> = last.remove().into_values().collect();
+                    group.append(&mut evicted);
+                    evicted = group;
+                }
+            }
+            CutoffTies::EvictNewest => {
+                while self.len > n
+                    && let Some(mut last) = self.groups.last_entry()
+                {
+                    evicted.extend(last.get_mut().pop_last().map(|(_, item)| item));
+                    self.counts.remove(last.key());
+                    self.len -= 1;
+                    if last.get().is_empty() {
+                        last.remove();
+                    }
+                }
+                evicted.reverse();
+            }
+        }
+        evicted
+    }
+
+    /// Removes the first entry equal to `item` among the entries with `key`, returning
+    /// it.
+    ///
+    /// Finding the entry scans the entries with `key`. Removing an entry doesn't bring
+    /// back entries evicted earlier.
+    pub fn remove(&mut self, item: &T, key: &K) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let (&order, _) = self
+            .groups
+            .get(key)?
+            .iter()
+            .find(|(_, entry)| *entry == item)?;
+        self.remove_at(key, order)
+    }
+
+    /// Removes the entry with `key` that was given the insertion order `order`.
+    fn remove_at(&mut self, key: &K, order: u64) -> Option<T> {
+        let group = self.groups.get_mut(key)?;
+        let removed = group.remove(&order)?;
+        if group.is_empty() {
+            self.groups.remove(key);
+        }
+        self.counts.remove(key);
+        self.len -= 1;
+        Some(removed)
+    }
+
+    /// Moves the first entry equal to `item` among the entries with key `from` to key
+    /// `to`, returning `None` if there is no such entry.
+    ///
+    /// The entry is removed and inserted again, so it comes last among the entries with
+    /// key `to`, and it leaves the board if the insert is refused.
+    pub fn update(&mut self, item: &T, from: &K, to: K) -> Option<Insertion<T>>
+    where
+        K: Clone,
+        T: PartialEq,
+    {
+        let item = self.remove(item, from)?;
+        Some(self.insert(item, to))
+    }
+
+    /// Watches a rank threshold: the `*_with_report` methods report the entries whose
+    /// rank moves from at or above `threshold` to below it, or the other way around.
+    ///
+    /// With the threshold `Rank(10)`, an entry entering or leaving the top 10 ranks is
+    /// reported. Thresholds aren't part of a snapshot.
+    pub fn watch_threshold(&mut self, threshold: Rank) {
+        self.thresholds.insert(threshold);
+    }
+
+    /// Returns the dense rank of the entries with `key`, or `None` if no entry has it.
+    pub fn rank_of(&self, key: &K) -> Option<Rank> {
+        self.groups
+            .contains_key(key)
+            .then(|| Rank(self.counts.count_below(key) + 1))
+    }
+
+    /// Returns the entries in rank order with their dense ranks.
+    pub fn iter(&self) -> impl Iterator<Item = (Rank, &T)> {
+        self.groups
+            .values()
+            .enumerate()
+            .flat_map(|(i, group)| group.values().map(move |item| (Rank(i + 1), item)))
+    }
+
+    /// Converts the board into a [`Ranking`] of its entries.
+    pub fn into_ranking(self) -> Ranking<T> {
+        Ranking::from_groups(
+            self.groups
+                .into_values()
+                .map(|group| group.into_values().collect()),
+        )
+    }
+
+    /// Returns the number of entries on the board.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the board has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of positions the board keeps, or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+}
+
+impl<K: Ord + Clone, T: Clone + PartialEq> Leaderboard<K, T> {
+    /// Like [`insert`](Leaderboard::insert), but also reports the entries that crossed a
+    /// watched threshold, including the ones displaced or evicted by the new entry.
+    ///
+    /// Entries are told apart by value, so the report is only meaningful if no two
+    /// entries on the board are equal. Each watched threshold adds the cost of walking
+    /// the board up to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::leaderboard::Leaderboard;
+    ///
+    /// let mut board = Leaderboard::new();
+    /// board.watch_threshold(Rank(2));
+    /// board.insert("ann", 10);
+    /// board.insert("bob", 20);
+    ///
+    /// let (_, report) = board.insert_with_report("cy", 5);
+    /// let crossings: Vec<(&str, bool)> =
+    ///     report.crossings().iter().map(|c| (c.item, c.entered())).collect();
+    /// assert_eq!(crossings, vec![("cy", true), ("bob", false)]);
+    /// ```
+    pub fn insert_with_report(&mut self, item: T, key: K) -> (Insertion<T>, MutationReport<T>) {
+        let watched = self.watched(&item, None);
+        let (moved, to) = (item.clone(), key.clone());
+        let insertion = self.insert(item, key);
+        let to = matches!(insertion, Insertion::Accepted { .. }).then_some(&to);
+        let report = self.report(watched, &moved, to);
+        (insertion, report)
+    }
+
+    /// Like [`update`](Leaderboard::update), but also reports the entries that crossed a
+    /// watched threshold, as [`insert_with_report`](Leaderboard::insert_with_report)
+    /// does.
+    pub fn update_with_report(
+        &mut self,
+        item: &T,
+        from: &K,
+        to: K,
+    ) -> (Option<Insertion<T>>, MutationReport<T>) {
+        let watched = self.watched(item, Some(from));
+        let new_key = to.clone();
+        let Some(insertion) = self.update(item, from, to) else {
+            return (None, MutationReport::default());
+        };
+        let to = matches!(insertion, Insertion::Accepted { .. }).then_some(&new_key);
+        let report = self.report(watched, item, to);
+        (Some(insertion), report)
+    }
+
+    /// Like [`remove`](Leaderboard::remove), but also reports the entries that crossed a
+    /// watched threshold, as [`insert_with_report`](Leaderboard::insert_with_report)
+    /// does.
+    pub fn remove_with_report(&mut self, item: &T, key: &K) -> (Option<T>, MutationReport<T>) {
+        let watched = self.watched(item, Some(key));
+        let removed = self.remove(item, key);
+        let report = self.report(watched, item, None);
+        (removed, report)
+    }
+
+    /// Collects the entries that can cross a threshold when `moved` is mutated.
+    ///
+    /// A mutation shifts every other entry by at most one rank, so only the entries at
+    /// each threshold and right below it can cross it, besides `moved` itself. On a
+    /// bounded board an insert can also evict entries of the last group, which may be
+    /// well above the threshold.
+    fn watched(&self, moved: &T, from: Option<&K>) -> Vec<Watched<K, T>> {
+        let moved_rank = from.and_then(|key| {
+            let group = self.groups.get(key)?;
+            group
+                .values()
+                .any(|item| item == moved)
+                .then(|| self.rank_of(key))
+                .flatten()
+        });
+        let mut watched = Vec::new();
+        for &threshold in &self.thresholds {
+            watched.push((threshold, moved_rank, None, moved.clone()));
+            let first = threshold.0.max(1) - 1;
+            let last = self
+                .groups
+                .iter()
+                .enumerate()
+                .next_back()
+                .filter(|&(i, _)| self.capacity.is_some() && i < first);
+            let groups = last
+                .into_iter()
+                .chain(self.groups.iter().enumerate().skip(first).take(2));
+            for (i, (key, group)) in groups {
+                for item in group.values().filter(|&item| item != moved) {
+                    watched.push((
+                        threshold,
+                        Some(Rank(i + 1)),
+                        Some(key.clone()),
+                        item.clone(),
+                    ));
+                }
+            }
+        }
+        watched
+    }
+
+    /// Reports the watched entries whose rank crossed their threshold, given the key
+    /// `moved` ended up under, if it is on the board.
+    fn report(&self, watched: Vec<Watched<K, T>>, moved: &T, to: Option<&K>) -> MutationReport<T> {
+        let crossings = watched
+            .into_iter()
+            .filter_map(|(threshold, old, key, item)| {
+                let new = if &item == moved {
+                    to.and_then(|key| self.rank_of(key))
+                } else {
+                    key.filter(|key| {
+                        self.groups
+                            .get(key)
+                            .is_some_and(|g| g.values().any(|i| *i == item))
+                    })
+                    .and_then(|key| self.rank_of(&key))
+                };
+                let within = |rank: Option<Rank>| rank.is_some_and(|rank| rank <= threshold);
+                (within(old) != within(new)).then_some(RankCrossing {
+                    item,
+                    threshold,
+                    old,
+                    new,
+                })
+            })
+            .collect();
+        MutationReport { crossings }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<K: Ord + Clone, T: Clone> Leaderboard<K, T> {
+    /// Saves the board as a snapshot that can be serialized.
+    ///
+    /// The snapshot holds each distinct key once, followed by its entries in insertion
+    /// order, along with the capacity and the [`CutoffTies`] policy.
+    pub fn snapshot(&self) -> LeaderboardSnapshot<K, T> {
+        LeaderboardSnapshot {
+            version: SNAPSHOT_VERSION,
+            capacity: self.capacity,
+            ties: self.ties,
+            groups: self
+                .groups
+                .iter()
+                .map(|(key, group)| (key.clone(), group.values().cloned().collect()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<K: Ord + Clone, T: Eq + Hash> Leaderboard<K, T> {
+    /// Rebuilds a board from a snapshot taken by [`snapshot`](Leaderboard::snapshot).
+    ///
+    /// The snapshot is checked before it is used, so a snapshot from another version of
+    /// this crate or one edited by hand is rejected rather than misread: the keys must
+    /// be in increasing order, each with at least one entry, no entry may occur twice,
+    /// and the entries must fit the capacity. Entries are treated as ids, so a board
+    /// that holds the same entry twice can be saved but not restored. The board,
+    /// including its order-statistic tree, is rebuilt in a single pass over the groups,
+    /// in O(n + d log d) for `n` entries and `d` distinct keys.
+    pub fn restore(snapshot: LeaderboardSnapshot<K, T>) -> Result<Self, SnapshotError> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: snapshot.version,
+            });
+        }
+        let mut seen = HashSet::new();
+        for (group, (key, entries)) in snapshot.groups.iter().enumerate() {
+            if entries.is_empty() {
+                return Err(SnapshotError::EmptyGroup { group });
+            }
+            if group > 0 && snapshot.groups[group - 1].0 >= *key {
+                return Err(SnapshotError::UnorderedKeys { group });
+            }
+            if !entries.iter().all(|entry| seen.insert(entry)) {
+                return Err(SnapshotError::DuplicateEntry { group });
+            }
+        }
+        let len = seen.len();
+        if let Some(n) = snapshot.capacity {
+            let last = snapshot
+                .groups
+                .last()
+                .map_or(0, |(_, entries)| entries.len());
+            let over = match snapshot.ties {
+                CutoffTies::KeepAll => len > 0 && len - last >= n,
+                CutoffTies::EvictNewest => len > n,
+            };
+            if over {
+                return Err(SnapshotError::OverCapacity);
+            }
+        }
+        let mut orders = 0..;
+        let mut groups = BTreeMap::new();
+        let counts = KeyCounts::from_sorted(snapshot.groups.into_iter().map(|(key, entries)| {
+            let count = entries.len();
+            groups.insert(key.clone(), orders.by_ref().zip(entries).collect());
+            (key, count)
+        }));
+        Ok(Leaderboard {
+            groups,
+            counts,
+            next: orders.start,
+            len,
+            capacity: snapshot.capacity,
+            ties: snapshot.ties,
+            thresholds: BTreeSet::new(),
+        })
+    }
+}
+
+impl<K: Ord, T> Default for Leaderboard<K, T> {
+    fn default() -> Self {
+        Leaderboard::new()
+    }
+}
+
+/// The layout version written by [`Leaderboard::snapshot`].
+#[cfg(feature = "serde")]
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// A saved [`Leaderboard`], for serializing it and restoring it with
+/// [`Leaderboard::restore`].
+///
+/// The snapshot carries a layout version, so a snapshot written by a version of this
+/// crate with a different layout fails to restore instead of being misread.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::leaderboard::{CutoffTies, Leaderboard};
+///
+/// let mut board = Leaderboard::with_capacity_by_rank(2, CutoffTies::KeepAll);
+/// board.insert("ann", 3);
+/// board.insert("bob", 1);
+/// let saved = serde_json::to_string(&board.snapshot()).unwrap();
+///
+/// let restored: Leaderboard<u32, String> =
+///     Leaderboard::restore(serde_json::from_str(&saved).unwrap()).unwrap();
+/// let entries: Vec<(Rank, &str)> = restored.iter().map(|(r, s)| (r, s.as_str())).collect();
+/// assert_eq!(entries, vec![(Rank(1), "bob"), (Rank(2), "ann")]);
+/// assert_eq!(restored.capacity(), Some(2));
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LeaderboardSnapshot<K, T> {
+    version: u32,
+    capacity: Option<usize>,
+    ties: CutoffTies,
+    groups: Vec<(K, Vec<T>)>,
+}
+
+/// Why [`Leaderboard::restore`] rejected a snapshot.
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot was written with a layout this version can't read.
+    UnsupportedVersion {
+        /// The layout version of the snapshot.
+        found: u32,
+    },
+    /// A key has no entries.
+    EmptyGroup {
+        /// The index of the key among the snapshot's keys.
+        group: usize,
+    },
+    /// A key isn't greater than the key before it.
+    UnorderedKeys {
+        /// The index of the key among the snapshot's keys.
+        group: usize,
+    },
+    /// An entry occurs a second time.
+    DuplicateEntry {
+        /// The index of the key the second occurrence is under.
+        group: usize,
+    },
+    /// The entries don't fit the board's capacity.
+    OverCapacity,
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::UnsupportedVersion { found } => write!(
+                f,
+                "snapshot layout version {found} is not supported, expected {SNAPSHOT_VERSION}"
+            ),
+            SnapshotError::EmptyGroup { group } => write!(f, "snapshot key {group} has no entries"),
+            SnapshotError::UnorderedKeys { group } => {
+                write!(f, "snapshot key {group} is out of order")
+            }
+            SnapshotError::DuplicateEntry { group } => {
+                write!(f, "snapshot key {group} repeats an earlier entry")
+            }
+            SnapshotError::OverCapacity => {
+                write!(f, "snapshot holds more entries than its capacity")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error for SnapshotError {}
+
+/// A [`Leaderboard`] that can be shared and updated across threads.
+///
+/// Each entry is on the board at most once, so entries are identified by value: inserting
+/// an entry that is already on the board moves it to its new key. Every method takes the
+/// lock once and calls no user code while holding it other than the `Ord`, `Hash`, `Eq`
+/// and `Clone` implementations of the keys and entries, so reads such as
+/// [`snapshot_top`](ConcurrentLeaderboard::snapshot_top) see the board as it was between
+/// two updates.
+///
+/// The board remembers where each entry is, so inserting, moving and ranking an entry
+/// hold the lock for O(log n) for `n` entries, plus the entries evicted.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Reverse;
+///
+/// use librank::Rank;
+/// use librank::leaderboard::ConcurrentLeaderboard;
+///
+/// let board = ConcurrentLeaderboard::new();
+/// std::thread::scope(|s| {
+///     s.spawn(|| board.insert("ann", Reverse(70)));
+///     s.spawn(|| board.insert("bob", Reverse(90)));
+/// });
+/// board.update(&"ann", Reverse(95));
+///
+/// assert_eq!(board.rank_of(&"bob"), Some(Rank(2)));
+/// assert_eq!(board.snapshot_top(1), vec![(Rank(1), "ann")]);
+/// ```
+#[derive(Debug)]
+pub struct ConcurrentLeaderboard<K, T> {
+    inner: RwLock<Entries<K, T>>,
+}
+
+/// The board and the key and insertion order of each entry on it, so that an entry is
+/// found on the board without scanning the entries tied with it.
+#[derive(Debug)]
+struct Entries<K, T> {
+    board: Leaderboard<K, T>,
+    keys: HashMap<T, (K, u64)>,
+}
+
+impl<K: Ord + Clone, T: Eq + Hash + Clone> Entries<K, T> {
+    fn insert(&mut self, item: T, key: K) -> Insertion<T> {
+        if let Some((old, order)) = self.keys.remove(&item) {
+            self.board.remove_at(&old, order);
+        }
+        let order = self.board.next;
+        let insertion = self.board.insert(item.clone(), key.clone());
+        if let Insertion::Accepted { evicted, .. } = &insertion {
+            for entry in evicted {
+                self.keys.remove(entry);
+            }
+            self.keys.insert(item, (key, order));
+        }
+        insertion
+    }
+}
+
+impl<K: Ord + Clone, T: Eq + Hash + Clone> ConcurrentLeaderboard<K, T> {
+    /// Creates an empty leaderboard with no limit on its size.
+    pub fn new() -> Self {
+        ConcurrentLeaderboard::from_board(Leaderboard::new())
+    }
+
+    /// Creates an empty leaderboard that only keeps the entries at the first `n`
+    /// positions, like [`Leaderboard::with_capacity_by_rank`].
+    pub fn with_capacity_by_rank(n: usize, ties: CutoffTies) -> Self {
+        ConcurrentLeaderboard::from_board(Leaderboard::with_capacity_by_rank(n, ties))
+    }
+
+    fn from_board(board: Leaderboard<K, T>) -> Self {
+        ConcurrentLeaderboard {
+            inner: RwLock::new(Entries {
+                board,
+                keys: HashMap::new(),
+            }),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Entries<K, T>> {
+        self.inner
+            .read()
+            .expect("ConcurrentLeaderboard: lock poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Entries<K, T>> {
+        self.inner
+            .write()
+            .expect("ConcurrentLeaderboard: lock poisoned")
+    }
+
+    /// Inserts an entry with its key, as [`Leaderboard::insert`] does.
+    ///
+    /// If the entry is already on the board it is moved to the new key, which counts as
+    /// a new insertion among entries with equal keys. If the move is refused, the entry
+    /// leaves the board.
+    pub fn insert(&self, item: T, key: K) -> Insertion<T> {
+        self.write().insert(item, key)
+    }
+
+    /// Moves an entry that is on the board to a new key, returning `None` if it isn't
+    /// on the board.
+    pub fn update(&self, item: &T, key: K) -> Option<Insertion<T>> {
+        let mut entries = self.write();
+        let (item, _) = entries.keys.get_key_value(item)?;
+        let item = item.clone();
+        Some(entries.insert(item, key))
+    }
+
+    /// Returns the dense rank of an entry, or `None` if it isn't on the board.
+    pub fn rank_of(&self, item: &T) -> Option<Rank> {
+        let entries = self.read();
+        let (key, _) = entries.keys.get(item)?;
+        entries.board.rank_of(key)
+    }
+
+    /// Returns the entries at the first `n` positions with their dense ranks, all read
+    /// from the same state of the board.
+    pub fn snapshot_top(&self, n: usize) -> Vec<(Rank, T)> {
+        self.read()
+            .board
+            .iter()
+            .take(n)
+            .map(|(rank, item)| (rank, item.clone()))
+            .collect()
+    }
+
+    /// Returns the number of entries on the board.
+    pub fn len(&self) -> usize {
+        self.read().board.len()
+    }
+
+    /// Returns `true` if the board has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.read().board.is_empty()
+    }
+
+    /// Converts the board into a [`Ranking`] of its entries.
+    pub fn into_ranking(self) -> Ranking<T> {
+        self.inner
+            .into_inner()
+            .expect("ConcurrentLeaderboard: lock poisoned")
+            .board
+            .into_ranking()
+    }
+}
+
+impl<K: Ord + Clone, T: Eq + Hash + Clone> Default for ConcurrentLeaderboard<K, T> {
+    fn default() -> Self {
+        ConcurrentLeaderboard::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lcg;
+
+    fn entries(board: &Leaderboard<u32, char>) -> Vec<(usize, char)> {
+        board.iter().map(|(rank, &c)| (rank.0, c)).collect()
+    }
+
+    fn accepted(rank: usize, evicted: &[char]) -> Insertion<char> {
+        Insertion::Accepted {
+            rank: Rank(rank),
+            evicted: evicted.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_unbounded_leaderboard_ranks_inserts() {
+        let mut board = Leaderboard::new();
+        assert_eq!(board.insert('a', 20), accepted(1, &[]));
+        assert_eq!(board.insert('b', 10), accepted(1, &[]));
+        assert_eq!(board.insert('c', 20), accepted(2, &[]));
+        assert_eq!(board.insert('d', 30), accepted(3, &[]));
+        assert_eq!(
+            entries(&board),
+            vec![(1, 'b'), (2, 'a'), (2, 'c'), (3, 'd')]
+        );
+        assert_eq!(board.capacity(), None);
+
+        let ranking = board.into_ranking();
+        assert_eq!(ranking.distinct_ranks(), 3);
+        assert_eq!(ranking.items(), &['b', 'a', 'c', 'd']);
+    }
+
+    #[test]
+    fn test_keep_all_ties_at_cutoff() {
+        let mut board = Leaderboard::with_capacity_by_rank(3, CutoffTies::KeepAll);
+        board.insert('a', 10);
+        board.insert('b', 20);
+        board.insert('c', 30);
+        // Ties with the last position are kept beyond the capacity.
+        assert_eq!(board.insert('d', 30), accepted(3, &[]));
+        assert_eq!(board.len(), 4);
+        assert_eq!(
+            board.insert('e', 40),
+            Insertion::Refused {
+                item: 'e',
+                rank: Rank(4),
+                position: 5,
+            }
+        );
+
+        // 'f' pushes the whole tied group past the cutoff.
+        assert_eq!(board.insert('f', 15), accepted(2, &['c', 'd']));
+        assert_eq!(entries(&board), vec![(1, 'a'), (2, 'f'), (3, 'b')]);
+
+        // A key equal to the last position joins it, and nothing is evicted.
+        assert_eq!(board.insert('g', 20), accepted(3, &[]));
+        assert_eq!(board.len(), 4);
+        assert_eq!(board.insert('h', 5), accepted(1, &['b', 'g']));
+        assert_eq!(entries(&board), vec![(1, 'h'), (2, 'a'), (3, 'f')]);
+    }
+
+    #[test]
+    fn test_evict_newest_at_cutoff() {
+        let mut board = Leaderboard::with_capacity_by_rank(3, CutoffTies::EvictNewest);
+        board.insert('a', 10);
+        board.insert('b', 30);
+        board.insert('c', 30);
+        // Tied with the last position but inserted after it.
+        assert_eq!(
+            board.insert('d', 30),
+            Insertion::Refused {
+                item: 'd',
+                rank: Rank(2),
+                position: 4,
+            }
+        );
+        // The newest of the tied entries goes first.
+        assert_eq!(board.insert('e', 20), accepted(2, &['c']));
+        assert_eq!(entries(&board), vec![(1, 'a'), (2, 'e'), (3, 'b')]);
+        assert_eq!(board.insert('f', 20), accepted(2, &['b']));
+        assert_eq!(entries(&board), vec![(1, 'a'), (2, 'e'), (2, 'f')]);
+        assert_eq!(board.len(), 3);
+    }
+
+    #[test]
+    fn test_zero_capacity_refuses_everything() {
+        for ties in [CutoffTies::KeepAll, CutoffTies::EvictNewest] {
+            let mut board = Leaderboard::with_capacity_by_rank(0, ties);
+            assert_eq!(
+                board.insert('a', 1),
+                Insertion::Refused {
+                    item: 'a',
+                    rank: Rank(1),
+                    position: 1,
+                }
+            );
+            assert!(board.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_remove_and_rank_of() {
+        let mut board = Leaderboard::new();
+        board.insert('a', 10);
+        board.insert('b', 20);
+        board.insert('c', 20);
+        board.insert('d', 30);
+        assert_eq!(board.rank_of(&30), Some(Rank(3)));
+        assert_eq!(board.rank_of(&25), None);
+
+        assert_eq!(board.remove(&'b', &20), Some('b'));
+        assert_eq!(board.remove(&'b', &20), None);
+        assert_eq!(board.remove(&'d', &20), None);
+        assert_eq!(board.remove(&'c', &20), Some('c'));
+        assert_eq!(board.rank_of(&30), Some(Rank(2)));
+        assert_eq!(entries(&board), vec![(1, 'a'), (2, 'd')]);
+    }
+
+    #[test]
+    fn test_ranks_match_linear_walk() {
+        for (seed, capacity, ties) in [
+            (1, None, CutoffTies::KeepAll),
+            (2, Some(40), CutoffTies::KeepAll),
+            (3, Some(40), CutoffTies::EvictNewest),
+        ] {
+            let mut board = Leaderboard::new();
+            board.capacity = capacity;
+            board.ties = ties;
+            for (i, state) in lcg(seed).take(3000).enumerate() {
+                let key = ((state >> 33) % 60) as u32;
+                let rank = Rank(board.groups.range(..key).count() + 1);
+                let ahead: usize = board.groups.range(..key).map(|(_, g)| g.len()).sum();
+                let tied = board.groups.get(&key).map_or(0, BTreeMap::len);
+                if (state >> 60) % 4 == 0 {
+                    if let Some(&item) = board.groups.get(&key).and_then(|g| g.values().next()) {
+                        board.remove(&item, &key);
+                    }
+                } else {
+                    match board.insert(i, key) {
+                        Insertion::Accepted { rank: got, .. } => assert_eq!(got, rank),
+                        Insertion::Refused {
+                            rank: got,
+                            position,
+                            ..
+                        } => assert_eq!((got, position), (rank, ahead + tied + 1)),
+                    }
+                }
+                let probe = ((state >> 45) % 64) as u32;
+                let expected = board
+                    .groups
+                    .contains_key(&probe)
+                    .then(|| Rank(board.groups.range(..probe).count() + 1));
+                assert_eq!(board.rank_of(&probe), expected);
+                assert_eq!(board.counts.count_entries_below(&u32::MAX), board.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrent_leaderboard_moves_entries() {
+        let board = ConcurrentLeaderboard::with_capacity_by_rank(2, CutoffTies::EvictNewest);
+        board.insert('a', 10);
+        board.insert('b', 20);
+        assert_eq!(board.insert('c', 5), accepted(1, &['b']));
+        assert_eq!(board.rank_of(&'b'), None);
+        assert_eq!(board.update(&'b', 1), None);
+
+        // Inserting an entry already on the board moves it.
+        assert_eq!(board.insert('c', 15), accepted(2, &[]));
+        assert_eq!(board.len(), 2);
+        assert_eq!(board.snapshot_top(5), vec![(Rank(1), 'a'), (Rank(2), 'c')]);
+
+        assert_eq!(board.insert('d', 12), accepted(2, &['c']));
+        assert_eq!(board.rank_of(&'c'), None);
+        assert_eq!(board.into_ranking().items(), &['a', 'd']);
+
+        // A move past the cutoff takes the entry off the board.
+        let board = ConcurrentLeaderboard::with_capacity_by_rank(2, CutoffTies::KeepAll);
+        board.insert('a', 10);
+        board.insert('b', 20);
+        board.insert('c', 20);
+        assert_eq!(
+            board.update(&'a', 30),
+            Some(Insertion::Refused {
+                item: 'a',
+                rank: Rank(2),
+                position: 3,
+            })
+        );
+        assert_eq!(board.rank_of(&'a'), None);
+        assert_eq!(board.snapshot_top(5), vec![(Rank(1), 'b'), (Rank(1), 'c')]);
+
+        // A tied entry that moves to its own key goes last among the tied entries.
+        let board = ConcurrentLeaderboard::new();
+        for c in ['a', 'b', 'c', 'd'] {
+            board.insert(c, 10);
+        }
+        assert_eq!(board.update(&'b', 10), Some(accepted(1, &[])));
+        assert_eq!(board.into_ranking().items(), &['a', 'c', 'd', 'b']);
+    }
+
+    #[test]
+    fn test_concurrent_leaderboard_matches_sequential_replay() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<ConcurrentLeaderboard<u32, u32>>();
+
+        // Each thread owns its own entries, so the final key of every entry doesn't
+        // depend on how the threads interleave.
+        let ops: Vec<Vec<(u32, u32, bool)>> = (0..4u64)
+            .map(|thread| {
+                lcg(thread + 1)
+                    .take(500)
+                    .map(|state| {
+                        let item = thread as u32 * 100 + (state >> 33) as u32 % 25;
+                        let key = (state >> 40) as u32 % 40;
+                        (item, key, (state >> 60) % 2 == 0)
+                    })
+                    .collect()
+            })
+            .collect();
+        let apply = |board: &ConcurrentLeaderboard<u32, u32>, ops: &[(u32, u32, bool)]| {
+            for &(item, key, update) in ops {
+                if update {
+                    board.update(&item, key);
+                } else {
+                    board.insert(item, key);
+                }
+                board.rank_of(&item);
+                board.snapshot_top(3);
+            }
+        };
+
+        let concurrent = ConcurrentLeaderboard::new();
+        std::thread::scope(|s| {
+            for thread_ops in &ops {
+                s.spawn(|| apply(&concurrent, thread_ops));
+            }
+        });
+        let sequential = ConcurrentLeaderboard::new();
+        for thread_ops in &ops {
+            apply(&sequential, thread_ops);
+        }
+
+        // Ties may be ordered differently, but every entry has the same rank.
+        let sorted = |board: &ConcurrentLeaderboard<u32, u32>| {
+            let mut entries = board.snapshot_top(usize::MAX);
+            entries.sort();
+            entries
+        };
+        assert_eq!(concurrent.len(), sequential.len());
+        assert_eq!(sorted(&concurrent), sorted(&sequential));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut board = Leaderboard::with_capacity_by_rank(3, CutoffTies::EvictNewest);
+        for (c, key) in [('a', 20), ('b', 10), ('c', 20), ('d', 5)] {
+            board.insert(c, key);
+        }
+        let saved = serde_json::to_string(&board.snapshot()).unwrap();
+        let mut restored: Leaderboard<u32, char> =
+            Leaderboard::restore(serde_json::from_str(&saved).unwrap()).unwrap();
+        assert_eq!(entries(&restored), entries(&board));
+        assert_eq!(restored.len(), 3);
+        for key in [1, 5, 10, 15, 20] {
+            assert_eq!(restored.rank_of(&key), board.rank_of(&key));
+        }
+
+        // The capacity and tie policy come back too.
+        assert_eq!(restored.insert('e', 1), board.insert('e', 1));
+        assert_eq!(restored.insert('f', 10), board.insert('f', 10));
+        assert_eq!(entries(&restored), entries(&board));
+        for key in [1, 5, 10, 15, 20] {
+            assert_eq!(restored.rank_of(&key), board.rank_of(&key));
+        }
+
+        let empty: Leaderboard<u32, char> = Leaderboard::new();
+        assert!(Leaderboard::restore(empty.snapshot()).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_restore_rejects_corrupted_snapshots() {
+        let restore = |json: &str| {
+            let snapshot: LeaderboardSnapshot<u32, String> = serde_json::from_str(json).unwrap();
+            Leaderboard::restore(snapshot).map(|board| board.len())
+        };
+        let snapshot = |version: u32, capacity: &str, groups: &str| {
+            format!(
+                r#"{{"version":{version},"capacity":{capacity},"ties":"KeepAll","groups":{groups}}}"#
+            )
+        };
+        assert_eq!(
+            restore(&snapshot(1, "null", r#"[[1,["a"]],[2,["b","c"]]]"#)),
+            Ok(3)
+        );
+        assert_eq!(
+            restore(&snapshot(1, "null", r#"[[1,["a"]],[2,["b","a"]]]"#)),
+            Err(SnapshotError::DuplicateEntry { group: 1 })
+        );
+        assert_eq!(
+            restore(&snapshot(1, "null", r#"[[2,["a"]],[2,["b"]]]"#)),
+            Err(SnapshotError::UnorderedKeys { group: 1 })
+        );
+        assert_eq!(
+            restore(&snapshot(1, "null", r#"[[1,[]]]"#)),
+            Err(SnapshotError::EmptyGroup { group: 0 })
+        );
+        assert_eq!(
+            restore(&snapshot(1, "1", r#"[[1,["a"]],[2,["b"]]]"#)),
+            Err(SnapshotError::OverCapacity)
+        );
+        assert_eq!(
+            restore(&snapshot(2, "null", "[]")),
+            Err(SnapshotError::UnsupportedVersion { found: 2 })
+        );
+    }
+
+    #[test]
+    fn test_insert_at_top_pushes_tenth_out_of_top_ten() {
+        let mut board = Leaderboard::new();
+        board.watch_threshold(Rank(10));
+        for i in 1..=12 {
+            board.insert(i, i * 10);
+        }
+        let (insertion, report) = board.insert_with_report(0, 5);
+        assert_eq!(
+            insertion,
+            Insertion::Accepted {
+                rank: Rank(1),
+                evicted: vec![],
+            }
+        );
+        assert_eq!(
+            report.crossings(),
+            &[
+                RankCrossing {
+                    item: 0,
+                    threshold: Rank(10),
+                    old: None,
+                    new: Some(Rank(1)),
+                },
+                RankCrossing {
+                    item: 10,
+                    threshold: Rank(10),
+                    old: Some(Rank(10)),
+                    new: Some(Rank(11)),
+                },
+            ]
+        );
+        assert!(report.crossings()[0].entered());
+        assert!(!report.crossings()[1].entered());
+
+        // Joining a tie shifts no one, so only the new entry crosses.
+        let (_, report) = board.insert_with_report(13, 10);
+        let crossed: Vec<u32> = report.crossings().iter().map(|c| c.item).collect();
+        assert_eq!(crossed, vec![13]);
+        let (_, report) = board.insert_with_report(14, 200);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_update_and_remove_report_crossings() {
+        let mut board = Leaderboard::with_capacity_by_rank(3, CutoffTies::EvictNewest);
+        board.watch_threshold(Rank(1));
+        board.watch_threshold(Rank(2));
+        board.insert('a', 10);
+        board.insert('b', 20);
+        board.insert('c', 30);
+        let crossed = |report: MutationReport<char>| -> Vec<(char, usize, bool)> {
+            report
+                .crossings()
+                .iter()
+                .map(|c| (c.item, c.threshold.0, c.entered()))
+                .collect()
+        };
+
+        // 'c' jumps to the top, pushing 'a' below 1 and 'b' below 2.
+        let (_, report) = board.update_with_report(&'c', &30, 5);
+        assert_eq!(
+            crossed(report),
+            vec![
+                ('c', 1, true),
+                ('a', 1, false),
+                ('c', 2, true),
+                ('b', 2, false)
+            ]
+        );
+
+        // Evicted entries are reported when they were above a threshold.
+        let (_, report) = board.insert_with_report('d', 1);
+        assert_eq!(
+            crossed(report),
+            vec![
+                ('d', 1, true),
+                ('c', 1, false),
+                ('d', 2, true),
+                ('a', 2, false)
+            ]
+        );
+        assert_eq!(entries(&board), vec![(1, 'd'), (2, 'c'), (3, 'a')]);
+
+        let (removed, report) = board.remove_with_report(&'d', &1);
+        assert_eq!(removed, Some('d'));
+        assert_eq!(
+            crossed(report),
+            vec![
+                ('d', 1, false),
+                ('c', 1, true),
+                ('d', 2, false),
+                ('a', 2, true)
+            ]
+        );
+        assert_eq!(
+            board.update_with_report(&'z', &1, 2),
+            (None, MutationReport::default())
+        );
+    }
+
+    #[test]
+    fn test_report_evictions_above_threshold() {
+        let mut board = Leaderboard::with_capacity_by_rank(3, CutoffTies::EvictNewest);
+        board.watch_threshold(Rank(10));
+        board.insert('a', 10);
+        board.insert('b', 20);
+        board.insert('c', 30);
+
+        let (insertion, report) = board.insert_with_report('d', 5);
+        assert_eq!(insertion, accepted(1, &['c']));
+        assert_eq!(
+            report.crossings(),
+            &[
+                RankCrossing {
+                    item: 'd',
+                    threshold: Rank(10),
+                    old: None,
+                    new: Some(Rank(1)),
+                },
+                RankCrossing {
+                    item: 'c',
+                    threshold: Rank(10),
+                    old: Some(Rank(3)),
+                    new: None,
+                },
+            ]
+        );
+
+        // A whole tied group evicted from a board that keeps ties.
+        let mut board = Leaderboard::with_capacity_by_rank(2, CutoffTies::KeepAll);
+        board.watch_threshold(Rank(5));
+        board.insert('a', 10);
+        board.insert('b', 20);
+        board.insert('c', 20);
+        let (insertion, report) = board.update_with_report(&'a', &10, 15);
+        assert_eq!(insertion, Some(accepted(1, &[])));
+        assert!(report.is_empty());
+        let (insertion, report) = board.insert_with_report('d', 1);
+        assert_eq!(insertion, accepted(1, &['b', 'c']));
+        let exited: Vec<(char, Option<Rank>)> = report
+            .crossings()
+            .iter()
+            .filter(|c| !c.entered())
+            .map(|c| (c.item, c.old))
+            .collect();
+        assert_eq!(exited, vec![('b', Some(Rank(2))), ('c', Some(Rank(2)))]);
+    }
+}
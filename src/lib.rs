@@ -23,9 +23,75 @@
 //!
 //! assert_eq!(ranked, expected);
 //! ```
+//!
+//! # Tie order
+//!
+//! Items with equal keys keep their input order in the output of every method that
+//! sorts, since the sorts are stable, unless its documentation says otherwise. This is
+//! part of the API contract. The ones that order ties some other way, such as
+//! `rank_by_unstable` for speed or `sample::rank_by_random_ties` at random, say so.
+//!
+//! # Features
+//!
+//! - `arrow`: ranking the values of an Arrow array, in the `arrow` module.
+//! - `csv`: ranking the rows of a CSV file by a column, in the `csv` module.
+//! - `futures`: ranking the items of an async `Stream`, in the `stream` module.
+//! - `rand`: random sampling weighted by rank and random tie-breaking, in the `sample`
+//!   module.
+//! - `rayon`: ranking large collections in parallel, in the `parallel` module.
+//! - `serde`: writing ranked items as JSON or JSON Lines, in the `json` module, and
+//!   serializing [`RankCheckpoint`]s and leaderboard snapshots.
 
+pub mod aggregate;
+pub mod array;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod cache;
+pub mod compare;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod eval;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod leaderboard;
+pub mod lookup;
+pub mod map;
+pub mod online;
+mod order_stat;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod points;
 pub mod rank;
+pub mod ranking;
+#[cfg(feature = "rand")]
+pub mod sample;
+pub mod seeding;
+pub mod selection;
+pub mod sql;
+#[cfg(feature = "futures")]
+pub mod stream;
+pub mod table;
+#[cfg(test)]
+mod test_support;
+pub mod tier;
+pub mod verify;
 
+pub use lookup::PercentileMethod;
+pub use lookup::PercentileOutOfRange;
+pub use lookup::RankTable;
+pub use rank::GroupStats;
+pub use rank::IneligiblePlacement;
+pub use rank::ProgressRankedBy;
 pub use rank::Rank;
+pub use rank::RankCheckpoint;
+pub use rank::RankItem;
 pub use rank::RankedBy;
 pub use rank::RankedExt;
+pub use rank::RankingStrategy;
+pub use rank::RelativeSide;
+pub use rank::ScatterConflict;
+pub use rank::TieTracker;
+pub use ranking::RankChange;
+pub use ranking::RankDelta;
+pub use ranking::RankSummary;
+pub use ranking::Ranking;
@@ -0,0 +1,439 @@
+//! Looking up the rank a key has in a reference population.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::rank::Rank;
+use crate::ranking::Ranking;
+
+/// The dense ranks of a set of keys, for looking up the rank of any key in O(log n).
+///
+/// The table stores the distinct keys in ascending order along with how many items have
+/// each; the rank of a key is its position among them, as
+/// [`rank_by`](crate::RankedExt::rank_by) would give it. Use
+/// [`RankedExt::annotate_ranks`](crate::RankedExt::annotate_ranks) to look up the ranks
+/// of a whole iterator of items.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::RankTable;
+///
+/// let table: RankTable<u32> = [50, 10, 30, 10].into_iter().collect();
+///
+/// assert_eq!(table.rank(&30), Some(Rank(2)));
+/// assert_eq!(table.rank(&40), None);
+/// assert_eq!(table.floor_rank(&40), Some(Rank(2)));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RankTable<K> {
+    keys: Vec<K>,
+    /// The number of items with a key up to and including each key.
+    cumulative: Vec<usize>,
+}
+
+impl<K: Ord> RankTable<K> {
+    /// Builds a table from the keys of a population, in any order and with duplicates.
+    pub fn from_keys(keys: impl IntoIterator<Item = K>) -> Self {
+        let mut keys: Vec<K> = keys.into_iter().collect();
+        keys.sort();
+        let mut cumulative = Vec::new();
+        for (i, pair) in keys.windows(2).enumerate() {
+            if pair[0] != pair[1] {
+                cumulative.push(i + 1);
+            }
+        }
+        if !keys.is_empty() {
+            cumulative.push(keys.len());
+        }
+        keys.dedup();
+        RankTable { keys, cumulative }
+    }
+
+    /// Builds a table from a ranking, calling `key_fn` on the first item of each group.
+    ///
+    /// `key_fn` must return the keys the ranking was sorted by, in ascending order, so
+    /// that the ranks in the table are those of the ranking. For a ranking by a
+    /// [`Reverse`](std::cmp::Reverse) key, return the reversed key.
+    pub fn from_ranking<T>(ranking: &Ranking<T>, mut key_fn: impl FnMut(&T) -> K) -> Self {
+        let mut keys = Vec::with_capacity(ranking.distinct_ranks());
+        let mut cumulative = Vec::with_capacity(ranking.distinct_ranks());
+        let mut total = 0;
+        for (_, group) in ranking.groups() {
+            keys.push(key_fn(&group[0]));
+            total += group.len();
+            cumulative.push(total);
+        }
+        debug_assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        RankTable { keys, cumulative }
+    }
+
+    /// Returns the rank of `key`, or `None` if the population doesn't contain it.
+    pub fn rank(&self, key: &K) -> Option<Rank> {
+        self.keys.binary_search(key).ok().map(|i| Rank(i + 1))
+    }
+
+    /// Returns the rank of the greatest key in the population that is less than or
+    /// equal to `key`, or `None` if `key` is below all of them.
+    ///
+    /// This maps a score to the rank of the highest threshold it reaches.
+    pub fn floor_rank(&self, key: &K) -> Option<Rank> {
+        match self.keys.partition_point(|k| k <= key) {
+            0 => None,
+            i => Some(Rank(i)),
+        }
+    }
+
+    /// Returns the rank `key` would get if it were added to the population, without
+    /// adding it.
+    ///
+    /// This is one plus the number of distinct keys less than `key`. A key equal to an
+    /// existing one gets that key's rank. A key strictly between two existing keys gets
+    /// the rank of the greater one, which would move one rank down. A key below all
+    /// others gets rank 1 and one above all others gets
+    /// [`len`](RankTable::len) + 1. The table is ascending, so rank 1 is the smallest
+    /// key; for a ranking where the largest key comes first, build the table and query
+    /// it with [`Reverse`](std::cmp::Reverse) keys, and "below" means "better".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    ///
+    /// use librank::{Rank, RankTable};
+    ///
+    /// // Highest score first.
+    /// let table: RankTable<Reverse<u32>> = [90, 80, 80, 70].map(Reverse).into_iter().collect();
+    ///
+    /// assert_eq!(table.would_rank(&Reverse(80)), Rank(2));
+    /// assert_eq!(table.would_rank(&Reverse(87)), Rank(2));
+    /// assert_eq!(table.would_rank(&Reverse(99)), Rank(1));
+    /// assert_eq!(table.would_rank(&Reverse(10)), Rank(4));
+    /// ```
+    pub fn would_rank(&self, key: &K) -> Rank {
+        Rank(self.keys.partition_point(|k| k < key) + 1)
+    }
+
+    /// Returns how many items of the population `key` would beat, that is the number of
+    /// items with a greater key, which rank after it.
+    ///
+    /// Items with an equal key are tied with it rather than beaten.
+    pub fn would_displace(&self, key: &K) -> usize {
+        let at_or_below = match self.keys.partition_point(|k| k <= key) {
+            0 => 0,
+            i => self.cumulative[i - 1],
+        };
+        self.item_count() - at_or_below
+    }
+
+    /// Returns the key at percentile `p` of the population by the nearest-rank method:
+    /// the smallest key that at least a fraction `p` of the items are less than or
+    /// equal to.
+    ///
+    /// `p` is a fraction between 0 and 1, so the 95th percentile is `0.95`; 0 gives the
+    /// smallest key and 1 the largest. Duplicate keys count once per item. Returns
+    /// `Ok(None)` if the table is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PercentileOutOfRange`] if `p` is not between 0 and 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankTable;
+    ///
+    /// let table: RankTable<u32> = [15, 20, 35, 40, 50].into_iter().collect();
+    ///
+    /// assert_eq!(table.value_at_percentile(0.3), Ok(Some(&20)));
+    /// assert_eq!(table.value_at_percentile(0.5), Ok(Some(&35)));
+    /// assert!(table.value_at_percentile(1.5).is_err());
+    /// ```
+    pub fn value_at_percentile(&self, p: f64) -> Result<Option<&K>, PercentileOutOfRange> {
+        check_percentile(p)?;
+        let n = self.item_count();
+        if n == 0 {
+            return Ok(None);
+        }
+        // `p * n` can land just above an integer, as 0.07 * 100 does, so products that
+        // close to an integer are rounded to it rather than taken up to the next one.
+        let exact = p * n as f64;
+        let nearest = exact.round();
+        let ordinal = if (exact - nearest).abs() < 1e-9 {
+            nearest
+        } else {
+            exact.ceil()
+        };
+        let ordinal = (ordinal as usize).clamp(1, n);
+        Ok(Some(
+            &self.keys[self.cumulative.partition_point(|&c| c < ordinal)],
+        ))
+    }
+
+    /// Returns the value at percentile `p` of a population of numeric keys, computed
+    /// with the given method.
+    ///
+    /// [`PercentileMethod::NearestRank`] gives the same key as
+    /// [`value_at_percentile`](RankTable::value_at_percentile), converted to `f64`.
+    /// Returns `Ok(None)` if the table is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PercentileOutOfRange`] if `p` is not between 0 and 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{PercentileMethod, RankTable};
+    ///
+    /// let table: RankTable<u32> = [15, 20, 35, 40, 50].into_iter().collect();
+    ///
+    /// assert_eq!(table.percentile(0.4, PercentileMethod::NearestRank), Ok(Some(20.0)));
+    /// assert_eq!(table.percentile(0.4, PercentileMethod::Linear), Ok(Some(29.0)));
+    /// ```
+    pub fn percentile(
+        &self,
+        p: f64,
+        method: PercentileMethod,
+    ) -> Result<Option<f64>, PercentileOutOfRange>
+    where
+        K: Copy + Into<f64>,
+    {
+        match method {
+            PercentileMethod::NearestRank => Ok(self.value_at_percentile(p)?.map(|&k| k.into())),
+            PercentileMethod::Linear => {
+                check_percentile(p)?;
+                let n = self.item_count();
+                if n == 0 {
+                    return Ok(None);
+                }
+                let position = p * (n - 1) as f64;
+                let below = position.floor() as usize;
+                let lower: f64 = self.key_at(below).into();
+                if below + 1 == n {
+                    return Ok(Some(lower));
+                }
+                let upper: f64 = self.key_at(below + 1).into();
+                Ok(Some(lower + (position - below as f64) * (upper - lower)))
+            }
+        }
+    }
+
+    /// Returns the key of the item at a 0-based index of the sorted population.
+    fn key_at(&self, index: usize) -> K
+    where
+        K: Copy,
+    {
+        self.keys[self.cumulative.partition_point(|&c| c <= index)]
+    }
+
+    /// Returns the number of items in the population, counting duplicate keys.
+    pub fn item_count(&self) -> usize {
+        self.cumulative.last().copied().unwrap_or(0)
+    }
+
+    /// Returns the number of distinct keys, which is also the highest rank.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if the table contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the distinct keys in rank order.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+}
+
+/// How [`RankTable::percentile`] picks the value at a percentile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PercentileMethod {
+    /// The key of the item whose position is the percentile of the population rounded
+    /// up, as in [`RankTable::value_at_percentile`]. The result is always one of the
+    /// keys.
+    NearestRank,
+    /// Interpolates linearly between the keys of the two items around position
+    /// `p * (n - 1)`. This is the default of NumPy and of spreadsheet `PERCENTILE`
+    /// functions.
+    Linear,
+}
+
+/// The error returned when a percentile is not between 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PercentileOutOfRange(pub f64);
+
+impl fmt::Display for PercentileOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "percentile {} is not between 0 and 1", self.0)
+    }
+}
+
+impl Error for PercentileOutOfRange {}
+
+fn check_percentile(p: f64) -> Result<(), PercentileOutOfRange> {
+    if (0.0..=1.0).contains(&p) {
+        Ok(())
+    } else {
+        Err(PercentileOutOfRange(p))
+    }
+}
+
+impl<K: Ord> FromIterator<K> for RankTable<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        RankTable::from_keys(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+
+    #[test]
+    fn test_rank_table_lookups() {
+        let table = RankTable::from_keys([40, 10, 20, 40, 30]);
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.rank(&10), Some(Rank(1)));
+        assert_eq!(table.rank(&40), Some(Rank(4)));
+        assert_eq!(table.rank(&25), None);
+
+        assert_eq!(table.floor_rank(&5), None);
+        assert_eq!(table.floor_rank(&10), Some(Rank(1)));
+        assert_eq!(table.floor_rank(&25), Some(Rank(2)));
+        assert_eq!(table.floor_rank(&99), Some(Rank(4)));
+        assert_eq!(RankTable::<i32>::from_keys([]).floor_rank(&1), None);
+    }
+
+    #[test]
+    fn test_rank_table_from_ranking_matches_rank_by() {
+        let population = vec![("a", 7), ("b", 3), ("c", 7), ("d", 5)];
+        let ranking: Ranking<(&str, i32)> = population.into_iter().rank_by(|&(_, s)| s).collect();
+        let table = RankTable::from_ranking(&ranking, |&(_, s)| s);
+        for (rank, &(_, score)) in ranking.iter() {
+            assert_eq!(table.rank(&score), Some(rank));
+        }
+        assert_eq!(table.keys(), &[3, 5, 7]);
+    }
+
+    #[test]
+    fn test_would_rank_boundaries() {
+        let table = RankTable::from_keys([20, 40, 40, 60]);
+        assert_eq!(table.item_count(), 4);
+        // Equal to existing keys.
+        assert_eq!(table.would_rank(&20), Rank(1));
+        assert_eq!(table.would_rank(&40), Rank(2));
+        assert_eq!(table.would_rank(&60), Rank(3));
+        // Between keys.
+        assert_eq!(table.would_rank(&30), Rank(2));
+        assert_eq!(table.would_rank(&59), Rank(3));
+        // Beyond either end.
+        assert_eq!(table.would_rank(&i32::MIN), Rank(1));
+        assert_eq!(table.would_rank(&61), Rank(4));
+        assert_eq!(RankTable::<i32>::from_keys([]).would_rank(&0), Rank(1));
+    }
+
+    #[test]
+    fn test_would_displace() {
+        let table = RankTable::from_keys([20, 40, 40, 60]);
+        assert_eq!(table.would_displace(&10), 4);
+        assert_eq!(table.would_displace(&20), 3);
+        assert_eq!(table.would_displace(&30), 3);
+        assert_eq!(table.would_displace(&40), 1);
+        assert_eq!(table.would_displace(&60), 0);
+        assert_eq!(table.would_displace(&99), 0);
+
+        let ranking: Ranking<i32> = vec![40, 20, 60, 40].into_iter().rank_by(|&x| x).collect();
+        assert_eq!(RankTable::from_ranking(&ranking, |&x| x), table);
+    }
+
+    #[test]
+    fn test_value_at_percentile_nearest_rank() {
+        let table = RankTable::from_keys([40, 15, 50, 35, 20]);
+        let at = |p| table.value_at_percentile(p).unwrap().copied();
+        assert_eq!(at(0.0), Some(15));
+        assert_eq!(at(0.05), Some(15));
+        assert_eq!(at(0.3), Some(20));
+        assert_eq!(at(0.4), Some(20));
+        assert_eq!(at(0.5), Some(35));
+        assert_eq!(at(1.0), Some(50));
+
+        // Duplicates count once per item.
+        let table = RankTable::from_keys([1, 2, 2, 2, 9]);
+        assert_eq!(table.value_at_percentile(0.5), Ok(Some(&2)));
+        assert_eq!(table.value_at_percentile(0.8), Ok(Some(&2)));
+        assert_eq!(table.value_at_percentile(0.81), Ok(Some(&9)));
+        assert_eq!(
+            RankTable::<i32>::from_keys([]).value_at_percentile(0.5),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_value_at_percentile_exact_products() {
+        // The f64 products of these with 100 are slightly above the integer.
+        let table = RankTable::from_keys(1..=100u32);
+        for (p, ordinal) in [(0.07, 7), (0.14, 14), (0.28, 28), (0.55, 55)] {
+            assert!(p * 100.0 > ordinal as f64);
+            assert_eq!(table.value_at_percentile(p), Ok(Some(&ordinal)));
+            assert_eq!(
+                table.percentile(p, PercentileMethod::NearestRank),
+                Ok(Some(ordinal as f64))
+            );
+        }
+        assert_eq!(table.value_at_percentile(0.071), Ok(Some(&8)));
+    }
+
+    #[test]
+    fn test_percentile_linear() {
+        let table = RankTable::from_keys([15u32, 20, 35, 40, 50]);
+        let at = |p| table.percentile(p, PercentileMethod::Linear).unwrap();
+        assert_eq!(at(0.0), Some(15.0));
+        assert_eq!(at(0.25), Some(20.0));
+        assert_eq!(at(0.4), Some(29.0));
+        assert_eq!(at(1.0), Some(50.0));
+
+        let table = RankTable::from_keys([1u8, 2, 2, 2, 9]);
+        let value = table
+            .percentile(0.9, PercentileMethod::Linear)
+            .unwrap()
+            .unwrap();
+        assert!((value - 6.2).abs() < 1e-9);
+        assert_eq!(
+            table.percentile(0.5, PercentileMethod::NearestRank),
+            Ok(Some(2.0))
+        );
+        assert_eq!(
+            RankTable::<u8>::from_keys([]).percentile(0.5, PercentileMethod::Linear),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_percentile_out_of_range() {
+        let table = RankTable::from_keys([1u8, 2, 3]);
+        assert_eq!(
+            table.value_at_percentile(-0.1),
+            Err(PercentileOutOfRange(-0.1))
+        );
+        assert_eq!(
+            table.value_at_percentile(1.01),
+            Err(PercentileOutOfRange(1.01))
+        );
+        assert!(table.value_at_percentile(f64::NAN).is_err());
+        assert!(table.percentile(2.0, PercentileMethod::Linear).is_err());
+        // Out-of-range percentiles are rejected even when the table is empty.
+        assert!(
+            RankTable::<u8>::from_keys([])
+                .value_at_percentile(7.0)
+                .is_err()
+        );
+        assert_eq!(
+            PercentileOutOfRange(1.5).to_string(),
+            "percentile 1.5 is not between 0 and 1"
+        );
+    }
+}
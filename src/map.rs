@@ -0,0 +1,158 @@
+//! Ranking the entries of a map by their values.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+use crate::rank::{Rank, rank_keyed};
+
+/// An extension trait for ranking the entries of [`HashMap`] and [`BTreeMap`] by their
+/// values.
+///
+/// Entries with equal ranking keys are ordered by their map key, so the result doesn't
+/// depend on the iteration order of a `HashMap`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use librank::Rank;
+/// use librank::map::MapRankExt;
+///
+/// let scores = HashMap::from([("cy", 7), ("ann", 9), ("bob", 7)]);
+///
+/// assert_eq!(
+///     scores.rank_values_desc(),
+///     vec![(Rank(1), &"ann", &9), (Rank(2), &"bob", &7), (Rank(2), &"cy", &7)]
+/// );
+/// ```
+pub trait MapRankExt<K, V> {
+    /// Ranks the entries by a key derived from each entry, smallest first, as
+    /// [`rank_by`](crate::RankedExt::rank_by) does. Wrap the key in [`Reverse`] to rank the
+    /// largest first.
+    fn rank_values_by<'a, F, X>(&'a self, f: F) -> Vec<(Rank, &'a K, &'a V)>
+    where
+        F: FnMut(&'a K, &'a V) -> X,
+        X: Ord;
+
+    /// Ranks the entries by value, smallest first, like
+    /// [`rank_by`](crate::RankedExt::rank_by).
+    ///
+    /// This is the same as [`rank_values_asc`](MapRankExt::rank_values_asc). For maps of
+    /// scores, where the highest score should get rank 1, use
+    /// [`rank_values_desc`](MapRankExt::rank_values_desc).
+    fn rank_values(&self) -> Vec<(Rank, &K, &V)>
+    where
+        V: Ord,
+    {
+        self.rank_values_asc()
+    }
+
+    /// Ranks the entries by value, largest first: the highest score gets rank 1.
+    fn rank_values_desc(&self) -> Vec<(Rank, &K, &V)>
+    where
+        V: Ord,
+    {
+        self.rank_values_by(|_, value| Reverse(value))
+    }
+
+    /// Ranks the entries by value, smallest first.
+    fn rank_values_asc(&self) -> Vec<(Rank, &K, &V)>
+    where
+        V: Ord,
+    {
+        self.rank_values_by(|_, value| value)
+    }
+}
+
+impl<K: Ord, V, S: BuildHasher> MapRankExt<K, V> for HashMap<K, V, S> {
+    fn rank_values_by<'a, F, X>(&'a self, f: F) -> Vec<(Rank, &'a K, &'a V)>
+    where
+        F: FnMut(&'a K, &'a V) -> X,
+        X: Ord,
+    {
+        let mut entries: Vec<(&K, &V)> = self.iter().collect();
+        entries.sort_unstable_by_key(|&(key, _)| key);
+        rank_entries(entries, f)
+    }
+}
+
+impl<K: Ord, V> MapRankExt<K, V> for BTreeMap<K, V> {
+    fn rank_values_by<'a, F, X>(&'a self, f: F) -> Vec<(Rank, &'a K, &'a V)>
+    where
+        F: FnMut(&'a K, &'a V) -> X,
+        X: Ord,
+    {
+        rank_entries(self.iter().collect(), f)
+    }
+}
+
+/// Ranks entries sorted by map key, keeping that order among ties.
+fn rank_entries<'a, K, V, F, X>(entries: Vec<(&'a K, &'a V)>, mut f: F) -> Vec<(Rank, &'a K, &'a V)>
+where
+    F: FnMut(&'a K, &'a V) -> X,
+    X: Ord,
+{
+    let keyed = entries
+        .into_iter()
+        .map(|(k, v)| (f(k, v), (k, v)))
+        .collect();
+    rank_keyed(keyed)
+        .map(|(rank, (k, v))| (rank, k, v))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_values_ties_ordered_by_map_key() {
+        let names = ["eve", "bob", "dan", "ann", "cy", "fay"];
+        let scores = [3, 5, 3, 5, 1, 3];
+        let expected = vec![
+            (Rank(1), &"ann", &5),
+            (Rank(1), &"bob", &5),
+            (Rank(2), &"dan", &3),
+            (Rank(2), &"eve", &3),
+            (Rank(2), &"fay", &3),
+            (Rank(3), &"cy", &1),
+        ];
+        // Different insertion orders and hashers must not change the tie order.
+        for rotation in 0..names.len() {
+            let mut map = HashMap::new();
+            for i in 0..names.len() {
+                let j = (i + rotation) % names.len();
+                map.insert(names[j], scores[j]);
+            }
+            assert_eq!(map.rank_values_desc(), expected);
+        }
+        let btree: BTreeMap<&str, i32> = names.into_iter().zip(scores).collect();
+        assert_eq!(btree.rank_values_desc(), expected);
+
+        let ascending: Vec<(Rank, &&str)> = btree
+            .rank_values_asc()
+            .into_iter()
+            .map(|(r, k, _)| (r, k))
+            .collect();
+        assert_eq!(ascending[0], (Rank(1), &"cy"));
+        assert_eq!(ascending[1], (Rank(2), &"dan"));
+        assert_eq!(btree.rank_values(), btree.rank_values_asc());
+    }
+
+    #[test]
+    fn test_rank_values_by_derived_key() {
+        let stats = HashMap::from([("x", (10, 4)), ("y", (9, 3)), ("z", (4, 2))]);
+        let by_average: Vec<(Rank, &&str)> = stats
+            .rank_values_by(|_, &(points, games)| Reverse(points / games))
+            .into_iter()
+            .map(|(r, k, _)| (r, k))
+            .collect();
+        assert_eq!(
+            by_average,
+            vec![(Rank(1), &"y"), (Rank(2), &"x"), (Rank(2), &"z")]
+        );
+        assert!(HashMap::<u8, u8>::new().rank_values_desc().is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+//! Rankers that update incrementally as values arrive.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::order_stat::KeyCounts;
+use crate::rank::Rank;
+
+/// Ranks each new key among the last `window_size` keys pushed, itself included.
+///
+/// The window is kept as an order-statistic multiset of keys alongside the keys in
+/// arrival order, so inserting a key, evicting the oldest one and ranking the new key
+/// are each O(log w) expected. The rank is dense: one plus the number of distinct
+/// smaller keys in the window. Until the window fills up, keys are ranked among those
+/// present.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::online::WindowedRanker;
+///
+/// let mut ranker = WindowedRanker::new(3);
+/// let ranks: Vec<Rank> = [5, 3, 8, 1, 8].into_iter().map(|x| ranker.push(x)).collect();
+///
+/// assert_eq!(ranks, vec![Rank(1), Rank(1), Rank(3), Rank(1), Rank(2)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WindowedRanker<K> {
+    window_size: usize,
+    window: VecDeque<K>,
+    counts: KeyCounts<K>,
+}
+
+impl<K> WindowedRanker<K>
+where
+    K: Ord + Clone,
+{
+    /// Creates a ranker over windows of `window_size` keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is 0.
+    pub fn new(window_size: usize) -> Self {
+        assert!(
+            window_size > 0,
+            "WindowedRanker: window size must be positive"
+        );
+        WindowedRanker {
+            window_size,
+            window: VecDeque::with_capacity(window_size.min(1024)),
+            counts: KeyCounts::new(),
+        }
+    }
+
+    /// Pushes a key, evicting the oldest one if the window is full, and returns the
+    /// key's rank in the window.
+    pub fn push(&mut self, key: K) -> Rank {
+        if self.window.len() == self.window_size
+            && let Some(oldest) = self.window.pop_front()
+        {
+            self.counts.remove(&oldest);
+        }
+        let rank = Rank(self.counts.count_below(&key) + 1);
+        self.counts.insert(key.clone());
+        self.window.push_back(key);
+        rank
+    }
+
+    /// Like [`push`](WindowedRanker::push), but passes an item through along with its
+    /// rank.
+    pub fn push_item<T>(&mut self, item: T, key: K) -> (Rank, T) {
+        (self.push(key), item)
+    }
+
+    /// Returns the number of keys currently in the window.
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Returns `true` if no key has been pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.window.is_empty()
+    }
+
+    /// Returns the maximum number of keys in the window.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+}
+
+/// Keeps the `k` items with the smallest keys out of all the items pushed so far,
+/// ranked.
+///
+/// Unlike [`RankedExt::top_k_by`](crate::RankedExt::top_k_by), which ranks a whole
+/// iterator at once, this can be fed one item at a time for as long as needed and be
+/// read at any point. The retained items are kept ordered by key and arrival, so a push
+/// is O(log k). When items tie across the cutoff, the ones pushed first are kept, as
+/// with `top_k_by`. Ranks are dense over the retained items, so they change as items
+/// are evicted.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::online::RunningTopK;
+///
+/// let mut top = RunningTopK::new(2);
+/// top.push("ann", 30);
+/// top.push("bob", 20);
+/// assert_eq!(top.current().collect::<Vec<_>>(), vec![(Rank(1), &"bob"), (Rank(2), &"ann")]);
+///
+/// // cy ties with bob and pushes ann out.
+/// assert_eq!(top.push("cy", 20), Some("ann"));
+/// assert_eq!(top.into_ranked().collect::<Vec<_>>(), vec![(Rank(1), "bob"), (Rank(1), "cy")]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RunningTopK<K, T> {
+    k: usize,
+    pushed: usize,
+    retained: BTreeMap<(K, usize), T>,
+}
+
+impl<K, T> RunningTopK<K, T>
+where
+    K: Ord,
+{
+    /// Creates a structure keeping the `k` best items.
+    pub fn new(k: usize) -> Self {
+        RunningTopK {
+            k,
+            pushed: 0,
+            retained: BTreeMap::new(),
+        }
+    }
+
+    /// Pushes an item with its key, and returns the item evicted to keep at most `k`,
+    /// which may be the pushed one.
+    pub fn push(&mut self, item: T, key: K) -> Option<T> {
+        self.retained.insert((key, self.pushed), item);
+        self.pushed += 1;
+        if self.retained.len() > self.k {
+            self.retained.pop_last().map(|(_, item)| item)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the retained items in rank order, with their dense ranks among the
+    /// retained items.
+    pub fn current(&self) -> impl Iterator<Item = (Rank, &T)> {
+        let mut rank = Rank(0);
+        let mut prev_key = None;
+        self.retained.iter().map(move |((key, _), item)| {
+            if prev_key != Some(key) {
+                rank = Rank(rank.0 + 1);
+                prev_key = Some(key);
+            }
+            (rank, item)
+        })
+    }
+
+    /// Consumes the structure and yields the retained items in rank order, ranked as
+    /// by [`current`](RunningTopK::current).
+    pub fn into_ranked(self) -> impl Iterator<Item = (Rank, T)> {
+        let mut rank = Rank(0);
+        let mut prev_key = None;
+        self.retained.into_iter().map(move |((key, _), item)| {
+            if prev_key.as_ref() != Some(&key) {
+                rank = Rank(rank.0 + 1);
+                prev_key = Some(key);
+            }
+            (rank, item)
+        })
+    }
+
+    /// Returns the number of retained items, at most `k`.
+    pub fn len(&self) -> usize {
+        self.retained.len()
+    }
+
+    /// Returns `true` if no item is retained.
+    pub fn is_empty(&self) -> bool {
+        self.retained.is_empty()
+    }
+
+    /// Returns the number of pushed items, including the evicted ones.
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+    use crate::test_support::lcg;
+
+    fn dense_rank_in(window: &[u32], key: u32) -> Rank {
+        let mut smaller: Vec<u32> = window.iter().copied().filter(|&k| k < key).collect();
+        smaller.sort_unstable();
+        smaller.dedup();
+        Rank(smaller.len() + 1)
+    }
+
+    #[test]
+    fn test_windowed_ranker_matches_sorted_window() {
+        let values: Vec<u32> = lcg(3)
+            .take(500)
+            .map(|state| ((state >> 33) % 20) as u32)
+            .collect();
+        for window_size in [1, 2, 5, 17] {
+            let mut ranker = WindowedRanker::new(window_size);
+            for (i, &value) in values.iter().enumerate() {
+                let start = (i + 1).saturating_sub(window_size);
+                assert_eq!(
+                    ranker.push(value),
+                    dense_rank_in(&values[start..=i], value),
+                    "window {window_size}, index {i}"
+                );
+                assert_eq!(ranker.len(), (i + 1).min(window_size));
+            }
+        }
+    }
+
+    #[test]
+    fn test_windowed_ranker_warm_up_and_items() {
+        let mut ranker = WindowedRanker::new(4);
+        assert!(ranker.is_empty());
+        assert_eq!(ranker.push_item('a', 10), (Rank(1), 'a'));
+        assert_eq!(ranker.push_item('b', 10), (Rank(1), 'b'));
+        assert_eq!(ranker.push_item('c', 30), (Rank(2), 'c'));
+        assert_eq!(ranker.push_item('d', 20), (Rank(2), 'd'));
+        // 'a' drops out here, but another 10 is still in the window.
+        assert_eq!(ranker.push_item('e', 25), (Rank(3), 'e'));
+        assert_eq!(ranker.window_size(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_windowed_ranker_zero_window() {
+        WindowedRanker::<u32>::new(0);
+    }
+
+    #[test]
+    fn test_running_top_k_matches_ranking_all_pushed() {
+        let values: Vec<(u32, usize)> = lcg(21)
+            .take(300)
+            .enumerate()
+            .map(|(i, state)| (((state >> 33) % 25) as u32, i))
+            .collect();
+        for k in [0, 1, 3, 10, 400] {
+            let mut top = RunningTopK::new(k);
+            for (i, &(key, id)) in values.iter().enumerate() {
+                let evicted = top.push(id, key);
+                assert_eq!(evicted.is_some(), i >= k);
+                let expected: Vec<(Rank, usize)> = values[..=i]
+                    .iter()
+                    .rank_by(|&&(key, _)| key)
+                    .take(k)
+                    .map(|(rank, &(_, id))| (rank, id))
+                    .collect();
+                let current: Vec<(Rank, usize)> =
+                    top.current().map(|(rank, &id)| (rank, id)).collect();
+                assert_eq!(current, expected, "k {k}, index {i}");
+            }
+            assert_eq!(top.pushed(), values.len());
+            let expected = values
+                .iter()
+                .top_k_by(k, |&&(key, _)| key)
+                .map(|(r, &(_, id))| (r, id));
+            assert!(top.into_ranked().eq(expected));
+        }
+    }
+
+    #[test]
+    fn test_running_top_k_ranks_follow_evictions() {
+        let mut top = RunningTopK::new(3);
+        top.push('a', 5);
+        top.push('b', 1);
+        top.push('c', 9);
+        let ranks: Vec<Rank> = top.current().map(|(rank, _)| rank).collect();
+        assert_eq!(ranks, vec![Rank(1), Rank(2), Rank(3)]);
+
+        // A tie with 'b' evicts 'c', and 'a' is now the only item at rank 2.
+        assert_eq!(top.push('d', 1), Some('c'));
+        let current: Vec<(Rank, char)> = top.current().map(|(r, &c)| (r, c)).collect();
+        assert_eq!(
+            current,
+            vec![(Rank(1), 'b'), (Rank(1), 'd'), (Rank(2), 'a')]
+        );
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.push('e', 7), Some('e'));
+    }
+}
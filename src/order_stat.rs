@@ -0,0 +1,331 @@
+//! A multiset of keys that counts the keys below a key in O(log n).
+
+use std::cmp::Ordering;
+
+type Link = Option<usize>;
+
+#[derive(Clone, Debug)]
+struct Node<K> {
+    key: K,
+    /// How many times the key is in the multiset.
+    count: usize,
+    priority: u64,
+    /// The number of distinct keys in the subtree rooted here.
+    distinct: usize,
+    /// The number of occurrences of the keys in the subtree rooted here.
+    entries: usize,
+    left: Link,
+    right: Link,
+}
+
+/// A multiset of keys kept in a treap whose nodes know the number of distinct keys and
+/// of occurrences below them, so inserting, removing and counting the keys less than a
+/// key are all O(log n) expected for `n` distinct keys.
+///
+/// The priorities come from a fixed-seed generator, so the shape of the tree, and with
+/// it the running time, is the same from one run to the next.
+#[derive(Clone, Debug)]
+pub(crate) struct KeyCounts<K> {
+    nodes: Vec<Node<K>>,
+    free: Vec<usize>,
+    root: Link,
+    state: u64,
+}
+
+impl<K: Ord> KeyCounts<K> {
+    pub(crate) fn new() -> Self {
+        KeyCounts {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            state: 0,
+        }
+    }
+
+    /// Builds the multiset from distinct keys in increasing order, each with its number
+    /// of occurrences, in O(n) for `n` keys.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_sorted(keys: impl IntoIterator<Item = (K, usize)>) -> Self {
+        let mut counts = KeyCounts::new();
+        // The right spine of the tree built so far, from the root down. A new key goes at
+        // the bottom of the spine, taking the nodes of lower priority below it as its
+        // left subtree, and those nodes are final once they leave the spine.
+        let mut spine: Vec<usize> = Vec::new();
+        for (key, count) in keys {
+            let i = counts.alloc(key);
+            counts.nodes[i].count = count;
+            let mut left = None;
+            while let Some(&top) = spine.last()
+                && counts.nodes[top].priority < counts.nodes[i].priority
+            {
+                spine.pop();
+                counts.update(top);
+                left = Some(top);
+            }
+            counts.nodes[i].left = left;
+            if let Some(&top) = spine.last() {
+                counts.nodes[top].right = Some(i);
+            }
+            spine.push(i);
+        }
+        for &i in spine.iter().rev() {
+            counts.update(i);
+        }
+        counts.root = spine.first().copied();
+        counts
+    }
+
+    /// Returns the number of distinct keys in the multiset that are less than `key`.
+    pub(crate) fn count_below(&self, key: &K) -> usize {
+        let mut below = 0;
+        let mut link = self.root;
+        while let Some(i) = link {
+            let node = &self.nodes[i];
+            if *key <= node.key {
+                link = node.left;
+            } else {
+                below += self.distinct(node.left) + 1;
+                link = node.right;
+            }
+        }
+        below
+    }
+
+    /// Returns the number of occurrences of the keys in the multiset that are less than
+    /// `key`.
+    pub(crate) fn count_entries_below(&self, key: &K) -> usize {
+        let mut below = 0;
+        let mut link = self.root;
+        while let Some(i) = link {
+            let node = &self.nodes[i];
+            if *key <= node.key {
+                link = node.left;
+            } else {
+                below += self.entries(node.left) + node.count;
+                link = node.right;
+            }
+        }
+        below
+    }
+
+    /// Returns the number of occurrences of `key`.
+    pub(crate) fn count(&self, key: &K) -> usize {
+        self.find(key).map_or(0, |i| self.nodes[i].count)
+    }
+
+    /// Adds one occurrence of `key`.
+    pub(crate) fn insert(&mut self, key: K) {
+        if self.find(&key).is_some() {
+            let mut link = self.root;
+            while let Some(i) = link {
+                self.nodes[i].entries += 1;
+                match key.cmp(&self.nodes[i].key) {
+                    Ordering::Less => link = self.nodes[i].left,
+                    Ordering::Greater => link = self.nodes[i].right,
+                    Ordering::Equal => {
+                        self.nodes[i].count += 1;
+                        return;
+                    }
+                }
+            }
+        }
+        let (below, above) = self.split(self.root, &key);
+        let node = self.alloc(key);
+        let below = self.merge(below, Some(node));
+        self.root = self.merge(below, above);
+    }
+
+    /// Removes one occurrence of `key`, if there is one.
+    pub(crate) fn remove(&mut self, key: &K) {
+        self.root = self.remove_from(self.root, key);
+    }
+
+    fn find(&self, key: &K) -> Option<usize> {
+        let mut link = self.root;
+        while let Some(i) = link {
+            match key.cmp(&self.nodes[i].key) {
+                Ordering::Less => link = self.nodes[i].left,
+                Ordering::Greater => link = self.nodes[i].right,
+                Ordering::Equal => return Some(i),
+            }
+        }
+        None
+    }
+
+    fn distinct(&self, link: Link) -> usize {
+        link.map_or(0, |i| self.nodes[i].distinct)
+    }
+
+    fn entries(&self, link: Link) -> usize {
+        link.map_or(0, |i| self.nodes[i].entries)
+    }
+
+    fn update(&mut self, i: usize) {
+        let (left, right) = (self.nodes[i].left, self.nodes[i].right);
+        self.nodes[i].distinct = self.distinct(left) + self.distinct(right) + 1;
+        self.nodes[i].entries = self.entries(left) + self.entries(right) + self.nodes[i].count;
+    }
+
+    fn alloc(&mut self, key: K) -> usize {
+        // SplitMix64, which spreads consecutive states over the whole range.
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        let node = Node {
+            key,
+            count: 1,
+            priority: z ^ (z >> 31),
+            distinct: 1,
+            entries: 1,
+            left: None,
+            right: None,
+        };
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Splits a subtree into the keys less than `key` and the rest.
+    fn split(&mut self, link: Link, key: &K) -> (Link, Link) {
+        let Some(i) = link else {
+            return (None, None);
+        };
+        if self.nodes[i].key < *key {
+            let (below, above) = self.split(self.nodes[i].right, key);
+            self.nodes[i].right = below;
+            self.update(i);
+            (Some(i), above)
+        } else {
+            let (below, above) = self.split(self.nodes[i].left, key);
+            self.nodes[i].left = above;
+            self.update(i);
+            (below, Some(i))
+        }
+    }
+
+    /// Joins two subtrees, all of whose keys in `below` are less than those in `above`.
+    fn merge(&mut self, below: Link, above: Link) -> Link {
+        match (below, above) {
+            (None, link) | (link, None) => link,
+            (Some(i), Some(j)) => {
+                if self.nodes[i].priority > self.nodes[j].priority {
+                    let right = self.merge(self.nodes[i].right, above);
+                    self.nodes[i].right = right;
+                    self.update(i);
+                    Some(i)
+                } else {
+                    let left = self.merge(below, self.nodes[j].left);
+                    self.nodes[j].left = left;
+                    self.update(j);
+                    Some(j)
+                }
+            }
+        }
+    }
+
+    fn remove_from(&mut self, link: Link, key: &K) -> Link {
+        let i = link?;
+        match key.cmp(&self.nodes[i].key) {
+            Ordering::Less => {
+                let left = self.remove_from(self.nodes[i].left, key);
+                self.nodes[i].left = left;
+            }
+            Ordering::Greater => {
+                let right = self.remove_from(self.nodes[i].right, key);
+                self.nodes[i].right = right;
+            }
+            Ordering::Equal => {
+                self.nodes[i].count -= 1;
+                if self.nodes[i].count > 0 {
+                    self.update(i);
+                    return Some(i);
+                }
+                self.free.push(i);
+                return self.merge(self.nodes[i].left, self.nodes[i].right);
+            }
+        }
+        self.update(i);
+        Some(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::test_support::lcg;
+
+    #[test]
+    fn test_key_counts_match_btreemap() {
+        let mut counts = KeyCounts::new();
+        let mut expected: BTreeMap<u64, usize> = BTreeMap::new();
+        for state in lcg(11).take(5000) {
+            let key = (state >> 33) % 200;
+            if (state >> 60) % 3 == 0 {
+                counts.remove(&key);
+                if let Some(count) = expected.get_mut(&key) {
+                    *count -= 1;
+                    if *count == 0 {
+                        expected.remove(&key);
+                    }
+                }
+            } else {
+                counts.insert(key);
+                *expected.entry(key).or_insert(0) += 1;
+            }
+            let probe = (state >> 45) % 210;
+            assert_eq!(counts.count_below(&probe), expected.range(..probe).count());
+            assert_eq!(
+                counts.count_entries_below(&probe),
+                expected
+                    .range(..probe)
+                    .map(|(_, count)| count)
+                    .sum::<usize>()
+            );
+            assert_eq!(
+                counts.count(&probe),
+                expected.get(&probe).copied().unwrap_or(0)
+            );
+        }
+        assert_eq!(counts.distinct(counts.root), expected.len());
+        assert_eq!(
+            counts.entries(counts.root),
+            expected.values().sum::<usize>()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_sorted_matches_inserts() {
+        let keys: Vec<(u64, usize)> = lcg(5)
+            .take(300)
+            .enumerate()
+            .map(|(i, state)| (i as u64 * 2, (state >> 33) as usize % 4 + 1))
+            .collect();
+        let built = KeyCounts::from_sorted(keys.iter().copied());
+        let mut inserted = KeyCounts::new();
+        for &(key, count) in &keys {
+            for _ in 0..count {
+                inserted.insert(key);
+            }
+        }
+        for probe in 0..610 {
+            assert_eq!(built.count_below(&probe), inserted.count_below(&probe));
+            assert_eq!(
+                built.count_entries_below(&probe),
+                inserted.count_entries_below(&probe)
+            );
+            assert_eq!(built.count(&probe), inserted.count(&probe));
+        }
+        assert!(KeyCounts::<u64>::from_sorted([]).root.is_none());
+    }
+}
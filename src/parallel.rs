@@ -0,0 +1,104 @@
+//! Ranking large collections in parallel with [`rayon`].
+
+use rayon::prelude::*;
+
+use crate::RankedExt;
+use crate::rank::{Rank, RankedBy};
+use crate::ranking::Ranking;
+
+/// Ranks the items by a key on the rayon thread pool.
+///
+/// The items are split into one chunk per thread, each chunk is ranked on its own and
+/// the partial rankings are combined with [`Ranking::merge`]. The result is the same as
+/// collecting [`rank_by`](crate::RankedExt::rank_by) into a [`Ranking`], including the
+/// input order of tied items.
+///
+/// # Examples
+///
+/// ```
+/// use librank::parallel::par_rank_by;
+/// use librank::{RankedExt, Ranking};
+///
+/// let data: Vec<u32> = (0..1000).map(|i| i * 7 % 31).collect();
+/// let expected: Ranking<u32> = data.clone().into_iter().rank_by(|&x| x).collect();
+///
+/// assert_eq!(par_rank_by(data, |&x| x), expected);
+/// ```
+pub fn par_rank_by<T, K, F>(items: Vec<T>, f: F) -> Ranking<T>
+where
+    T: Send,
+    F: Fn(&T) -> K + Sync,
+    K: Ord + Eq,
+{
+    let chunk_size = items.len().div_ceil(rayon::current_num_threads()).max(1);
+    let parts: Vec<Ranking<T>> = items
+        .into_par_iter()
+        .chunks(chunk_size)
+        .map(|chunk| chunk.into_iter().rank_by(&f).collect())
+        .collect();
+    Ranking::merge(parts, &f)
+}
+
+/// Ranks the items by a key into a `Vec`, sorting them in parallel with rayon's stable
+/// sort.
+///
+/// Only the sort runs in parallel: assigning the ranks compares each item with the
+/// previous one, so it's a single sequential pass, and its output goes straight into the
+/// returned `Vec`. The result is the same as
+/// [`rank_by_collect`](crate::RankedExt::rank_by_collect), including the input order of
+/// tied items.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::parallel::par_rank_by_collect;
+///
+/// let ranked = par_rank_by_collect(vec![3, 1, 3], |&x| x);
+///
+/// assert_eq!(ranked, vec![(Rank(1), 1), (Rank(2), 3), (Rank(2), 3)]);
+/// ```
+pub fn par_rank_by_collect<T, K, F>(mut items: Vec<T>, f: F) -> Vec<(Rank, T)>
+where
+    T: Send,
+    F: Fn(&T) -> K + Sync,
+    K: Ord + Eq,
+{
+    items.par_sort_by_key(&f);
+    let mut ranked = Vec::with_capacity(items.len());
+    ranked.extend(RankedBy::new(items.into_iter(), f));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lcg;
+
+    #[test]
+    fn test_par_rank_by_matches_rank_by() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        for len in [0, 1, 5, 1000] {
+            let data: Vec<(u64, usize)> = (0..len).map(|i| ((i as u64 * 37) % 11, i)).collect();
+            let expected: Ranking<(u64, usize)> =
+                data.clone().into_iter().rank_by(|&(k, _)| k).collect();
+            let ranked = pool.install(|| par_rank_by(data, |&(k, _)| k));
+            assert_eq!(ranked, expected);
+        }
+    }
+
+    #[test]
+    fn test_par_rank_by_collect_matches_rank_by() {
+        let data: Vec<(u64, usize)> = lcg(9)
+            .take(100_000)
+            .enumerate()
+            .map(|(i, state)| ((state >> 33) % 1000, i))
+            .collect();
+        let expected = data.clone().into_iter().rank_by_collect(|&(k, _)| k);
+        assert_eq!(par_rank_by_collect(data, |&(k, _)| k), expected);
+        assert!(par_rank_by_collect(Vec::<u8>::new(), |&x| x).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Scoring ranked items with a points table, as in motorsport championships.
+
+use crate::rank::Rank;
+
+/// How tied items share the points of the positions they occupy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TiePolicy {
+    /// Every tied item gets the mean of the points of the positions the group covers.
+    #[default]
+    Split,
+    /// Every tied item gets the points of the group's first position.
+    Full,
+    /// Ties are ignored: every item gets the points of its own position, in the order
+    /// the items arrive.
+    None,
+}
+
+/// Assigns points to ranked items from a table indexed by finishing position.
+///
+/// `table[0]` is the points for the first position, `table[1]` for the second and so
+/// on; positions past the end of the table get 0. Positions are counted like SQL's
+/// `RANK()`, so a tie group of two at the second position covers the second and third
+/// positions whatever the ranks of the items are, and the next group starts at the
+/// fourth. Consecutive items with the same rank form a tie group, so the input must be
+/// in rank order, and each group is buffered until the rank changes. The ranks are
+/// passed through unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use librank::points::{TiePolicy, assign_points};
+/// use librank::{Rank, RankedExt};
+///
+/// let table = [25.0, 18.0, 15.0, 12.0];
+/// let laps = vec![("ann", 90), ("bob", 92), ("cy", 92), ("dee", 95)];
+/// let points: Vec<(&str, f64)> = assign_points(
+///     laps.into_iter().rank_by(|&(_, t)| t),
+///     &table,
+///     TiePolicy::Split,
+/// )
+/// .map(|(_, points, (name, _))| (name, points))
+/// .collect();
+///
+/// assert_eq!(points, vec![("ann", 25.0), ("bob", 16.5), ("cy", 16.5), ("dee", 12.0)]);
+/// ```
+pub fn assign_points<T>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    table: &[f64],
+    tie_policy: TiePolicy,
+) -> impl Iterator<Item = (Rank, f64, T)> {
+    let points_at = move |position: usize| table.get(position).copied().unwrap_or(0.0);
+    let mut ranked = ranked.into_iter().peekable();
+    let mut position = 0;
+    let mut group = Vec::new().into_iter();
+    std::iter::from_fn(move || {
+        loop {
+            if let Some(next) = group.next() {
+                return Some(next);
+            }
+            let (rank, first) = ranked.next()?;
+            let mut items = vec![first];
+            while let Some((_, item)) = ranked.next_if(|&(r, _)| r == rank) {
+                items.push(item);
+            }
+            let start = position;
+            position += items.len();
+            let shared = match tie_policy {
+                TiePolicy::Split => {
+                    (start..position).map(points_at).sum::<f64>() / items.len() as f64
+                }
+                TiePolicy::Full | TiePolicy::None => points_at(start),
+            };
+            group = items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let points = match tie_policy {
+                        TiePolicy::None => points_at(start + i),
+                        TiePolicy::Split | TiePolicy::Full => shared,
+                    };
+                    (rank, points, item)
+                })
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+
+    const TABLE: [f64; 5] = [25.0, 18.0, 15.0, 12.0, 10.0];
+
+    /// Three drivers tied at positions 4 to 6, past the end of the five-place table.
+    fn points(policy: TiePolicy) -> Vec<(Rank, f64)> {
+        let times = vec![
+            ('a', 1),
+            ('b', 2),
+            ('c', 3),
+            ('d', 4),
+            ('e', 4),
+            ('f', 4),
+            ('g', 5),
+        ];
+        assign_points(times.into_iter().rank_by(|&(_, t)| t), &TABLE, policy)
+            .map(|(rank, points, _)| (rank, points))
+            .collect()
+    }
+
+    #[test]
+    fn test_three_way_tie_spanning_end_of_table() {
+        let split = points(TiePolicy::Split);
+        assert_eq!(
+            split[..3],
+            [(Rank(1), 25.0), (Rank(2), 18.0), (Rank(3), 15.0)]
+        );
+        for &(rank, value) in &split[3..6] {
+            assert_eq!(rank, Rank(4));
+            assert!((value - 22.0 / 3.0).abs() < 1e-9);
+        }
+        assert_eq!(split[6], (Rank(5), 0.0));
+
+        let full: Vec<f64> = points(TiePolicy::Full)
+            .into_iter()
+            .map(|(_, p)| p)
+            .collect();
+        assert_eq!(full, vec![25.0, 18.0, 15.0, 12.0, 12.0, 12.0, 0.0]);
+
+        let none: Vec<f64> = points(TiePolicy::None)
+            .into_iter()
+            .map(|(_, p)| p)
+            .collect();
+        assert_eq!(none, vec![25.0, 18.0, 15.0, 12.0, 10.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_assign_points_edge_cases() {
+        let ranked = vec![(Rank(1), 'a'), (Rank(1), 'b')];
+        let points: Vec<f64> = assign_points(ranked, &[10.0], TiePolicy::Split)
+            .map(|(_, p, _)| p)
+            .collect();
+        assert_eq!(points, vec![5.0, 5.0]);
+        assert_eq!(
+            assign_points(Vec::<(Rank, char)>::new(), &TABLE, TiePolicy::Split).count(),
+            0
+        );
+        let without_table: Vec<f64> = assign_points(vec![(Rank(1), 'a')], &[], TiePolicy::Full)
+            .map(|(_, p, _)| p)
+            .collect();
+        assert_eq!(without_table, vec![0.0]);
+    }
+}
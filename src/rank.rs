@@ -1,5 +1,15 @@
 //! An iterator extension trait for ranking items.
 
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use crate::lookup::RankTable;
+use crate::order_stat::KeyCounts;
+use crate::ranking::Ranking;
+
 /// Represents the rank of an item.
 /// The rank is a 1-based integer.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -13,6 +23,46 @@ pub struct RankedBy<I, F, K> {
     f: F,
     rank: Rank,
     prev_key: Option<K>,
+    consumed: usize,
+}
+
+/// The state of a [`RankedBy`] between two items, for pausing a ranking of presorted
+/// items and resuming it later, possibly in another process.
+///
+/// With the `serde` feature the checkpoint can be serialized. It holds the last rank
+/// yielded, the key of the last item and how many items were read, but not the items
+/// themselves: the caller resumes the input where it left off, for example by
+/// reopening a database cursor at [`consumed`](RankCheckpoint::consumed), and passes
+/// the rest of it to [`rank_by_with_checkpoint`](RankedExt::rank_by_with_checkpoint).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RankCheckpoint<K> {
+    rank: usize,
+    prev_key: Option<K>,
+    consumed: usize,
+}
+
+impl<K> RankCheckpoint<K> {
+    /// Returns the number of items read before the checkpoint was taken.
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// Returns the rank of the last item read, or `None` if no item was read.
+    pub fn rank(&self) -> Option<Rank> {
+        self.prev_key.as_ref().map(|_| Rank(self.rank))
+    }
+}
+
+impl<K> Default for RankCheckpoint<K> {
+    /// Returns the checkpoint of a ranking that hasn't read any item.
+    fn default() -> Self {
+        RankCheckpoint {
+            rank: 0,
+            prev_key: None,
+            consumed: 0,
+        }
+    }
 }
 
 impl<I, F, K> Iterator for RankedBy<I, F, K>
@@ -23,16 +73,247 @@ where
 {
     type Item = (Rank, I::Item);
     fn next(&mut self) -> Option<Self::Item> {
-        let Some(item) = self.iter.next() else {
-            return None;
-        };
+        let item = self.iter.next()?;
         let key = (self.f)(&item);
         if self.prev_key.as_ref() != Some(&key) {
             self.rank = Rank(self.rank.0 + 1);
             self.prev_key = Some(key);
         }
+        self.consumed += 1;
         Some((self.rank, item))
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I, F, K> RankedBy<I, F, K>
+where
+    I: Iterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+{
+    /// Ranks an iterator that is already sorted by `f`.
+    pub(crate) fn new(iter: I, f: F) -> Self {
+        RankedBy {
+            iter,
+            f,
+            rank: Rank(0),
+            prev_key: None,
+            consumed: 0,
+        }
+    }
+
+    /// Returns a checkpoint of the ranking so far, from which
+    /// [`rank_by_with_checkpoint`](RankedExt::rank_by_with_checkpoint) continues with
+    /// the same ranks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![1, 1, 2, 3, 3, 5];
+    /// let mut ranked = data.clone().into_iter().rank_presorted_by(|&x| x);
+    /// let head: Vec<(Rank, i32)> = ranked.by_ref().take(3).collect();
+    /// let checkpoint = ranked.checkpoint();
+    ///
+    /// let rest = data.into_iter().skip(checkpoint.consumed());
+    /// let tail: Vec<(Rank, i32)> = rest.rank_by_with_checkpoint(checkpoint, |&x| x).collect();
+    ///
+    /// assert_eq!(head, vec![(Rank(1), 1), (Rank(1), 1), (Rank(2), 2)]);
+    /// assert_eq!(tail, vec![(Rank(3), 3), (Rank(3), 3), (Rank(4), 5)]);
+    /// ```
+    pub fn checkpoint(&self) -> RankCheckpoint<K>
+    where
+        K: Clone,
+    {
+        RankCheckpoint {
+            rank: self.rank.0,
+            prev_key: self.prev_key.clone(),
+            consumed: self.consumed,
+        }
+    }
+
+    /// Records which ranks are shared by more than one item as the iterator is consumed.
+    ///
+    /// Only the size of the current rank group is tracked, so the input is not buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let mut ranked = vec![10, 20, 10, 30, 20, 10].into_iter().rank_by(|&x| x).track_ties();
+    /// ranked.by_ref().for_each(drop);
+    ///
+    /// assert!(ranked.has_ties());
+    /// assert_eq!(ranked.tie_groups(), &[(Rank(1), 3), (Rank(2), 2)]);
+    /// ```
+    pub fn track_ties(self) -> TieTracker<Self> {
+        TieTracker {
+            iter: self,
+            current: None,
+            tie_groups: Vec::new(),
+        }
+    }
+
+    /// Yields the rank groups one at a time, each with an iterator over its items.
+    ///
+    /// Each group is read into a buffer when it is reached, so only one group is held
+    /// at a time, and the group iterators can be kept or dropped in any order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let mut groups = vec![10, 20, 10, 30].into_iter().rank_by(|&x| x).iter_groups();
+    ///
+    /// let (rank, items) = groups.next().unwrap();
+    /// assert_eq!((rank, items.collect::<Vec<_>>()), (Rank(1), vec![10, 10]));
+    /// assert_eq!(groups.map(|(rank, items)| (rank, items.len())).collect::<Vec<_>>(), vec![(Rank(2), 1), (Rank(3), 1)]);
+    /// ```
+    pub fn iter_groups(self) -> impl Iterator<Item = (Rank, std::vec::IntoIter<I::Item>)> {
+        let mut ranked = self.peekable();
+        std::iter::from_fn(move || {
+            let (rank, first) = ranked.next()?;
+            let mut group = vec![first];
+            while let Some((_, item)) = ranked.next_if(|&(next, _)| next == rank) {
+                group.push(item);
+            }
+            Some((rank, group.into_iter()))
+        })
+    }
+
+    /// Orders the items of each tie group by a hash of an identity of each item, instead
+    /// of by their input order.
+    ///
+    /// The bytes returned by `id_fn` are hashed with [`stable_hash`], whose output never
+    /// changes, so the order is the same across runs, machines and releases, yet
+    /// doesn't depend on the order in which items were submitted. Items with equal hashes
+    /// keep their input order. Each tie group is buffered when it is reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let entries = vec![("carol", 3), ("alice", 3), ("bob", 1), ("dave", 3)];
+    /// let ranked: Vec<(Rank, &str)> = entries
+    ///     .into_iter()
+    ///     .rank_by(|&(_, score)| score)
+    ///     .order_ties_by_hash(|&(name, _)| name)
+    ///     .map(|(rank, (name, _))| (rank, name))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), "bob"), (Rank(2), "alice"), (Rank(2), "dave"), (Rank(2), "carol")]);
+    /// ```
+    pub fn order_ties_by_hash<G, B>(self, mut id_fn: G) -> impl Iterator<Item = (Rank, I::Item)>
+    where
+        G: FnMut(&I::Item) -> B,
+        B: AsRef<[u8]>,
+    {
+        self.iter_groups().flat_map(move |(rank, group)| {
+            let mut group: Vec<I::Item> = group.collect();
+            group.sort_by_cached_key(|item| stable_hash(id_fn(item).as_ref()));
+            group.into_iter().map(move |item| (rank, item))
+        })
+    }
+}
+
+/// An iterator adapter that records tie statistics of a ranked iterator.
+/// Created by [`RankedBy::track_ties`].
+pub struct TieTracker<I> {
+    iter: I,
+    current: Option<(Rank, usize)>,
+    tie_groups: Vec<(Rank, usize)>,
+}
+
+impl<I> TieTracker<I> {
+    /// Returns the ranks shared by more than one item, with their group sizes.
+    ///
+    /// The rank group being consumed is only included once the next group starts or
+    /// the iterator is exhausted.
+    pub fn tie_groups(&self) -> &[(Rank, usize)] {
+        &self.tie_groups
+    }
+
+    /// Returns `true` if any completed rank group has more than one item.
+    pub fn has_ties(&self) -> bool {
+        !self.tie_groups.is_empty()
+    }
+
+    fn finish_group(&mut self) {
+        if let Some((rank, size)) = self.current.take()
+            && size > 1
+        {
+            self.tie_groups.push((rank, size));
+        }
+    }
+}
+
+impl<I, T> Iterator for TieTracker<I>
+where
+    I: Iterator<Item = (Rank, T)>,
+{
+    type Item = (Rank, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some((rank, item)) = self.iter.next() else {
+            self.finish_group();
+            return None;
+        };
+        match &mut self.current {
+            Some((current, size)) if *current == rank => *size += 1,
+            _ => {
+                self.finish_group();
+                self.current = Some((rank, 1));
+            }
+        }
+        Some((rank, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// A ranked iterator that can be polled for how far it has got.
+/// Created by [`RankedExt::rank_by_with_progress_iter`].
+pub struct ProgressRankedBy<I, F, K> {
+    inner: RankedBy<I, F, K>,
+    total: usize,
+}
+
+impl<I, F, K> ProgressRankedBy<I, F, K> {
+    /// Returns `(emitted, total)`: the number of items yielded so far and the number of
+    /// items being ranked.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.inner.consumed, self.total)
+    }
+}
+
+impl<I, F, K> Iterator for ProgressRankedBy<I, F, K>
+where
+    I: Iterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+{
+    type Item = (Rank, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
 }
 
 /// An extension trait for iterators that provides a `rank_by` method.
@@ -42,6 +323,13 @@ pub trait RankedExt: Iterator {
     /// This method sorts the iterator's items by the key produced by the given function,
     /// and then assigns a rank to each item. The rank is dense, meaning that items with
     /// the same key will have the same rank, and the next rank will be incremented by 1.
+    /// This matches SQL's `DENSE_RANK()`, not `RANK()`; see the [`sql`](crate::sql)
+    /// module for the other window functions.
+    ///
+    /// The sort is stable: items with equal keys are yielded in their input order. This
+    /// is guaranteed, as it is for every other method that sorts unless its
+    /// documentation says otherwise. Use [`rank_by_unstable`](RankedExt::rank_by_unstable)
+    /// when the order of ties doesn't matter.
     ///
     /// # Examples
     ///
@@ -68,123 +356,6015 @@ pub trait RankedExt: Iterator {
         Self: Sized,
         F: FnMut(&Self::Item) -> K,
         K: Ord + Eq;
-}
 
-impl<I> RankedExt for I
-where
-    I: Iterator,
-{
-    fn rank_by<F, K>(self, mut f: F) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    /// Ranks the items in the iterator by a key, in descending order.
+    ///
+    /// This is [`rank_by`](RankedExt::rank_by) with the order reversed: the items with
+    /// the greatest key get rank 1. Ties keep their stable input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![10, 20, 10, 30];
+    /// let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by_desc(|&x| x).collect();
+    ///
+    /// let expected = vec![(Rank(1), 30), (Rank(2), 20), (Rank(3), 10), (Rank(3), 10)];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_by_desc<F, K>(self, f: F) -> impl Iterator<Item = (Rank, Self::Item)>
     where
         Self: Sized,
         F: FnMut(&Self::Item) -> K,
-        K: Ord + Eq,
-    {
-        let mut v = Vec::from_iter(self);
-        v.sort_by_key(&mut f);
-        RankedBy {
-            iter: v.into_iter(),
-            f,
-            rank: Rank(0),
-            prev_key: None,
-        }
-    }
-}
+        K: Ord + Eq;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Returns the items of the `n`-th rank group.
+    ///
+    /// This is equivalent to ranking the items with `rank_by` and keeping only those
+    /// with `Rank(n)`, but it does not build `(Rank, T)` pairs for the whole input.
+    /// Returns an empty `Vec` if `n` is zero or greater than the number of rank groups.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![10, 20, 10, 30, 20, 10];
+    /// assert_eq!(data.into_iter().rank_by_nth_group(2, |&x| x), vec![20, 20]);
+    /// ```
+    fn rank_by_nth_group<F, K>(self, n: usize, f: F) -> Vec<Self::Item>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
 
-    #[test]
-    fn test_rank_by_unique_keys() {
-        let data = vec![3, 7, 4, 1, 5, 9, 2, 6];
-        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
-        let expected = vec![
-            (Rank(1), 1),
-            (Rank(2), 2),
-            (Rank(3), 3),
-            (Rank(4), 4),
-            (Rank(5), 5),
-            (Rank(6), 6),
-            (Rank(7), 7),
-            (Rank(8), 9),
-        ];
-        assert_eq!(ranked, expected);
-    }
+    /// Ranks the items by a key and yields them with their empirical cumulative density.
+    ///
+    /// Items are yielded in sorted order, each with `(position - 1) / (n - 1)`, where
+    /// `position` is its 1-based ordinal position. Tied items all get the value at the
+    /// midpoint of the positions they occupy, as in fractional ranking, so the minimum
+    /// gets 0.0 and the maximum 1.0 only when they are not tied. A single item gets 0.5.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![30, 10, 20, 20, 40];
+    /// let ecdf: Vec<(f64, i32)> = data.into_iter().rank_by_ecdf(|&x| x).collect();
+    ///
+    /// assert_eq!(ecdf, vec![(0.0, 10), (0.375, 20), (0.375, 20), (0.75, 30), (1.0, 40)]);
+    /// ```
+    fn rank_by_ecdf<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
 
-    #[test]
-    fn test_rank_by_duplicate_keys() {
-        let data = vec![10, 20, 10, 30, 20, 10];
-        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
-        let expected = vec![
-            (Rank(1), 10),
-            (Rank(1), 10),
-            (Rank(1), 10),
-            (Rank(2), 20),
-            (Rank(2), 20),
-            (Rank(3), 30),
-        ];
-        assert_eq!(ranked, expected);
-    }
+    /// Ranks the items by a key and yields each with the size of the previous rank group.
+    ///
+    /// The gap is 0 for items of rank 1 and, for every later rank, the number of items in
+    /// the rank just before it, so distinct keys always have a gap of 1. Summing the gaps
+    /// of the groups seen so far and adding 1 gives the competition ("1224") rank of the
+    /// current group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![10, 20, 10, 30];
+    /// let ranked: Vec<(Rank, usize, i32)> = data.into_iter().rank_by_with_gap(|&x| x).collect();
+    ///
+    /// let expected = vec![
+    ///     (Rank(1), 0, 10),
+    ///     (Rank(1), 0, 10),
+    ///     (Rank(2), 2, 20),
+    ///     (Rank(3), 1, 30),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_by_with_gap<F, K>(self, f: F) -> impl Iterator<Item = (Rank, usize, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
 
-    #[test]
-    fn test_rank_by_empty_iterator() {
-        let ranked: Vec<(Rank, i32)> = std::iter::empty().rank_by(|&x| x).collect();
-        assert!(ranked.is_empty());
-    }
+    /// Ranks the items by a key, keeping at most `max_per_group` items of each rank.
+    ///
+    /// The kept items are the first ones of each group in stable sorted order; the rest
+    /// of the group is dropped. Ranks are computed over all items, so dropping items
+    /// doesn't change the rank of the following groups.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![10, 20, 10, 30, 20, 10];
+    /// let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by_limit_per_group(2, |&x| x).collect();
+    ///
+    /// let expected = vec![
+    ///     (Rank(1), 10),
+    ///     (Rank(1), 10),
+    ///     (Rank(2), 20),
+    ///     (Rank(2), 20),
+    ///     (Rank(3), 30),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_by_limit_per_group<F, K>(
+        self,
+        max_per_group: usize,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
 
-    #[test]
+    /// Ranks the items by a key, reporting progress as the ranked items are consumed.
+    ///
+    /// `progress` is called with `(emitted, total)` each time an item is yielded, where
+    /// `emitted` counts the items yielded so far, including the current one, and `total`
+    /// is the number of items, known once they have been collected and sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let mut calls = Vec::new();
+    /// let ranked: Vec<_> = vec![3, 1, 2]
+    ///     .into_iter()
+    ///     .rank_by_with_progress(|&x| x, |emitted, total| calls.push((emitted, total)))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked.len(), 3);
+    /// assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+    /// ```
+    fn rank_by_with_progress<F, K, P>(
+        self,
+        f: F,
+        progress: P,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        P: FnMut(usize, usize);
+
+    /// Ranks the items by a key, returning an iterator that can be polled for its
+    /// progress.
+    ///
+    /// This is [`rank_by_with_progress`](RankedExt::rank_by_with_progress) without the
+    /// callback: [`ProgressRankedBy::progress`] returns `(emitted, total)` whenever the
+    /// caller asks. The total is known as soon as the method returns, since the items
+    /// are collected and sorted up front.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let mut ranked = vec![3, 1, 2].into_iter().rank_by_with_progress_iter(|&x| x);
+    /// assert_eq!(ranked.progress(), (0, 3));
+    ///
+    /// ranked.next();
+    /// assert_eq!(ranked.progress(), (1, 3));
+    /// ```
+    fn rank_by_with_progress_iter<F, K>(
+        self,
+        f: F,
+    ) -> ProgressRankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and collects them into rank groups.
+    ///
+    /// The groups are in ascending rank order, and the items of each group keep their
+    /// stable sorted order. Every group is non-empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![10, 20, 10, 30, 20, 10];
+    /// let groups = data.into_iter().rank_by_to_groups(|&x| x);
+    ///
+    /// let expected = vec![
+    ///     (Rank(1), vec![10, 10, 10]),
+    ///     (Rank(2), vec![20, 20]),
+    ///     (Rank(3), vec![30]),
+    /// ];
+    /// assert_eq!(groups, expected);
+    /// ```
+    fn rank_by_to_groups<F, K>(self, f: F) -> Vec<(Rank, Vec<Self::Item>)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Like [`rank_by_to_groups`](RankedExt::rank_by_to_groups), but with the groups in
+    /// descending rank order, highest rank first.
+    fn rank_by_to_groups_desc<F, K>(self, f: F) -> Vec<(Rank, Vec<Self::Item>)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key that may be missing, giving the rest a fallback rank.
+    ///
+    /// Items for which `f` returns `Some` are ranked as with
+    /// [`rank_by`](RankedExt::rank_by). They are followed by all items for which `f`
+    /// returns `None`, each with `fallback_rank` and in their input order. The fallback
+    /// rank is not validated, so e.g. `Rank(0)` can be used to mark the unranked items.
+    /// Each key is computed exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec!["20", "n/a", "10"];
+    /// let ranked: Vec<(Rank, &str)> = data
+    ///     .into_iter()
+    ///     .rank_by_with_fallback(|s| s.parse::<u32>().ok(), Rank(0))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), "10"), (Rank(2), "20"), (Rank(0), "n/a")]);
+    /// ```
+    fn rank_by_with_fallback<F, K>(
+        self,
+        f: F,
+        fallback_rank: Rank,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> Option<K>,
+        K: Ord + Eq;
+
+    /// Ranks the items by a fallible key, failing on the first error.
+    ///
+    /// All keys are computed, in input order, before anything is ranked, so an error is
+    /// returned before any item is yielded. Each key is computed exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked: Vec<(Rank, &str)> = vec!["20", "10"]
+    ///     .into_iter()
+    ///     .rank_by_result_key(|s| s.parse::<u32>())
+    ///     .unwrap()
+    ///     .collect();
+    /// assert_eq!(ranked, vec![(Rank(1), "10"), (Rank(2), "20")]);
+    ///
+    /// let failed = vec!["20", "x"].into_iter().rank_by_result_key(|s| s.parse::<u32>());
+    /// assert!(failed.is_err());
+    /// ```
+    fn rank_by_result_key<F, K, E>(
+        self,
+        f: F,
+    ) -> Result<impl Iterator<Item = (Rank, Self::Item)>, E>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> Result<K, E>,
+        K: Ord + Eq;
+
+    /// Ranks the items by a fallible key, skipping the items whose key is an error.
+    ///
+    /// Each key is computed exactly once.
+    fn rank_by_result_key_filter_ok<F, K, E>(
+        self,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> Result<K, E>,
+        K: Ord + Eq;
+
+    /// Ranks the items by a primary key, ordering tied items by a display key.
+    ///
+    /// Items are sorted by `(primary_key, display_key)`, but ranks are assigned by the
+    /// primary key alone: items with equal primary keys share a rank and are yielded in
+    /// display key order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    ///
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let scores = vec![("Bob", 100), ("Charlie", 90), ("Alice", 100)];
+    /// let ranked: Vec<(Rank, (&str, u32))> = scores
+    ///     .into_iter()
+    ///     .rank_by_display_order(|&(_, score)| Reverse(score), |&(name, _)| name)
+    ///     .collect();
+    ///
+    /// let expected = vec![
+    ///     (Rank(1), ("Alice", 100)),
+    ///     (Rank(1), ("Bob", 100)),
+    ///     (Rank(2), ("Charlie", 90)),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_by_display_order<F1, K1, F2, K2>(
+        self,
+        primary_key: F1,
+        display_key: F2,
+    ) -> RankedBy<impl Iterator<Item = Self::Item>, F1, K1>
+    where
+        Self: Sized,
+        F1: FnMut(&Self::Item) -> K1,
+        K1: Ord + Eq,
+        F2: FnMut(&Self::Item) -> K2,
+        K2: Ord;
+
+    /// Ranks the items by a key and converts each rank into a score.
+    ///
+    /// `score_fn` receives the dense rank and the total number of distinct ranks, which
+    /// is known once all items are sorted. [`linear_score`] and [`log_score`] are ready
+    /// made score functions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    /// use librank::rank::linear_score;
+    ///
+    /// let data = vec![30, 10, 20, 10];
+    /// let scores: Vec<(f64, i32)> = data.into_iter().rank_by_as_scores(|&x| x, linear_score).collect();
+    ///
+    /// assert_eq!(scores, vec![(1.0, 10), (1.0, 10), (0.5, 20), (0.0, 30)]);
+    /// ```
+    fn rank_by_as_scores<F, K, S>(
+        self,
+        f: F,
+        score_fn: S,
+    ) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        S: Fn(Rank, usize) -> f64;
+
+    /// Returns the item at the `p`-th percentile of the items sorted by a key.
+    ///
+    /// `p` is in `[0.0, 100.0]`; the item is taken at sorted position
+    /// `floor(p / 100 * (n - 1))`, so 0 gives the minimum, 100 the maximum and 50 the
+    /// (lower) median. If that position falls inside a group of tied items, the first
+    /// item of the group in stable sort order is returned. Returns `None` for an empty
+    /// input or a `p` outside the range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![50, 10, 40, 20, 30];
+    /// assert_eq!(data.clone().into_iter().rank_by_nth_percentile(50.0, |&x| x), Some(30));
+    /// assert_eq!(data.into_iter().rank_by_nth_percentile(100.0, |&x| x), Some(50));
+    /// ```
+    fn rank_by_nth_percentile<F, K>(self, p: f64, f: F) -> Option<Self::Item>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by how far their key is from `center`, closest first.
+    ///
+    /// The distance is `|key - center|`, computed without underflow for unsigned
+    /// types, so items equidistant on either side of the center share a rank. See
+    /// [`rank_by_symmetric_with`](RankedExt::rank_by_symmetric_with) for keys with a
+    /// custom distance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![18u32, 25, 21, 19];
+    /// let ranked: Vec<(Rank, u32)> = data.into_iter().rank_by_symmetric(|&t| t, 20).collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), 21), (Rank(1), 19), (Rank(2), 18), (Rank(3), 25)]);
+    /// ```
+    fn rank_by_symmetric<F, K>(self, f: F, center: K) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Copy + std::ops::Sub<Output = K>;
+
+    /// Ranks the items by the distance of their key from `center`, closest first.
+    ///
+    /// `distance` is called with the item's key and the center and may return any
+    /// ordered type.
+    fn rank_by_symmetric_with<F, K, G, D>(
+        self,
+        f: F,
+        center: K,
+        distance: G,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        G: FnMut(&K, &K) -> D,
+        D: Ord + Eq;
+
+    /// Ranks the items by a key and yields each dense rank normalized to `[0.0, 1.0]`.
+    ///
+    /// The value is `(rank - 1) / (max_rank - 1)`: 0.0 for the best (lowest key) items
+    /// and 1.0 for the worst, with the ranks in between evenly spaced regardless of how
+    /// many items each rank holds. If all items share one rank they all get 0.0. Use
+    /// [`normalized_rank_by_rev`](RankedExt::normalized_rank_by_rev) for 1.0 as the best.
+    ///
+    /// This is not [`percent_rank_by`](RankedExt::percent_rank_by), which is based on
+    /// competition ranks and so depends on the size of the groups before each item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![1, 1, 1, 2, 3];
+    /// let normalized: Vec<f64> = data.into_iter().normalized_rank_by(|&x| x).map(|(r, _)| r).collect();
+    ///
+    /// assert_eq!(normalized, vec![0.0, 0.0, 0.0, 0.5, 1.0]);
+    /// ```
+    fn normalized_rank_by<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Like [`normalized_rank_by`](RankedExt::normalized_rank_by), but with 1.0 for the
+    /// best items and 0.0 for the worst. If all items share one rank they all get 1.0.
+    fn normalized_rank_by_rev<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and yields each with its SQL `PERCENT_RANK()`.
+    ///
+    /// The value is `(competition_rank - 1) / (n - 1)`, where the competition rank is
+    /// the 1-based position of the first item of its tie group, and `n` is the number of
+    /// items. A single item gets 0.0.
+    ///
+    /// Unlike [`normalized_rank_by`](RankedExt::normalized_rank_by), which spaces the
+    /// dense ranks evenly, this reflects how many items precede each tie group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![1, 1, 1, 2, 3];
+    /// let percent: Vec<f64> = data.into_iter().percent_rank_by(|&x| x).map(|(r, _)| r).collect();
+    ///
+    /// assert_eq!(percent, vec![0.0, 0.0, 0.0, 0.75, 1.0]);
+    /// ```
+    fn percent_rank_by<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items above and below a reference key independently.
+    ///
+    /// Each item is yielded with a flag that is `true` if its key is above `reference`
+    /// and `false` if it is below. Within each side, rank 1 is the key closest to the
+    /// reference. Items whose key equals the reference are placed on the side given by
+    /// `equal`, where they get rank 1. Items are yielded in ascending key order, so the
+    /// items below come first with decreasing ranks, followed by the items above with
+    /// increasing ranks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    /// use librank::{Rank, RelativeSide};
+    ///
+    /// let data = vec![7, 3, 5, 9, 4];
+    /// let ranked: Vec<(Rank, bool, i32)> =
+    ///     data.into_iter().rank_by_relative(|&x| x, 5, RelativeSide::Above).collect();
+    ///
+    /// let expected = vec![
+    ///     (Rank(2), false, 3),
+    ///     (Rank(1), false, 4),
+    ///     (Rank(1), true, 5),
+    ///     (Rank(2), true, 7),
+    ///     (Rank(3), true, 9),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_by_relative<F, K>(
+        self,
+        f: F,
+        reference: K,
+        equal: RelativeSide,
+    ) -> impl Iterator<Item = (Rank, bool, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key, from both ends at once.
+    ///
+    /// Each item is yielded with its dense rank counted from the lowest key and its
+    /// dense rank counted from the highest key, so that for every item
+    /// `forward + backward == distinct_ranks + 1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![20, 10, 30, 10];
+    /// let ranked: Vec<(Rank, Rank, i32)> = data.into_iter().rank_bidirectional_by(|&x| x).collect();
+    ///
+    /// let expected = vec![
+    ///     (Rank(1), Rank(3), 10),
+    ///     (Rank(1), Rank(3), 10),
+    ///     (Rank(2), Rank(2), 20),
+    ///     (Rank(3), Rank(1), 30),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_bidirectional_by<F, K>(self, f: F) -> impl Iterator<Item = (Rank, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key, ordering tied items with a comparator.
+    ///
+    /// Like [`rank_by_display_order`](RankedExt::rank_by_display_order), ranks depend
+    /// only on the key, and `tie_break` only decides the order of items with equal keys.
+    /// A comparator that is a total order on the items, such as comparing unique ids,
+    /// makes the output independent of the input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![(2, 'b'), (1, 'z'), (2, 'a')];
+    /// let ranked: Vec<(Rank, (i32, char))> = data
+    ///     .into_iter()
+    ///     .rank_by_with_tie_break(|&(k, _)| k, |a, b| a.1.cmp(&b.1))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), (1, 'z')), (Rank(2), (2, 'a')), (Rank(2), (2, 'b'))]);
+    /// ```
+    fn rank_by_with_tie_break<F, K, C>(
+        self,
+        f: F,
+        tie_break: C,
+    ) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        C: Fn(&Self::Item, &Self::Item) -> Ordering;
+
+    /// Ranks the items by a key within each partition defined by a group key.
+    ///
+    /// This is SQL's `DENSE_RANK() OVER (PARTITION BY group ORDER BY key)`: the rank
+    /// restarts at 1 for every distinct group. The output is sorted by `(group, key)`,
+    /// so the items of each group are yielded together, groups in ascending order, and
+    /// ties keep their input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let salaries = vec![("eng", 120), ("ops", 90), ("eng", 100), ("ops", 90)];
+    /// let ranked: Vec<(&str, Rank, (&str, u32))> = salaries
+    ///     .into_iter()
+    ///     .rank_within_by(|&(dept, _)| dept, |&(_, salary)| salary)
+    ///     .collect();
+    ///
+    /// let expected = vec![
+    ///     ("eng", Rank(1), ("eng", 100)),
+    ///     ("eng", Rank(2), ("eng", 120)),
+    ///     ("ops", Rank(1), ("ops", 90)),
+    ///     ("ops", Rank(1), ("ops", 90)),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_within_by<GF, G, F, K>(
+        self,
+        group_fn: GF,
+        f: F,
+    ) -> impl Iterator<Item = (G, Rank, Self::Item)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord + Eq,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and yields each with its ordinal position scaled to
+    /// `[0.0, 1.0]`.
+    ///
+    /// The value is `i / (n - 1)`, where `i` is the item's 0-based position in the
+    /// ranking and `n` is the number of items, so the first item gets 0.0 and the last
+    /// gets 1.0. Tied items are spaced out in their input order; for tie-aware values
+    /// use [`normalized_rank_by`](RankedExt::normalized_rank_by), which scales the dense
+    /// rank instead. A single item gets 0.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![3, 1, 1, 2, 5];
+    /// let scaled: Vec<(f64, i32)> = data.into_iter().rank_by_normalize_count(|&x| x).collect();
+    ///
+    /// assert_eq!(scaled, vec![(0.0, 1), (0.25, 1), (0.5, 2), (0.75, 3), (1.0, 5)]);
+    /// ```
+    fn rank_by_normalize_count<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the groups of items by an aggregate key, and the items within each group by
+    /// a member key.
+    ///
+    /// Items are partitioned by `group_fn`. `group_key_fn` receives the members of each
+    /// group, in input order, and returns the key the group is ranked by; `f` ranks the
+    /// members within their group. Both levels use dense ranking and break ties
+    /// independently: a group's rank doesn't depend on how its members rank.
+    ///
+    /// The output is ordered by group rank, then by member rank. Groups tied on their key
+    /// are yielded in ascending group order, and tied members in input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    ///
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let scores = vec![("red", 3), ("blue", 9), ("red", 5), ("blue", 4)];
+    /// let ranked: Vec<(Rank, &str, Rank, u32)> = scores
+    ///     .into_iter()
+    ///     .rank_hierarchical_by(
+    ///         |&(team, _)| team,
+    ///         |members| Reverse(members.iter().map(|&(_, score)| score).sum::<u32>()),
+    ///         |&(_, score)| Reverse(score),
+    ///     )
+    ///     .map(|(team_rank, team, rank, (_, score))| (team_rank, team, rank, score))
+    ///     .collect();
+    ///
+    /// let expected = vec![
+    ///     (Rank(1), "blue", Rank(1), 9),
+    ///     (Rank(1), "blue", Rank(2), 4),
+    ///     (Rank(2), "red", Rank(1), 5),
+    ///     (Rank(2), "red", Rank(2), 3),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_hierarchical_by<GF, G, GKF, GK, F, K>(
+        self,
+        group_fn: GF,
+        group_key_fn: GKF,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, G, Rank, Self::Item)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord + Clone,
+        GKF: FnMut(&[Self::Item]) -> GK,
+        GK: Ord + Eq,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and clamps each rank to `[min, max]`.
+    ///
+    /// The ranks are computed as in [`rank_by`](RankedExt::rank_by) and then clamped:
+    /// ranks below `min` become `Rank(min)` and ranks above `max` become `Rank(max)`.
+    /// The order of the items is unaffected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min > max`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![10, 20, 30, 40];
+    /// let ranks: Vec<Rank> = data.into_iter().rank_by_clamp(2, 3, |&x| x).map(|(r, _)| r).collect();
+    ///
+    /// assert_eq!(ranks, vec![Rank(2), Rank(2), Rank(3), Rank(3)]);
+    /// ```
+    fn rank_by_clamp<F, K>(
+        self,
+        min: usize,
+        max: usize,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Yields the `k` best items by key, ranked.
+    ///
+    /// Only `k` items are held at a time, in a bounded heap, so this runs in O(n log k)
+    /// time and O(k) memory. Exactly `min(k, n)` items are yielded: when items tie
+    /// across the cutoff, the ones that came first in the input are kept. Ranks are
+    /// dense over the kept items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![('a', 3), ('b', 1), ('c', 2), ('d', 2), ('e', 5)];
+    /// let top: Vec<(Rank, char)> = data.into_iter().top_k_by(2, |&(_, k)| k).map(|(r, (c, _))| (r, c)).collect();
+    ///
+    /// assert_eq!(top, vec![(Rank(1), 'b'), (Rank(2), 'c')]);
+    /// ```
+    fn top_k_by<F, K>(self, k: usize, f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Yields the `k` best items by key within each partition defined by a group key.
+    ///
+    /// Each group keeps its own bounded heap, so memory is O(groups × k). The cutoff
+    /// within a group works as in [`top_k_by`](RankedExt::top_k_by): ties across it are
+    /// resolved in input order, and ranks are dense over the kept items. As with
+    /// [`rank_within_by`](RankedExt::rank_within_by), groups are yielded in ascending
+    /// order with their members together.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    ///
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let sales = vec![("books", 5), ("toys", 8), ("books", 7), ("toys", 2), ("books", 1)];
+    /// let top: Vec<(&str, Rank, u32)> = sales
+    ///     .into_iter()
+    ///     .top_k_within_by(2, |&(category, _)| category, |&(_, sold)| Reverse(sold))
+    ///     .map(|(category, rank, (_, sold))| (category, rank, sold))
+    ///     .collect();
+    ///
+    /// let expected = vec![
+    ///     ("books", Rank(1), 7),
+    ///     ("books", Rank(2), 5),
+    ///     ("toys", Rank(1), 8),
+    ///     ("toys", Rank(2), 2),
+    /// ];
+    /// assert_eq!(top, expected);
+    /// ```
+    fn top_k_within_by<GF, G, F, K>(
+        self,
+        k: usize,
+        group_fn: GF,
+        f: F,
+    ) -> impl Iterator<Item = (G, Rank, Self::Item)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord + Clone,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key, numbering the ranks from `start` instead of 1.
+    ///
+    /// The first group gets `Rank(start)`, the second `Rank(start + 1)`, and so on; ties
+    /// work as in [`rank_by`](RankedExt::rank_by), which this equals for `start == 1`.
+    /// Use it to continue the numbering of an earlier batch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![20, 10, 20];
+    /// let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by_offset(4, |&x| x).collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(4), 10), (Rank(5), 20), (Rank(5), 20)]);
+    /// ```
+    fn rank_by_offset<F, K>(self, start: usize, f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Folds the items of each group into an aggregate and ranks the groups by it.
+    ///
+    /// Items are partitioned by `group_fn`, and each group's aggregate starts as a clone
+    /// of `init` and is updated with `fold` for every member, in input order, in a
+    /// single pass. The groups are then ranked by `f` applied to their aggregates and
+    /// yielded as `(rank, group, aggregate)`. Groups tied on their key share a rank and
+    /// are yielded in ascending group order.
+    ///
+    /// Since members are folded in input order, floating-point sums are reproducible
+    /// for a given input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    ///
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let sales = vec![("fr", 10), ("de", 30), ("fr", 25), ("it", 5)];
+    /// let ranked: Vec<(Rank, &str, u32)> = sales
+    ///     .into_iter()
+    ///     .rank_groups_by(|&(country, _)| country, 0, |total, (_, amount)| *total += amount, |&total| Reverse(total))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), "fr", 35), (Rank(2), "de", 30), (Rank(3), "it", 5)]);
+    /// ```
+    fn rank_groups_by<GF, G, A, FF, F, K>(
+        self,
+        group_fn: GF,
+        init: A,
+        fold: FF,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, G, A)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord,
+        A: Clone,
+        FF: FnMut(&mut A, Self::Item),
+        F: FnMut(&A) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and passes each rank through `rank_map`.
+    ///
+    /// This covers numbering schemes that [`rank_by_offset`](RankedExt::rank_by_offset)
+    /// and [`rank_by_clamp`](RankedExt::rank_by_clamp) don't. `rank_map` should be
+    /// non-decreasing so the output stays a ranking, with tied items sharing a rank;
+    /// this is not checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let data = vec![3, 1, 2];
+    /// let ranks: Vec<Rank> = data
+    ///     .into_iter()
+    ///     .rank_by_map_rank(|&x| x, |r| Rank(r.0 * 2))
+    ///     .map(|(r, _)| r)
+    ///     .collect();
+    ///
+    /// assert_eq!(ranks, vec![Rank(2), Rank(4), Rank(6)]);
+    /// ```
+    fn rank_by_map_rank<F, K, R>(
+        self,
+        f: F,
+        rank_map: R,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        R: FnMut(Rank) -> Rank;
+
+    /// Ranks every pair of an item of this iterator with an item of `other`.
+    ///
+    /// All `n * m` pairs of the Cartesian product are built, cloning the items as
+    /// needed, and ranked together by `f`, with ties keeping the pairs in row-major
+    /// order. If either side is empty, nothing is yielded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked: Vec<(Rank, i32, i32)> = vec![1, 2]
+    ///     .into_iter()
+    ///     .rank_by_cross(vec![10, 20], |&a, &b| a + b)
+    ///     .collect();
+    ///
+    /// let expected = vec![
+    ///     (Rank(1), 1, 10),
+    ///     (Rank(2), 2, 10),
+    ///     (Rank(3), 1, 20),
+    ///     (Rank(4), 2, 20),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_by_cross<J, F, K>(
+        self,
+        other: J,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item, J::Item)>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        J: IntoIterator,
+        J::Item: Clone,
+        F: FnMut(&Self::Item, &J::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks each item among all the items seen so far, itself included.
+    ///
+    /// This is an expanding window: items are yielded lazily in arrival order, each with
+    /// its dense rank at the time it arrived, so it works on unbounded iterators. Earlier
+    /// ranks are never revised. The keys seen are kept in an order-statistic multiset, so
+    /// counting the distinct smaller keys to rank an item is O(log d) expected for `d`
+    /// distinct keys so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranks: Vec<Rank> = (1..).map(|x| (x * 7) % 10).rank_expanding_by(|&x| x).map(|(r, _)| r).take(5).collect();
+    ///
+    /// // 7, 4, 1, 8, 5
+    /// assert_eq!(ranks, vec![Rank(1), Rank(1), Rank(1), Rank(4), Rank(3)]);
+    /// ```
+    fn rank_expanding_by<F, K>(self, f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key within runs of consecutive items sharing a bucket key.
+    ///
+    /// The input is read lazily: each time the bucket key changes, the current run is
+    /// ranked and yielded as `(bucket, rank, item)` before the next run is read, so
+    /// memory is bounded by the largest run rather than the whole input. Within a run,
+    /// items are ordered by key with ties in input order, and ranks restart at 1.
+    ///
+    /// Only consecutive items are grouped together: a bucket key that reappears after
+    /// a different one starts a new run, with its own ranking. Use
+    /// [`rank_within_by`](RankedExt::rank_within_by) to group items regardless of
+    /// their position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let readings = vec![(1, 30), (1, 10), (2, 50), (2, 20), (2, 20)];
+    /// let ranked: Vec<(u32, Rank, u32)> = readings
+    ///     .into_iter()
+    ///     .rank_per_bucket_by(|&(day, _)| day, |&(_, value)| value)
+    ///     .map(|(day, rank, (_, value))| (day, rank, value))
+    ///     .collect();
+    ///
+    /// let expected = vec![
+    ///     (1, Rank(1), 10),
+    ///     (1, Rank(2), 30),
+    ///     (2, Rank(1), 20),
+    ///     (2, Rank(1), 20),
+    ///     (2, Rank(2), 50),
+    /// ];
+    /// assert_eq!(ranked, expected);
+    /// ```
+    fn rank_per_bucket_by<BF, B, F, K>(
+        self,
+        bucket_fn: BF,
+        f: F,
+    ) -> impl Iterator<Item = (B, Rank, Self::Item)>
+    where
+        Self: Sized,
+        BF: FnMut(&Self::Item) -> B,
+        B: Eq + Clone,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key, calling `on_transition(old, new)` whenever the rank
+    /// changes from one item to the next.
+    ///
+    /// The output is that of [`rank_by`](RankedExt::rank_by). The callback isn't called
+    /// for the first item, so it runs `distinct_ranks - 1` times over a full pass, each
+    /// time just before the first item of the new rank is yielded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let mut transitions = Vec::new();
+    /// let count = vec![2, 1, 2, 3]
+    ///     .into_iter()
+    ///     .rank_by_inspect_transitions(|&x| x, |old, new| transitions.push((old, new)))
+    ///     .count();
+    ///
+    /// assert_eq!(count, 4);
+    /// assert_eq!(transitions, vec![(Rank(1), Rank(2)), (Rank(2), Rank(3))]);
+    /// ```
+    fn rank_by_inspect_transitions<F, K, C>(
+        self,
+        f: F,
+        on_transition: C,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        C: FnMut(Rank, Rank);
+
+    /// Ranks the items by a key and collects the pairs into a `Vec`.
+    ///
+    /// This is shorthand for `.rank_by(f).collect::<Vec<_>>()`. The sorted items are
+    /// moved into an output vector allocated once at its final size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked = vec!['b', 'a', 'b'].into_iter().rank_by_collect(|&c| c);
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), 'a'), (Rank(2), 'b'), (Rank(2), 'b')]);
+    /// ```
+    fn rank_by_collect<F, K>(self, f: F) -> Vec<(Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and maps each ranked item through `transform`.
+    ///
+    /// The ranks are assigned as in [`rank_by`](RankedExt::rank_by) before `transform`
+    /// runs, so they don't depend on it. Use it to rank by one field and keep another.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    ///
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let players = vec![("ann", 7), ("bob", 9)];
+    /// let names: Vec<(Rank, &str)> = players
+    ///     .into_iter()
+    ///     .rank_by_transform(|&(_, score)| Reverse(score), |(name, _)| name)
+    ///     .collect();
+    ///
+    /// assert_eq!(names, vec![(Rank(1), "bob"), (Rank(2), "ann")]);
+    /// ```
+    fn rank_by_transform<F, K, M, U>(self, f: F, transform: M) -> impl Iterator<Item = (Rank, U)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        M: FnMut(Self::Item) -> U;
+
+    /// Ranks items that already arrive sorted by a key, without collecting them.
+    ///
+    /// Each item is yielded as soon as it is read, so this works on unbounded
+    /// iterators. The input must be sorted by `f` in ascending order; this is not
+    /// checked, and an unsorted input gets a new rank wherever the key changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranks: Vec<Rank> = (0..).rank_presorted_by(|&x| x / 2).map(|(r, _)| r).take(5).collect();
+    ///
+    /// assert_eq!(ranks, vec![Rank(1), Rank(1), Rank(2), Rank(2), Rank(3)]);
+    /// ```
+    fn rank_presorted_by<F, K>(self, f: F) -> RankedBy<Self, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Resumes a [`rank_presorted_by`](RankedExt::rank_presorted_by) ranking from a
+    /// [`RankCheckpoint`], ranking the items that follow it.
+    ///
+    /// `self` must be the rest of the sorted input, starting right after the
+    /// [`consumed`](RankCheckpoint::consumed) items read before the checkpoint. An item
+    /// with the same key as the last one before the checkpoint stays in its rank group.
+    /// The checkpoint of the resumed ranking counts the items read before and after.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankCheckpoint, RankedExt};
+    ///
+    /// // Starting from the default checkpoint is the same as `rank_presorted_by`.
+    /// let ranks: Vec<Rank> = [2, 2, 7]
+    ///     .into_iter()
+    ///     .rank_by_with_checkpoint(RankCheckpoint::default(), |&x| x)
+    ///     .map(|(r, _)| r)
+    ///     .collect();
+    ///
+    /// assert_eq!(ranks, vec![Rank(1), Rank(1), Rank(2)]);
+    /// ```
+    fn rank_by_with_checkpoint<F, K>(
+        self,
+        checkpoint: RankCheckpoint<K>,
+        f: F,
+    ) -> RankedBy<Self, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Returns the number of rank groups [`rank_by`](RankedExt::rank_by) would produce,
+    /// which is the number of distinct keys.
+    ///
+    /// The items are sorted and the key changes counted, without building any
+    /// `(Rank, T)` pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// assert_eq!(vec![3, 1, 3, 2].into_iter().rank_by_distinct_count(|&x| x), 3);
+    /// ```
+    fn rank_by_distinct_count<F, K>(self, f: F) -> usize
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and inserts a separator before each rank group but the
+    /// first.
+    ///
+    /// `separator_fn` is called with the rank of the group that is about to start, so it
+    /// runs `distinct_ranks - 1` times and never before the first item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankItem, RankedExt};
+    ///
+    /// let lines: Vec<String> = vec!["b", "a", "b"]
+    ///     .into_iter()
+    ///     .rank_by_interpose(|&s| s, |_| "---")
+    ///     .map(|entry| match entry {
+    ///         RankItem::Item(Rank(r), s) => format!("{r}. {s}"),
+    ///         RankItem::Separator(sep) => sep.to_string(),
+    ///     })
+    ///     .collect();
+    ///
+    /// assert_eq!(lines, vec!["1. a", "---", "2. b", "2. b"]);
+    /// ```
+    fn rank_by_interpose<F, K, S, Sep>(
+        self,
+        f: F,
+        separator_fn: S,
+    ) -> impl Iterator<Item = RankItem<Self::Item, Sep>>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        S: FnMut(Rank) -> Sep;
+
+    /// Ranks the items by a key and yields each with its 0-based position within its
+    /// rank group.
+    ///
+    /// Tied items are numbered in their input order, and the position restarts at 0
+    /// with every new rank.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked: Vec<(Rank, usize, char)> = vec![(1, 'a'), (2, 'b'), (1, 'c')]
+    ///     .into_iter()
+    ///     .rank_by_enumerate_within_group(|&(k, _)| k)
+    ///     .map(|(r, i, (_, c))| (r, i, c))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), 0, 'a'), (Rank(1), 1, 'c'), (Rank(2), 0, 'b')]);
+    /// ```
+    fn rank_by_enumerate_within_group<F, K>(
+        self,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, usize, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and yields each with the total number of rank groups,
+    /// which is also the last rank.
+    ///
+    /// The count is the number of distinct keys, not of items, and is the same for every
+    /// item. It's known up front since the items are sorted before the first is yielded,
+    /// which makes it handy for percentages like `rank / total`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked: Vec<(Rank, usize, i32)> = vec![3, 1, 3].into_iter().rank_by_with_total(|&x| x).collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), 2, 1), (Rank(2), 2, 3), (Rank(2), 2, 3)]);
+    /// ```
+    fn rank_by_with_total<F, K>(self, f: F) -> impl Iterator<Item = (Rank, usize, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key with both dense and competition ranking, yielding
+    /// `(dense, competition, item)` for comparing the two.
+    ///
+    /// The two ranks agree on every item when all keys are distinct; after a tie group of
+    /// `n` items the competition rank runs `n - 1` ahead. This is meant for debugging and
+    /// tests; [`RankingStrategy`] gives the other ways of numbering ties.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked = vec![20, 10, 10].into_iter().rank_by_cross_validate(|&x| x);
+    ///
+    /// assert_eq!(
+    ///     ranked,
+    ///     vec![(Rank(1), Rank(1), 10), (Rank(1), Rank(1), 10), (Rank(2), Rank(3), 20)]
+    /// );
+    /// ```
+    fn rank_by_cross_validate<F, K>(self, f: F) -> Vec<(Rank, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key, ordering tied items by a hash of each item instead of
+    /// by their input order.
+    ///
+    /// This makes the output independent of the input order, which helps when the input
+    /// comes from a `HashMap` or a parallel collection, as long as tied items have
+    /// distinct hashes. Tied items with equal hashes keep their input order. The ranks
+    /// are dense, as with [`rank_by`](RankedExt::rank_by). [`stable_hash`] gives hashes
+    /// that don't change across platforms and releases.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let hash = |&(_, name): &(u32, char)| name as u64;
+    /// let ranked: Vec<(Rank, (u32, char))> = vec![(1, 'b'), (2, 'c'), (1, 'a')]
+    ///     .into_iter()
+    ///     .rank_by_stable_hash_tie_break(|&(score, _)| score, hash)
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), (1, 'a')), (Rank(1), (1, 'b')), (Rank(2), (2, 'c'))]);
+    /// ```
+    fn rank_by_stable_hash_tie_break<F, K, H>(
+        self,
+        f: F,
+        hash_fn: H,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        H: FnMut(&Self::Item) -> u64;
+
+    /// Ranks the items by a key and yields each with its 1-based position in the sorted
+    /// order, as `(position, rank, item)`.
+    ///
+    /// The position is the ordinal rank, which counts every item, and the rank is the
+    /// dense rank of [`rank_by`](RankedExt::rank_by). Tied items get consecutive
+    /// positions in their input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked: Vec<(usize, Rank, i32)> =
+    ///     vec![20, 10, 10].into_iter().rank_by_cumulative_sum(|&x| x).collect();
+    ///
+    /// assert_eq!(ranked, vec![(1, Rank(1), 10), (2, Rank(1), 10), (3, Rank(2), 20)]);
+    /// ```
+    fn rank_by_cumulative_sum<F, K>(self, f: F) -> impl Iterator<Item = (usize, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Like [`rank_by`](RankedExt::rank_by), but with an unstable sort, so tied items
+    /// may come out in any order.
+    ///
+    /// The ranks are the same as with `rank_by`. The sort can be faster and doesn't
+    /// allocate beyond collecting the items, which may matter for large inputs where
+    /// the order of ties is irrelevant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranks: Vec<Rank> = vec![20, 10, 20].into_iter().rank_by_unstable(|&x| x).map(|(r, _)| r).collect();
+    ///
+    /// assert_eq!(ranks, vec![Rank(1), Rank(2), Rank(2)]);
+    /// ```
+    fn rank_by_unstable<F, K>(self, f: F) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks only the items matching a predicate, yielding the others after all ranked
+    /// items with no rank.
+    ///
+    /// Excluded items don't count toward the ranks of the included ones, and are yielded
+    /// in their input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked: Vec<(Option<Rank>, i32)> = vec![30, -1, 10, 20]
+    ///     .into_iter()
+    ///     .rank_by_prefilter(|&x| x >= 0, |&x| x)
+    ///     .collect();
+    ///
+    /// assert_eq!(
+    ///     ranked,
+    ///     vec![(Some(Rank(1)), 10), (Some(Rank(2)), 20), (Some(Rank(3)), 30), (None, -1)]
+    /// );
+    /// ```
+    fn rank_by_prefilter<P, F, K>(
+        self,
+        pred: P,
+        f: F,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        P: FnMut(&Self::Item) -> bool,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Like [`rank_by`](RankedExt::rank_by), but sorts `(key, index)` pairs instead of
+    /// the items themselves.
+    ///
+    /// The items are collected once and then each is moved out exactly once, when it's
+    /// yielded, so large items aren't shuffled around by the sort. The keys are computed
+    /// once for the sort and once more as the items are yielded. The output is the same
+    /// as that of `rank_by`, including the input order of ties.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let pages = vec![[3u8; 2048], [1u8; 2048], [3u8; 2048]];
+    /// let ranks: Vec<(Rank, u8)> = pages
+    ///     .into_iter()
+    ///     .rank_by_indirect(|page| page[0])
+    ///     .map(|(rank, page)| (rank, page[0]))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranks, vec![(Rank(1), 1), (Rank(2), 3), (Rank(2), 3)]);
+    /// ```
+    fn rank_by_indirect<F, K>(self, f: F) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and summarizes each rank group, in ascending rank order.
+    ///
+    /// `first` and `last` are clones of the first and last item of the group in its
+    /// stable sort order, so for a group of one they are the same item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{GroupStats, Rank, RankedExt};
+    ///
+    /// let stats = vec![("b", 2), ("a", 1), ("c", 2)].into_iter().rank_by_group_stats(|&(_, k)| k);
+    ///
+    /// assert_eq!(
+    ///     stats[1],
+    ///     GroupStats {
+    ///         rank: Rank(2),
+    ///         count: 2,
+    ///         first: ("b", 2),
+    ///         last: ("c", 2),
+    ///         all_items: vec![("b", 2), ("c", 2)],
+    ///     }
+    /// );
+    /// ```
+    fn rank_by_group_stats<F, K>(self, f: F) -> Vec<GroupStats<Self::Item>>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks only the eligible items and yields every item, with no rank for the
+    /// ineligible ones.
+    ///
+    /// The ranks of the eligible items are the same as if the ineligible ones had been
+    /// filtered out first. `placement` chooses where the ineligible items go: after all
+    /// ranked items, as with [`rank_by_prefilter`](RankedExt::rank_by_prefilter), or at
+    /// their input positions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{IneligiblePlacement, Rank, RankedExt};
+    ///
+    /// let players = vec![("ann", 7, true), ("bob", 9, false), ("cy", 3, true)];
+    /// let ranked: Vec<(Option<Rank>, &str)> = players
+    ///     .into_iter()
+    ///     .rank_eligible_by(|&(_, _, ok)| ok, |&(_, score, _)| score, IneligiblePlacement::InPlace)
+    ///     .map(|(rank, (name, _, _))| (rank, name))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Some(Rank(1)), "cy"), (None, "bob"), (Some(Rank(2)), "ann")]);
+    /// ```
+    fn rank_eligible_by<E, F, K>(
+        self,
+        eligible: E,
+        f: F,
+        placement: IneligiblePlacement,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        E: FnMut(&Self::Item) -> bool,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key with a strategy chosen at runtime, such as one read from
+    /// a configuration file.
+    ///
+    /// The ranks are `f64` so that [`RankingStrategy::Fractional`] can be represented;
+    /// the other strategies always give whole numbers. Ties keep their input order,
+    /// which is also the order that [`RankingStrategy::Ordinal`] numbers them in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{RankedExt, RankingStrategy};
+    ///
+    /// let ranks = |strategy| -> Vec<f64> {
+    ///     vec![10, 20, 20, 30].into_iter().rank_by_strategy(strategy, |&x| x).map(|(r, _)| r).collect()
+    /// };
+    ///
+    /// assert_eq!(ranks(RankingStrategy::Competition), vec![1.0, 2.0, 2.0, 4.0]);
+    /// assert_eq!(ranks(RankingStrategy::Fractional), vec![1.0, 2.5, 2.5, 4.0]);
+    /// ```
+    fn rank_by_strategy<F, K>(
+        self,
+        strategy: RankingStrategy,
+        f: F,
+    ) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Looks up the rank of each item's key in a reference [`RankTable`], yielding
+    /// `None` for keys the table doesn't contain.
+    ///
+    /// The items are not sorted or buffered; each lookup is a binary search, O(log n)
+    /// in the size of the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankTable, RankedExt};
+    ///
+    /// let table: RankTable<u32> = [90, 70, 80].into_iter().collect();
+    /// let ranks: Vec<(Option<Rank>, u32)> = vec![80, 75].into_iter().annotate_ranks(&table, |&x| x).collect();
+    ///
+    /// assert_eq!(ranks, vec![(Some(Rank(2)), 80), (None, 75)]);
+    /// ```
+    fn annotate_ranks<F, K>(
+        self,
+        table: &RankTable<K>,
+        f: F,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord;
+
+    /// Like [`annotate_ranks`](RankedExt::annotate_ranks), but a key missing from the
+    /// table gets the rank of the greatest key in the table below it, as with
+    /// [`RankTable::floor_rank`]. Only keys below every key in the table get `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankTable, RankedExt};
+    ///
+    /// // The score thresholds of the grades, lowest first.
+    /// let thresholds: RankTable<u32> = [50, 70, 90].into_iter().collect();
+    /// let ranks: Vec<(Option<Rank>, u32)> =
+    ///     vec![75, 95, 20].into_iter().annotate_ranks_floor(&thresholds, |&x| x).collect();
+    ///
+    /// assert_eq!(ranks, vec![(Some(Rank(2)), 75), (Some(Rank(3)), 95), (None, 20)]);
+    /// ```
+    fn annotate_ranks_floor<F, K>(
+        self,
+        table: &RankTable<K>,
+        f: F,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord;
+
+    /// Ranks the items by a key, sorting the keys with `cmp` and grouping them into ranks
+    /// with `eq`.
+    ///
+    /// This separates the two questions [`rank_by`](RankedExt::rank_by) answers with
+    /// [`Ord`]: `cmp` decides the order and `eq` decides which neighbours share a rank.
+    /// The keys need no `Ord` implementation, so `f64` keys work with
+    /// [`f64::total_cmp`]. An item starts a new rank unless `eq` holds between its key and
+    /// the key of the item just before it, so with an epsilon comparison a run of close
+    /// values can share a rank even if its ends are further apart. Items that `eq`
+    /// treats as equal must be adjacent in `cmp` order to share a rank. The sort is
+    /// stable and `key_fn` is called once per item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let times = vec![10.02, 9.58, 10.0, 9.63];
+    /// let ranks: Vec<(Rank, f64)> = times
+    ///     .into_iter()
+    ///     .rank_by_with_eq_ord(|&t| t, f64::total_cmp, |a, b| (a - b).abs() < 0.05)
+    ///     .collect();
+    ///
+    /// assert_eq!(ranks, vec![(Rank(1), 9.58), (Rank(2), 9.63), (Rank(3), 10.0), (Rank(3), 10.02)]);
+    /// ```
+    fn rank_by_with_eq_ord<F, K, C, E>(
+        self,
+        key_fn: F,
+        cmp: C,
+        eq: E,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        C: Fn(&K, &K) -> Ordering,
+        E: Fn(&K, &K) -> bool;
+
+    /// Ranks the items by a key and places each at an output position chosen by
+    /// `target_fn`.
+    ///
+    /// `target_fn` is called with the rank of each item and its index within its rank
+    /// group, in rank order, and returns the item's position. This suits layouts such as
+    /// interleaving rank-1 and rank-2 items. [`sequential_positions`] places the items
+    /// one after the other, as `rank_by` would.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ScatterConflict`] if two items get the same position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// // Gold medals at even positions, silver ones at odd positions.
+    /// let medals = vec![("ann", 2), ("bob", 1), ("cy", 1), ("dee", 2)];
+    /// let layout = medals
+    ///     .into_iter()
+    ///     .rank_by_scatter(|&(_, m)| m, |rank, i| 2 * i + rank.0 - 1)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(layout[&0], (Rank(1), ("bob", 1)));
+    /// assert_eq!(layout[&1], (Rank(2), ("ann", 2)));
+    /// assert_eq!(layout[&2], (Rank(1), ("cy", 1)));
+    /// assert_eq!(layout[&3], (Rank(2), ("dee", 2)));
+    /// ```
+    fn rank_by_scatter<F, K, G>(
+        self,
+        f: F,
+        target_fn: G,
+    ) -> Result<HashMap<usize, (Rank, Self::Item)>, ScatterConflict>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        G: FnMut(Rank, usize) -> usize;
+
+    /// Ranks the items by a key and collects each rank group into a `BTreeSet`, dropping
+    /// duplicate items within a group.
+    ///
+    /// Duplicates are items equal by their own `Ord`, such as rows repeated by a join.
+    /// Ranks are assigned before deduplicating, so they are the same as with
+    /// [`rank_by`](RankedExt::rank_by). Each set is in the items' own order rather than
+    /// the input order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let rows = vec![("bob", 2), ("ann", 1), ("bob", 2), ("cy", 2)];
+    /// let groups = rows.into_iter().rank_by_into_btreeset(|&(_, points)| points);
+    ///
+    /// assert_eq!(groups[&Rank(1)], BTreeSet::from([("ann", 1)]));
+    /// assert_eq!(groups[&Rank(2)], BTreeSet::from([("bob", 2), ("cy", 2)]));
+    /// ```
+    fn rank_by_into_btreeset<F, K>(self, f: F) -> BTreeMap<Rank, BTreeSet<Self::Item>>
+    where
+        Self: Sized,
+        Self::Item: Ord,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and turns each into a value of any type with
+    /// `annotate_fn`.
+    ///
+    /// `annotate_fn` receives the rank of the item, its 0-based position within its rank
+    /// group, the size of the group and the item itself. This covers what
+    /// [`rank_by_enumerate_within_group`](RankedExt::rank_by_enumerate_within_group) does,
+    /// as well as tie flags, fractional ranks and formatted labels. Group sizes are
+    /// known because the items are sorted before any is annotated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let times = vec![("ann", 61), ("bob", 58), ("cy", 61)];
+    /// let labels: Vec<String> = times
+    ///     .into_iter()
+    ///     .rank_by_annotate(
+    ///         |&(_, t)| t,
+    ///         |rank, _, size, (name, _)| {
+    ///             let tie = if size > 1 { "T" } else { "" };
+    ///             format!("{tie}{} {name}", rank.0)
+    ///         },
+    ///     )
+    ///     .collect();
+    ///
+    /// assert_eq!(labels, vec!["1 bob", "T2 ann", "T2 cy"]);
+    /// ```
+    fn rank_by_annotate<F, K, G, A>(self, f: F, annotate_fn: G) -> impl Iterator<Item = A>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        G: FnMut(Rank, usize, usize, Self::Item) -> A;
+
+    /// Ranks the items by a key and yields the rank groups from the highest rank down,
+    /// keeping the ranks of [`rank_by`](RankedExt::rank_by).
+    ///
+    /// This lists the items from worst to best with rank 1 still the smallest key,
+    /// unlike [`rank_by_desc`](RankedExt::rank_by_desc), which gives rank 1 to the
+    /// largest key. Tied items keep their input order within their group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let ranked: Vec<(Rank, i32)> = vec![20, 10, 30, 20].into_iter().rank_by_rev_groups(|&x| x).collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(3), 30), (Rank(2), 20), (Rank(2), 20), (Rank(1), 10)]);
+    /// ```
+    fn rank_by_rev_groups<F, K>(self, f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq;
+
+    /// Ranks the items by a key and pairs each with the label of its rank: the first
+    /// rank gets `labels[0]`, the second `labels[1]` and so on, and the ranks past the
+    /// end of `labels` get `overflow`.
+    ///
+    /// Using an empty `labels` array fails to compile.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::RankedExt;
+    ///
+    /// let times = vec![("ann", 12), ("bob", 10), ("cy", 12), ("dee", 15)];
+    /// let medals: Vec<(&str, &str)> = times
+    ///     .into_iter()
+    ///     .rank_by_label_array(|&(_, t)| t, ["Gold", "Silver"], "None")
+    ///     .map(|(medal, (name, _))| (medal, name))
+    ///     .collect();
+    ///
+    /// assert_eq!(medals, vec![("Gold", "bob"), ("Silver", "ann"), ("Silver", "cy"), ("None", "dee")]);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use librank::RankedExt;
+    ///
+    /// let labels: [&str; 0] = [];
+    /// let _ = vec![1, 2].into_iter().rank_by_label_array(|&x| x, labels, "Other").count();
+    /// ```
+    fn rank_by_label_array<F, K, L, const N: usize>(
+        self,
+        f: F,
+        labels: [L; N],
+        overflow: L,
+    ) -> impl Iterator<Item = (L, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        L: Copy;
+
+    /// Splits the items in two with `group_fn` and ranks each side by a key on its own,
+    /// yielding the items in input order with their side and their rank within it.
+    ///
+    /// This is [`rank_within_by`](RankedExt::rank_within_by) with a `bool` group, but the
+    /// output keeps the input order instead of grouping the items, so interleaved
+    /// results such as alternating men's and women's entries stay interleaved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// // (is_women, time)
+    /// let results = vec![(true, 58), (false, 52), (true, 55), (false, 54)];
+    /// let ranked: Vec<(bool, Rank, u32)> = results
+    ///     .into_iter()
+    ///     .rank_by_bipartite(|&(_, t)| t, |&(women, _)| women)
+    ///     .map(|(women, rank, (_, time))| (women, rank, time))
+    ///     .collect();
+    ///
+    /// assert_eq!(
+    ///     ranked,
+    ///     vec![(true, Rank(2), 58), (false, Rank(1), 52), (true, Rank(1), 55), (false, Rank(2), 54)]
+    /// );
+    /// ```
+    fn rank_by_bipartite<F, K, G>(
+        self,
+        f: F,
+        group_fn: G,
+    ) -> impl Iterator<Item = (bool, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        G: FnMut(&Self::Item) -> bool;
+
+    /// Ranks the items by a key that decays with age, as in time-weighted rankings.
+    ///
+    /// Each key is multiplied by `e^(-decay_rate * age)`, where `age` is how far the
+    /// item's time, from `time_fn`, is behind the latest time among the items. The
+    /// decayed keys are then ranked like [`rank_by`](RankedExt::rank_by), smallest
+    /// first, ordered with [`f64::total_cmp`] and sharing a rank only when equal. This
+    /// takes two passes: the items are collected to find the latest time before any key
+    /// is decayed. `key_fn` and `time_fn` are called once per item. A `decay_rate` of 0
+    /// leaves the keys as they are.
+    ///
+    /// Decay moves older keys toward 0, and the smallest decayed key ranks first, so
+    /// among positive keys the older ones rank *better*. Negate the scores so that higher
+    /// and fresher scores rank first, as in the example below.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// // (name, negated score, day): older scores count for less.
+    /// let posts = vec![("old", -10.0, 0.0), ("new", -10.0, 2.0), ("mid", -7.0, 1.0)];
+    /// let ranked: Vec<(Rank, &str)> = posts
+    ///     .into_iter()
+    ///     .rank_by_temporal_decay(|&(_, score, _)| score, |&(_, _, day)| day, 0.5)
+    ///     .map(|(rank, (name, _, _))| (rank, name))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), "new"), (Rank(2), "mid"), (Rank(3), "old")]);
+    /// ```
+    fn rank_by_temporal_decay<F, G>(
+        self,
+        key_fn: F,
+        time_fn: G,
+        decay_rate: f64,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> f64,
+        G: FnMut(&Self::Item) -> f64;
+
+    /// Ranks the items by a key, giving keys within `epsilon` of each other the same rank.
+    ///
+    /// The keys are sorted, smallest first, and split into groups greedily: the smallest
+    /// key not yet in a group becomes the representative of the next group, and each key
+    /// after it joins the group as long as `key_diff` between it and the representative
+    /// is at most `epsilon`. Every key is thus within `epsilon` of its group's first key,
+    /// so a run of close values can't chain into one rank the way it can with
+    /// [`rank_by_with_eq_ord`](RankedExt::rank_by_with_eq_ord) and a pairwise epsilon
+    /// comparison. `key_diff` is called with the larger key first. The sort is stable and
+    /// `key_fn` is called once per item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two keys can't be compared, such as a NaN `f64` key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::Rank;
+    /// use librank::RankedExt;
+    ///
+    /// let times = vec![1.0, 1.05, 1.1, 1.2, 1.3];
+    /// let ranks: Vec<(Rank, f64)> =
+    ///     times.into_iter().rank_by_coalesce(|&t| t, 0.15, |a, b| a - b).collect();
+    ///
+    /// assert_eq!(
+    ///     ranks,
+    ///     vec![(Rank(1), 1.0), (Rank(1), 1.05), (Rank(1), 1.1), (Rank(2), 1.2), (Rank(2), 1.3)]
+    /// );
+    /// ```
+    fn rank_by_coalesce<F, K, D>(
+        self,
+        key_fn: F,
+        epsilon: K,
+        key_diff: D,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: PartialOrd,
+        D: Fn(&K, &K) -> K;
+}
+
+impl<I> RankedExt for I
+where
+    I: Iterator,
+{
+    fn rank_by<F, K>(self, mut f: F) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut v = Vec::from_iter(self);
+        v.sort_by_key(&mut f);
+        RankedBy::new(v.into_iter(), f)
+    }
+
+    fn rank_by_desc<F, K>(self, mut f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        self.rank_by(move |item| Reverse(f(item)))
+    }
+
+    fn rank_by_nth_group<F, K>(self, n: usize, mut f: F) -> Vec<Self::Item>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut v = Vec::from_iter(self);
+        v.sort_by_key(&mut f);
+        let (mut start, mut end) = (v.len(), v.len());
+        let mut group = 0;
+        let mut prev_key = None;
+        for (i, item) in v.iter().enumerate() {
+            let key = f(item);
+            if prev_key.as_ref() != Some(&key) {
+                group += 1;
+                if group == n {
+                    start = i;
+                } else if group > n {
+                    end = i;
+                    break;
+                }
+                prev_key = Some(key);
+            }
+        }
+        v.truncate(end);
+        v.split_off(start.min(end))
+    }
+
+    fn rank_by_ecdf<F, K>(self, mut f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (v, starts) = sort_into_groups(self, &mut f);
+        let n = v.len();
+        let mut densities = Vec::with_capacity(n);
+        for (i, &start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(n);
+            let density = if n == 1 {
+                0.5
+            } else {
+                (start + end - 1) as f64 / 2.0 / (n - 1) as f64
+            };
+            densities.resize(end, density);
+        }
+        densities.into_iter().zip(v)
+    }
+
+    fn rank_by_with_gap<F, K>(self, mut f: F) -> impl Iterator<Item = (Rank, usize, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (v, starts) = sort_into_groups(self, &mut f);
+        let mut group = 0;
+        let mut gap = 0;
+        v.into_iter().enumerate().map(move |(i, item)| {
+            if starts.get(group) == Some(&i) {
+                if group > 0 {
+                    gap = i - starts[group - 1];
+                }
+                group += 1;
+            }
+            (Rank(group), gap, item)
+        })
+    }
+
+    fn rank_by_limit_per_group<F, K>(
+        self,
+        max_per_group: usize,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut prev_rank = Rank(0);
+        let mut taken = 0;
+        self.rank_by(f).filter(move |&(rank, _)| {
+            if rank != prev_rank {
+                prev_rank = rank;
+                taken = 0;
+            }
+            taken += 1;
+            taken <= max_per_group
+        })
+    }
+
+    fn rank_by_with_progress<F, K, P>(
+        self,
+        f: F,
+        mut progress: P,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        P: FnMut(usize, usize),
+    {
+        let ranked = self.rank_by(f);
+        let total = ranked.size_hint().0;
+        let mut emitted = 0;
+        ranked.inspect(move |_| {
+            emitted += 1;
+            progress(emitted, total);
+        })
+    }
+
+    fn rank_by_with_progress_iter<F, K>(
+        self,
+        f: F,
+    ) -> ProgressRankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let inner = self.rank_by(f);
+        let total = inner.size_hint().0;
+        ProgressRankedBy { inner, total }
+    }
+
+    fn rank_by_to_groups<F, K>(self, f: F) -> Vec<(Rank, Vec<Self::Item>)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut groups = self.rank_by_to_groups_desc(f);
+        groups.reverse();
+        groups
+    }
+
+    fn rank_by_to_groups_desc<F, K>(self, mut f: F) -> Vec<(Rank, Vec<Self::Item>)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (mut v, starts) = sort_into_groups(self, &mut f);
+        let mut groups = Vec::with_capacity(starts.len());
+        for (i, &start) in starts.iter().enumerate().rev() {
+            groups.push((Rank(i + 1), v.split_off(start)));
+        }
+        groups
+    }
+
+    fn rank_by_with_fallback<F, K>(
+        self,
+        mut f: F,
+        fallback_rank: Rank,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> Option<K>,
+        K: Ord + Eq,
+    {
+        let mut keyed = Vec::new();
+        let mut missing = Vec::new();
+        for item in self {
+            match f(&item) {
+                Some(key) => keyed.push((key, item)),
+                None => missing.push(item),
+            }
+        }
+        rank_keyed(keyed).chain(missing.into_iter().map(move |item| (fallback_rank, item)))
+    }
+
+    fn rank_by_result_key<F, K, E>(
+        self,
+        mut f: F,
+    ) -> Result<impl Iterator<Item = (Rank, Self::Item)>, E>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> Result<K, E>,
+        K: Ord + Eq,
+    {
+        let keyed = self
+            .map(|item| f(&item).map(|key| (key, item)))
+            .collect::<Result<Vec<_>, E>>()?;
+        Ok(rank_keyed(keyed))
+    }
+
+    fn rank_by_result_key_filter_ok<F, K, E>(
+        self,
+        mut f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> Result<K, E>,
+        K: Ord + Eq,
+    {
+        let keyed = self
+            .filter_map(|item| f(&item).ok().map(|key| (key, item)))
+            .collect();
+        rank_keyed(keyed)
+    }
+
+    fn rank_by_display_order<F1, K1, F2, K2>(
+        self,
+        mut primary_key: F1,
+        mut display_key: F2,
+    ) -> RankedBy<impl Iterator<Item = Self::Item>, F1, K1>
+    where
+        Self: Sized,
+        F1: FnMut(&Self::Item) -> K1,
+        K1: Ord + Eq,
+        F2: FnMut(&Self::Item) -> K2,
+        K2: Ord,
+    {
+        let mut v = Vec::from_iter(self);
+        v.sort_by(|a, b| {
+            primary_key(a)
+                .cmp(&primary_key(b))
+                .then_with(|| display_key(a).cmp(&display_key(b)))
+        });
+        RankedBy::new(v.into_iter(), primary_key)
+    }
+
+    fn rank_by_as_scores<F, K, S>(
+        self,
+        f: F,
+        score_fn: S,
+    ) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        S: Fn(Rank, usize) -> f64,
+    {
+        let ranking: Ranking<Self::Item> = self.rank_by(f).collect();
+        let total = ranking.distinct_ranks();
+        ranking
+            .into_iter()
+            .map(move |(rank, item)| (score_fn(rank, total), item))
+    }
+
+    fn rank_by_nth_percentile<F, K>(self, p: f64, mut f: F) -> Option<Self::Item>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        if !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let (mut v, starts) = sort_into_groups(self, &mut f);
+        if v.is_empty() {
+            return None;
+        }
+        let position = (p / 100.0 * (v.len() - 1) as f64) as usize;
+        let group = starts.partition_point(|&start| start <= position) - 1;
+        Some(v.swap_remove(starts[group]))
+    }
+
+    fn rank_by_symmetric<F, K>(self, f: F, center: K) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Copy + std::ops::Sub<Output = K>,
+    {
+        self.rank_by_symmetric_with(f, center, |&key, &center| {
+            if key >= center {
+                key - center
+            } else {
+                center - key
+            }
+        })
+    }
+
+    fn rank_by_symmetric_with<F, K, G, D>(
+        self,
+        mut f: F,
+        center: K,
+        mut distance: G,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        G: FnMut(&K, &K) -> D,
+        D: Ord + Eq,
+    {
+        self.rank_by(move |item| distance(&f(item), &center))
+    }
+
+    fn normalized_rank_by<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let ranking: Ranking<Self::Item> = self.rank_by(f).collect();
+        let max_rank = ranking.distinct_ranks();
+        ranking.into_iter().map(move |(rank, item)| {
+            let normalized = if max_rank > 1 {
+                (rank.0 - 1) as f64 / (max_rank - 1) as f64
+            } else {
+                0.0
+            };
+            (normalized, item)
+        })
+    }
+
+    fn normalized_rank_by_rev<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        self.normalized_rank_by(f)
+            .map(|(normalized, item)| (1.0 - normalized, item))
+    }
+
+    fn percent_rank_by<F, K>(self, mut f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (v, starts) = sort_into_groups(self, &mut f);
+        let n = v.len();
+        let mut group = 0;
+        v.into_iter().enumerate().map(move |(i, item)| {
+            if starts.get(group + 1) == Some(&i) {
+                group += 1;
+            }
+            let percent = if n > 1 {
+                starts[group] as f64 / (n - 1) as f64
+            } else {
+                0.0
+            };
+            (percent, item)
+        })
+    }
+
+    fn rank_by_relative<F, K>(
+        self,
+        mut f: F,
+        reference: K,
+        equal: RelativeSide,
+    ) -> impl Iterator<Item = (Rank, bool, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (above, below): (Vec<_>, Vec<_>) =
+            self.partition(|item| match f(item).cmp(&reference) {
+                Ordering::Greater => true,
+                Ordering::Equal => equal == RelativeSide::Above,
+                Ordering::Less => false,
+            });
+        let below: Ranking<Self::Item> = below.into_iter().rank_by(&mut f).collect();
+        let above: Ranking<Self::Item> = above.into_iter().rank_by(&mut f).collect();
+        let below_ranks = below.distinct_ranks();
+        below
+            .into_iter()
+            .map(move |(rank, item)| (Rank(below_ranks + 1 - rank.0), false, item))
+            .chain(above.into_iter().map(|(rank, item)| (rank, true, item)))
+    }
+
+    fn rank_bidirectional_by<F, K>(self, f: F) -> impl Iterator<Item = (Rank, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let ranking: Ranking<Self::Item> = self.rank_by(f).collect();
+        let distinct = ranking.distinct_ranks();
+        ranking
+            .into_iter()
+            .map(move |(rank, item)| (rank, Rank(distinct + 1 - rank.0), item))
+    }
+
+    fn rank_by_with_tie_break<F, K, C>(
+        self,
+        mut f: F,
+        tie_break: C,
+    ) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        C: Fn(&Self::Item, &Self::Item) -> Ordering,
+    {
+        let mut v = Vec::from_iter(self);
+        v.sort_by(|a, b| f(a).cmp(&f(b)).then_with(|| tie_break(a, b)));
+        RankedBy::new(v.into_iter(), f)
+    }
+
+    fn rank_within_by<GF, G, F, K>(
+        self,
+        mut group_fn: GF,
+        mut f: F,
+    ) -> impl Iterator<Item = (G, Rank, Self::Item)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord + Eq,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut v: Vec<(G, K, Rank, Self::Item)> = self
+            .map(|item| (group_fn(&item), f(&item), Rank(0), item))
+            .collect();
+        v.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        let mut rank = Rank(0);
+        for i in 0..v.len() {
+            rank = match i.checked_sub(1).map(|j| &v[j]) {
+                Some(prev) if prev.0 == v[i].0 => {
+                    if prev.1 == v[i].1 {
+                        rank
+                    } else {
+                        Rank(rank.0 + 1)
+                    }
+                }
+                _ => Rank(1),
+            };
+            v[i].2 = rank;
+        }
+        v.into_iter()
+            .map(|(group, _, rank, item)| (group, rank, item))
+    }
+
+    fn rank_by_normalize_count<F, K>(self, f: F) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut v = Vec::from_iter(self);
+        v.sort_by_key(f);
+        let last = v.len().saturating_sub(1).max(1);
+        v.into_iter()
+            .enumerate()
+            .map(move |(i, item)| (i as f64 / last as f64, item))
+    }
+
+    fn rank_hierarchical_by<GF, G, GKF, GK, F, K>(
+        self,
+        mut group_fn: GF,
+        mut group_key_fn: GKF,
+        mut f: F,
+    ) -> impl Iterator<Item = (Rank, G, Rank, Self::Item)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord + Clone,
+        GKF: FnMut(&[Self::Item]) -> GK,
+        GK: Ord + Eq,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut groups: BTreeMap<G, Vec<Self::Item>> = BTreeMap::new();
+        for item in self {
+            groups.entry(group_fn(&item)).or_default().push(item);
+        }
+        let keyed = groups
+            .into_iter()
+            .map(|(group, members)| {
+                let group_key = group_key_fn(&members);
+                let ranked: Vec<_> = members.into_iter().rank_by(&mut f).collect();
+                (group_key, (group, ranked))
+            })
+            .collect();
+        rank_keyed(keyed).flat_map(|(group_rank, (group, ranked))| {
+            ranked
+                .into_iter()
+                .map(move |(rank, item)| (group_rank, group.clone(), rank, item))
+        })
+    }
+
+    fn rank_by_clamp<F, K>(
+        self,
+        min: usize,
+        max: usize,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        assert!(
+            min <= max,
+            "rank_by_clamp: min ({min}) is greater than max ({max})"
+        );
+        self.rank_by(f)
+            .map(move |(rank, item)| (Rank(rank.0.clamp(min, max)), item))
+    }
+
+    fn top_k_by<F, K>(self, k: usize, mut f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut heap = BinaryHeap::with_capacity(k.min(1024));
+        for (seq, item) in self.enumerate() {
+            push_bounded(&mut heap, k, f(&item), seq, item);
+        }
+        rank_heap(heap)
+    }
+
+    fn top_k_within_by<GF, G, F, K>(
+        self,
+        k: usize,
+        mut group_fn: GF,
+        mut f: F,
+    ) -> impl Iterator<Item = (G, Rank, Self::Item)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord + Clone,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut groups: BTreeMap<G, BinaryHeap<HeapEntry<K, Self::Item>>> = BTreeMap::new();
+        for (seq, item) in self.enumerate() {
+            let heap = groups.entry(group_fn(&item)).or_default();
+            push_bounded(heap, k, f(&item), seq, item);
+        }
+        groups.into_iter().flat_map(|(group, heap)| {
+            rank_heap(heap).map(move |(rank, item)| (group.clone(), rank, item))
+        })
+    }
+
+    fn rank_by_offset<F, K>(self, start: usize, f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        self.rank_by(f)
+            .map(move |(rank, item)| (Rank(rank.0 - 1 + start), item))
+    }
+
+    fn rank_groups_by<GF, G, A, FF, F, K>(
+        self,
+        mut group_fn: GF,
+        init: A,
+        mut fold: FF,
+        mut f: F,
+    ) -> impl Iterator<Item = (Rank, G, A)>
+    where
+        Self: Sized,
+        GF: FnMut(&Self::Item) -> G,
+        G: Ord,
+        A: Clone,
+        FF: FnMut(&mut A, Self::Item),
+        F: FnMut(&A) -> K,
+        K: Ord + Eq,
+    {
+        let mut groups: BTreeMap<G, A> = BTreeMap::new();
+        for item in self {
+            let aggregate = groups
+                .entry(group_fn(&item))
+                .or_insert_with(|| init.clone());
+            fold(aggregate, item);
+        }
+        let keyed = groups
+            .into_iter()
+            .map(|(group, aggregate)| (f(&aggregate), (group, aggregate)))
+            .collect();
+        rank_keyed(keyed).map(|(rank, (group, aggregate))| (rank, group, aggregate))
+    }
+
+    fn rank_by_map_rank<F, K, R>(
+        self,
+        f: F,
+        mut rank_map: R,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        R: FnMut(Rank) -> Rank,
+    {
+        self.rank_by(f)
+            .map(move |(rank, item)| (rank_map(rank), item))
+    }
+
+    fn rank_by_cross<J, F, K>(
+        self,
+        other: J,
+        mut f: F,
+    ) -> impl Iterator<Item = (Rank, Self::Item, J::Item)>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        J: IntoIterator,
+        J::Item: Clone,
+        F: FnMut(&Self::Item, &J::Item) -> K,
+        K: Ord + Eq,
+    {
+        let other = Vec::from_iter(other);
+        let mut keyed = Vec::new();
+        for a in self {
+            for b in &other {
+                keyed.push((f(&a, b), (a.clone(), b.clone())));
+            }
+        }
+        rank_keyed(keyed).map(|(rank, (a, b))| (rank, a, b))
+    }
+
+    fn rank_expanding_by<F, K>(self, mut f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut seen = KeyCounts::new();
+        self.map(move |item| {
+            let key = f(&item);
+            let rank = Rank(seen.count_below(&key) + 1);
+            seen.insert(key);
+            (rank, item)
+        })
+    }
+
+    fn rank_per_bucket_by<BF, B, F, K>(
+        self,
+        mut bucket_fn: BF,
+        mut f: F,
+    ) -> impl Iterator<Item = (B, Rank, Self::Item)>
+    where
+        Self: Sized,
+        BF: FnMut(&Self::Item) -> B,
+        B: Eq + Clone,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut iter = self.peekable();
+        let mut run = Vec::new();
+        std::iter::from_fn(move || {
+            let first = iter.next()?;
+            let bucket = bucket_fn(&first);
+            run.push(first);
+            while let Some(item) = iter.next_if(|item| bucket_fn(item) == bucket) {
+                run.push(item);
+            }
+            let ranked: Vec<_> = run.drain(..).rank_by(&mut f).collect();
+            Some(
+                ranked
+                    .into_iter()
+                    .map(move |(rank, item)| (bucket.clone(), rank, item)),
+            )
+        })
+        .flatten()
+    }
+
+    fn rank_by_inspect_transitions<F, K, C>(
+        self,
+        f: F,
+        mut on_transition: C,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        C: FnMut(Rank, Rank),
+    {
+        let mut prev_rank = None;
+        self.rank_by(f).inspect(move |&(rank, _)| {
+            if let Some(prev) = prev_rank
+                && prev != rank
+            {
+                on_transition(prev, rank);
+            }
+            prev_rank = Some(rank);
+        })
+    }
+
+    fn rank_by_collect<F, K>(self, f: F) -> Vec<(Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let ranked = self.rank_by(f);
+        let mut out = Vec::with_capacity(ranked.size_hint().0);
+        out.extend(ranked);
+        out
+    }
+
+    fn rank_by_transform<F, K, M, U>(
+        self,
+        f: F,
+        mut transform: M,
+    ) -> impl Iterator<Item = (Rank, U)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        M: FnMut(Self::Item) -> U,
+    {
+        self.rank_by(f)
+            .map(move |(rank, item)| (rank, transform(item)))
+    }
+
+    fn rank_presorted_by<F, K>(self, f: F) -> RankedBy<Self, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        RankedBy::new(self, f)
+    }
+
+    fn rank_by_with_checkpoint<F, K>(
+        self,
+        checkpoint: RankCheckpoint<K>,
+        f: F,
+    ) -> RankedBy<Self, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        RankedBy {
+            iter: self,
+            f,
+            rank: Rank(checkpoint.rank),
+            prev_key: checkpoint.prev_key,
+            consumed: checkpoint.consumed,
+        }
+    }
+
+    fn rank_by_distinct_count<F, K>(self, mut f: F) -> usize
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut keys: Vec<K> = self.map(|item| f(&item)).collect();
+        keys.sort();
+        keys.dedup();
+        keys.len()
+    }
+
+    fn rank_by_interpose<F, K, S, Sep>(
+        self,
+        f: F,
+        mut separator_fn: S,
+    ) -> impl Iterator<Item = RankItem<Self::Item, Sep>>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        S: FnMut(Rank) -> Sep,
+    {
+        let mut prev_rank = None;
+        self.rank_by(f).flat_map(move |(rank, item)| {
+            let separator = match prev_rank.replace(rank) {
+                Some(prev) if prev != rank => Some(RankItem::Separator(separator_fn(rank))),
+                _ => None,
+            };
+            separator
+                .into_iter()
+                .chain(Some(RankItem::Item(rank, item)))
+        })
+    }
+
+    fn rank_by_enumerate_within_group<F, K>(
+        self,
+        f: F,
+    ) -> impl Iterator<Item = (Rank, usize, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut prev_rank = Rank(0);
+        let mut position = 0;
+        self.rank_by(f).map(move |(rank, item)| {
+            if rank == prev_rank {
+                position += 1;
+            } else {
+                prev_rank = rank;
+                position = 0;
+            }
+            (rank, position, item)
+        })
+    }
+
+    fn rank_by_with_total<F, K>(self, mut f: F) -> impl Iterator<Item = (Rank, usize, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (v, starts) = sort_into_groups(self, &mut f);
+        let total = starts.len();
+        RankedBy::new(v.into_iter(), f).map(move |(rank, item)| (rank, total, item))
+    }
+
+    fn rank_by_cross_validate<F, K>(self, mut f: F) -> Vec<(Rank, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (v, starts) = sort_into_groups(self, &mut f);
+        let mut group = 0;
+        v.into_iter()
+            .enumerate()
+            .map(|(i, item)| {
+                if starts.get(group) == Some(&i) {
+                    group += 1;
+                }
+                (Rank(group), Rank(starts[group - 1] + 1), item)
+            })
+            .collect()
+    }
+
+    fn rank_by_stable_hash_tie_break<F, K, H>(
+        self,
+        mut f: F,
+        mut hash_fn: H,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        H: FnMut(&Self::Item) -> u64,
+    {
+        // Sorting by hash first leaves ties in hash order after the stable sort by key.
+        let mut hashed: Vec<(u64, Self::Item)> = self.map(|item| (hash_fn(&item), item)).collect();
+        hashed.sort_by_key(|&(hash, _)| hash);
+        let keyed = hashed
+            .into_iter()
+            .map(|(_, item)| (f(&item), item))
+            .collect();
+        rank_keyed(keyed)
+    }
+
+    fn rank_by_cumulative_sum<F, K>(self, f: F) -> impl Iterator<Item = (usize, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        self.rank_by(f)
+            .enumerate()
+            .map(|(i, (rank, item))| (i + 1, rank, item))
+    }
+
+    fn rank_by_unstable<F, K>(self, mut f: F) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut v = Vec::from_iter(self);
+        v.sort_unstable_by_key(&mut f);
+        RankedBy::new(v.into_iter(), f)
+    }
+
+    fn rank_by_prefilter<P, F, K>(
+        self,
+        pred: P,
+        f: F,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        P: FnMut(&Self::Item) -> bool,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (included, excluded): (Vec<_>, Vec<_>) = self.partition(pred);
+        included
+            .into_iter()
+            .rank_by(f)
+            .map(|(rank, item)| (Some(rank), item))
+            .chain(excluded.into_iter().map(|item| (None, item)))
+    }
+
+    fn rank_by_indirect<F, K>(self, mut f: F) -> RankedBy<impl Iterator<Item = Self::Item>, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut slots: Vec<Option<Self::Item>> = self.map(Some).collect();
+        let mut order: Vec<(K, usize)> = slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| (f(slot.as_ref().expect("every slot is filled")), i))
+            .collect();
+        // The indices are distinct, so an unstable sort still keeps ties in input order.
+        order.sort_unstable();
+        RankedBy::new(
+            order.into_iter().map(move |(_, i)| {
+                slots[i]
+                    .take()
+                    .expect("every index occurs once in the order")
+            }),
+            f,
+        )
+    }
+
+    fn rank_by_group_stats<F, K>(self, f: F) -> Vec<GroupStats<Self::Item>>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        self.rank_by_to_groups(f)
+            .into_iter()
+            .map(|(rank, all_items)| GroupStats {
+                rank,
+                count: all_items.len(),
+                first: all_items[0].clone(),
+                last: all_items[all_items.len() - 1].clone(),
+                all_items,
+            })
+            .collect()
+    }
+
+    fn rank_eligible_by<E, F, K>(
+        self,
+        mut eligible: E,
+        f: F,
+        placement: IneligiblePlacement,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        E: FnMut(&Self::Item) -> bool,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut included = Vec::new();
+        let mut excluded = Vec::new();
+        for (i, item) in self.enumerate() {
+            if eligible(&item) {
+                included.push(item);
+            } else {
+                excluded.push((i, item));
+            }
+        }
+        if placement == IneligiblePlacement::End {
+            // Past the last position, so they all come after the ranked items.
+            excluded.iter_mut().for_each(|(i, _)| *i = usize::MAX);
+        }
+        let mut ranked = included.into_iter().rank_by(f);
+        let mut excluded = excluded.into_iter().peekable();
+        let mut pos = 0;
+        std::iter::from_fn(move || {
+            let next = match excluded.next_if(|&(i, _)| i == pos) {
+                Some((_, item)) => Some((None, item)),
+                None => match ranked.next() {
+                    Some((rank, item)) => Some((Some(rank), item)),
+                    None => excluded.next().map(|(_, item)| (None, item)),
+                },
+            };
+            pos += 1;
+            next
+        })
+    }
+
+    fn rank_by_strategy<F, K>(
+        self,
+        strategy: RankingStrategy,
+        mut f: F,
+    ) -> impl Iterator<Item = (f64, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let (v, starts) = sort_into_groups(self, &mut f);
+        let n = v.len();
+        let mut group = 0;
+        v.into_iter().enumerate().map(move |(i, item)| {
+            if starts.get(group + 1) == Some(&i) {
+                group += 1;
+            }
+            let start = starts[group];
+            let len = starts.get(group + 1).copied().unwrap_or(n) - start;
+            let rank = strategy.rank_of(Rank(group + 1), start, len, i - start);
+            (rank, item)
+        })
+    }
+
+    fn annotate_ranks<F, K>(
+        self,
+        table: &RankTable<K>,
+        mut f: F,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord,
+    {
+        self.map(move |item| (table.rank(&f(&item)), item))
+    }
+
+    fn annotate_ranks_floor<F, K>(
+        self,
+        table: &RankTable<K>,
+        mut f: F,
+    ) -> impl Iterator<Item = (Option<Rank>, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord,
+    {
+        self.map(move |item| (table.floor_rank(&f(&item)), item))
+    }
+
+    fn rank_by_with_eq_ord<F, K, C, E>(
+        self,
+        mut key_fn: F,
+        cmp: C,
+        eq: E,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        C: Fn(&K, &K) -> Ordering,
+        E: Fn(&K, &K) -> bool,
+    {
+        let mut keyed: Vec<(K, Self::Item)> = self.map(|item| (key_fn(&item), item)).collect();
+        keyed.sort_by(|a, b| cmp(&a.0, &b.0));
+        let mut rank = Rank(0);
+        let mut prev_key = None;
+        keyed.into_iter().map(move |(key, item)| {
+            if !prev_key.as_ref().is_some_and(|prev| eq(prev, &key)) {
+                rank = Rank(rank.0 + 1);
+            }
+            prev_key = Some(key);
+            (rank, item)
+        })
+    }
+
+    fn rank_by_scatter<F, K, G>(
+        self,
+        f: F,
+        mut target_fn: G,
+    ) -> Result<HashMap<usize, (Rank, Self::Item)>, ScatterConflict>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        G: FnMut(Rank, usize) -> usize,
+    {
+        let mut placed = HashMap::new();
+        let mut prev_rank = Rank(0);
+        let mut index = 0;
+        for (rank, item) in self.rank_by(f) {
+            if rank == prev_rank {
+                index += 1;
+            } else {
+                prev_rank = rank;
+                index = 0;
+            }
+            let position = target_fn(rank, index);
+            match placed.entry(position) {
+                Entry::Occupied(entry) => {
+                    let &(placed_rank, _) = entry.get();
+                    return Err(ScatterConflict {
+                        position,
+                        placed: placed_rank,
+                        rejected: rank,
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((rank, item));
+                }
+            }
+        }
+        Ok(placed)
+    }
+
+    fn rank_by_into_btreeset<F, K>(self, f: F) -> BTreeMap<Rank, BTreeSet<Self::Item>>
+    where
+        Self: Sized,
+        Self::Item: Ord,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        let mut groups: BTreeMap<Rank, BTreeSet<Self::Item>> = BTreeMap::new();
+        for (rank, item) in self.rank_by(f) {
+            groups.entry(rank).or_default().insert(item);
+        }
+        groups
+    }
+
+    fn rank_by_annotate<F, K, G, A>(self, mut f: F, mut annotate_fn: G) -> impl Iterator<Item = A>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        G: FnMut(Rank, usize, usize, Self::Item) -> A,
+    {
+        let (v, starts) = sort_into_groups(self, &mut f);
+        let n = v.len();
+        let mut group = 0;
+        v.into_iter().enumerate().map(move |(i, item)| {
+            if starts.get(group + 1) == Some(&i) {
+                group += 1;
+            }
+            let start = starts[group];
+            let size = starts.get(group + 1).copied().unwrap_or(n) - start;
+            annotate_fn(Rank(group + 1), i - start, size, item)
+        })
+    }
+
+    fn rank_by_rev_groups<F, K>(self, f: F) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        flatten_groups(self.rank_by_to_groups_desc(f))
+    }
+
+    fn rank_by_label_array<F, K, L, const N: usize>(
+        self,
+        f: F,
+        labels: [L; N],
+        overflow: L,
+    ) -> impl Iterator<Item = (L, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        L: Copy,
+    {
+        const { assert!(N > 0, "rank_by_label_array needs at least one label") };
+        self.rank_by(f).map(move |(rank, item)| {
+            let label = labels.get(rank.0 - 1).copied().unwrap_or(overflow);
+            (label, item)
+        })
+    }
+
+    fn rank_by_bipartite<F, K, G>(
+        self,
+        mut f: F,
+        mut group_fn: G,
+    ) -> impl Iterator<Item = (bool, Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+        G: FnMut(&Self::Item) -> bool,
+    {
+        let (left, right): (Vec<_>, Vec<_>) = self
+            .enumerate()
+            .map(|(i, item)| (group_fn(&item), i, item))
+            .partition(|&(side, _, _)| side);
+        let mut slots: Vec<Option<(bool, Rank, Self::Item)>> = Vec::new();
+        slots.resize_with(left.len() + right.len(), || None);
+        for part in [left, right] {
+            for (rank, (side, i, item)) in part.into_iter().rank_by(|(_, _, item)| f(item)) {
+                slots[i] = Some((side, rank, item));
+            }
+        }
+        slots.into_iter().flatten()
+    }
+
+    fn rank_by_temporal_decay<F, G>(
+        self,
+        mut key_fn: F,
+        mut time_fn: G,
+        decay_rate: f64,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> f64,
+        G: FnMut(&Self::Item) -> f64,
+    {
+        let timed: Vec<(f64, f64, Self::Item)> = self
+            .map(|item| (key_fn(&item), time_fn(&item), item))
+            .collect();
+        let max_time = timed
+            .iter()
+            .map(|&(_, time, _)| time)
+            .fold(f64::NEG_INFINITY, f64::max);
+        timed
+            .into_iter()
+            .map(move |(key, time, item)| (key * (-decay_rate * (max_time - time)).exp(), item))
+            .rank_by_with_eq_ord(|&(key, _)| key, f64::total_cmp, |a, b| a == b)
+            .map(|(rank, (_, item))| (rank, item))
+    }
+
+    fn rank_by_coalesce<F, K, D>(
+        self,
+        mut key_fn: F,
+        epsilon: K,
+        key_diff: D,
+    ) -> impl Iterator<Item = (Rank, Self::Item)>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: PartialOrd,
+        D: Fn(&K, &K) -> K,
+    {
+        let mut keyed: Vec<(K, Self::Item)> = self.map(|item| (key_fn(&item), item)).collect();
+        keyed.sort_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .expect("rank_by_coalesce: keys must be comparable")
+        });
+        let mut rank = Rank(0);
+        let mut representative = None;
+        keyed.into_iter().map(move |(key, item)| {
+            if !representative
+                .as_ref()
+                .is_some_and(|rep| key_diff(&key, rep) <= epsilon)
+            {
+                rank = Rank(rank.0 + 1);
+                representative = Some(key);
+            }
+            (rank, item)
+        })
+    }
+}
+
+/// Sorts the items by key and returns them with the start index of every rank group.
+fn sort_into_groups<T, F, K>(iter: impl Iterator<Item = T>, f: &mut F) -> (Vec<T>, Vec<usize>)
+where
+    F: FnMut(&T) -> K,
+    K: Ord + Eq,
+{
+    let mut v = Vec::from_iter(iter);
+    v.sort_by_key(&mut *f);
+    let mut starts = Vec::new();
+    let mut prev_key = None;
+    for (i, item) in v.iter().enumerate() {
+        let key = f(item);
+        if prev_key.as_ref() != Some(&key) {
+            starts.push(i);
+            prev_key = Some(key);
+        }
+    }
+    (v, starts)
+}
+
+/// Sorts items paired with precomputed keys and ranks them by those keys.
+pub(crate) fn rank_keyed<T, K>(mut keyed: Vec<(K, T)>) -> impl Iterator<Item = (Rank, T)>
+where
+    K: Ord + Eq,
+{
+    keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut rank = Rank(0);
+    let mut prev_key = None;
+    keyed.into_iter().map(move |(key, item)| {
+        if prev_key.as_ref() != Some(&key) {
+            rank = Rank(rank.0 + 1);
+            prev_key = Some(key);
+        }
+        (rank, item)
+    })
+}
+
+/// Hashes bytes with 64-bit FNV-1a.
+///
+/// Unlike [`DefaultHasher`](std::collections::hash_map::DefaultHasher), the algorithm is
+/// fixed and will not change, so the hash of the same bytes is the same on every
+/// platform and in every release. It is meant for reproducible orderings, as in
+/// [`RankedBy::order_ties_by_hash`], not for hash tables exposed to untrusted input.
+///
+/// # Examples
+///
+/// ```
+/// use librank::rank::stable_hash;
+///
+/// assert_eq!(stable_hash(b"a"), 0xaf63dc4c8601ec8c);
+/// ```
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Scores ranks linearly from 1.0 for rank 1 down to 0.0 for the last of `total` ranks.
+///
+/// With a single rank the score is 1.0.
+pub fn linear_score(rank: Rank, total: usize) -> f64 {
+    if total <= 1 {
+        return 1.0;
+    }
+    (total - rank.0) as f64 / (total - 1) as f64
+}
+
+/// Scores ranks as `1 / log2(rank + 1)`, the discount used by DCG: 1.0 for rank 1,
+/// about 0.63 for rank 2, 0.5 for rank 3 and so on. `total` is ignored.
+pub fn log_score(rank: Rank, _total: usize) -> f64 {
+    1.0 / ((rank.0 + 1) as f64).log2()
+}
+
+/// The side of the reference that items equal to it are placed on by
+/// [`RankedExt::rank_by_relative`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelativeSide {
+    /// Items equal to the reference are ranked with the items above it.
+    Above,
+    /// Items equal to the reference are ranked with the items below it.
+    Below,
+}
+
+/// How tied items are numbered, for the APIs that let the caller choose.
+///
+/// The examples show the ranks given to the keys `[10, 20, 20, 30]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RankingStrategy {
+    /// `1 2 2 3`: tied items share a rank and no rank is skipped, as in
+    /// [`RankedExt::rank_by`].
+    #[default]
+    Dense,
+    /// `1 2 2 4`: tied items share the position of the first item of their group.
+    Competition,
+    /// `1 3 3 4`: tied items share the position of the last item of their group.
+    Modified,
+    /// `1 2 3 4`: every item gets its own position, with ties in input order.
+    Ordinal,
+    /// `1 2.5 2.5 4`: tied items share the mean of their positions.
+    Fractional,
+}
+
+impl RankingStrategy {
+    /// Returns the rank of the `offset`-th item of a tie group, given the group's dense
+    /// rank, the 0-based position where the group starts and its length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankingStrategy};
+    ///
+    /// // The second of the two 20s in `[10, 20, 20, 30]`.
+    /// let rank = |strategy: RankingStrategy| strategy.rank_of(Rank(2), 1, 2, 1);
+    ///
+    /// assert_eq!(rank(RankingStrategy::Dense), 2.0);
+    /// assert_eq!(rank(RankingStrategy::Competition), 2.0);
+    /// assert_eq!(rank(RankingStrategy::Modified), 3.0);
+    /// assert_eq!(rank(RankingStrategy::Ordinal), 3.0);
+    /// assert_eq!(rank(RankingStrategy::Fractional), 2.5);
+    /// ```
+    pub fn rank_of(self, dense: Rank, start: usize, len: usize, offset: usize) -> f64 {
+        match self {
+            RankingStrategy::Dense => dense.0 as f64,
+            RankingStrategy::Competition => (start + 1) as f64,
+            RankingStrategy::Modified => (start + len) as f64,
+            RankingStrategy::Ordinal => (start + offset + 1) as f64,
+            RankingStrategy::Fractional => start as f64 + (len + 1) as f64 / 2.0,
+        }
+    }
+}
+
+/// Walks the tie groups of sorted items, yielding the rank `strategy` gives each item
+/// with its index, where `tied` tells whether two neighbouring items are tied.
+#[cfg(any(feature = "arrow", feature = "csv"))]
+pub(crate) fn strategy_ranks<T>(
+    sorted: &[T],
+    strategy: RankingStrategy,
+    mut tied: impl FnMut(&T, &T) -> bool,
+) -> impl Iterator<Item = (f64, usize)> {
+    let mut start = 0;
+    let mut dense = Rank(0);
+    std::iter::from_fn(move || {
+        if start == sorted.len() {
+            return None;
+        }
+        let len = sorted[start..]
+            .iter()
+            .take_while(|item| tied(item, &sorted[start]))
+            .count();
+        dense = Rank(dense.0 + 1);
+        let group =
+            (start..start + len).map(move |i| (strategy.rank_of(dense, start, len, i - start), i));
+        start += len;
+        Some(group)
+    })
+    .flatten()
+}
+
+/// An item in a bounded top-k heap, ordered by key and then by input position.
+struct HeapEntry<K, T> {
+    key: K,
+    seq: usize,
+    item: T,
+}
+
+impl<K: Ord, T> PartialEq for HeapEntry<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, T> Eq for HeapEntry<K, T> {}
+
+impl<K: Ord, T> PartialOrd for HeapEntry<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for HeapEntry<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .cmp(&other.key)
+            .then_with(|| self.seq.cmp(&other.seq))
+    }
+}
+
+/// Pushes an item into a max-heap of at most `k` entries, evicting the worst one.
+///
+/// Among items with equal keys the latest is the worst, so earlier items survive ties
+/// at the cutoff.
+fn push_bounded<K: Ord, T>(
+    heap: &mut BinaryHeap<HeapEntry<K, T>>,
+    k: usize,
+    key: K,
+    seq: usize,
+    item: T,
+) {
+    if k == 0 {
+        return;
+    }
+    if heap.len() == k {
+        match heap.peek() {
+            Some(worst) if (&key, seq) < (&worst.key, worst.seq) => {
+                heap.pop();
+            }
+            _ => return,
+        }
+    }
+    heap.push(HeapEntry { key, seq, item });
+}
+
+/// Drains a top-k heap in order, with dense ranks.
+fn rank_heap<K: Ord, T>(heap: BinaryHeap<HeapEntry<K, T>>) -> impl Iterator<Item = (Rank, T)> {
+    let mut rank = Rank(0);
+    let mut prev_key = None;
+    heap.into_sorted_vec().into_iter().map(move |entry| {
+        if prev_key.as_ref() != Some(&entry.key) {
+            rank = Rank(rank.0 + 1);
+            prev_key = Some(entry.key);
+        }
+        (rank, entry.item)
+    })
+}
+
+/// An entry of [`RankedExt::rank_by_interpose`]: either a ranked item or a separator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RankItem<T, S> {
+    /// A ranked item.
+    Item(Rank, T),
+    /// A separator placed before the first item of a rank group.
+    Separator(S),
+}
+
+/// A summary of a rank group, returned by [`RankedExt::rank_by_group_stats`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupStats<T> {
+    /// The rank of the group.
+    pub rank: Rank,
+    /// The number of items in the group.
+    pub count: usize,
+    /// The first item of the group in sort order.
+    pub first: T,
+    /// The last item of the group in sort order.
+    pub last: T,
+    /// All the items of the group in sort order.
+    pub all_items: Vec<T>,
+}
+
+/// Merges iterators that are each sorted by a key into one ranked iterator.
+///
+/// The iterators are merged lazily with a min-heap holding one item from each, so
+/// merging `n` items from `k` iterators takes O(n log k) and never buffers more than
+/// `k` items. The output is the same as chaining the iterators and ranking the result
+/// with [`rank_by`](RankedExt::rank_by): tied items from earlier iterators come first.
+/// That each iterator is sorted is not checked.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::rank::rank_by_merge_n;
+///
+/// let shards = vec![vec![1, 4, 4], vec![2, 4], vec![]];
+/// let ranked: Vec<(Rank, i32)> = rank_by_merge_n(shards, |&x| x).collect();
+///
+/// assert_eq!(ranked, vec![(Rank(1), 1), (Rank(2), 2), (Rank(3), 4), (Rank(3), 4), (Rank(3), 4)]);
+/// ```
+pub fn rank_by_merge_n<I, F, K>(
+    iters: impl IntoIterator<Item = I>,
+    mut key_fn: F,
+) -> impl Iterator<Item = (Rank, I::Item)>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+{
+    let mut iters: Vec<I::IntoIter> = iters.into_iter().map(IntoIterator::into_iter).collect();
+    // Entries are ordered by key and then by iterator, so ties come out in chain order.
+    let mut heap = BinaryHeap::with_capacity(iters.len());
+    for (seq, iter) in iters.iter_mut().enumerate() {
+        if let Some(item) = iter.next() {
+            let key = key_fn(&item);
+            heap.push(Reverse(HeapEntry { key, seq, item }));
+        }
+    }
+    let mut rank = Rank(0);
+    let mut prev_key = None;
+    std::iter::from_fn(move || {
+        let Reverse(entry) = heap.pop()?;
+        if let Some(item) = iters[entry.seq].next() {
+            let key = key_fn(&item);
+            heap.push(Reverse(HeapEntry {
+                key,
+                seq: entry.seq,
+                item,
+            }));
+        }
+        if prev_key.as_ref() != Some(&entry.key) {
+            rank = Rank(rank.0 + 1);
+            prev_key = Some(entry.key);
+        }
+        Some((rank, entry.item))
+    })
+}
+
+/// Where [`RankedExt::rank_eligible_by`] puts the items that aren't ranked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IneligiblePlacement {
+    /// After all ranked items, in input order.
+    #[default]
+    End,
+    /// At the same position in the output as in the input, with the ranked items
+    /// filling the other positions in rank order.
+    InPlace,
+}
+
+/// Returns a target function for [`RankedExt::rank_by_scatter`] that places the items
+/// at positions 0, 1, 2 and so on, in rank order.
+pub fn sequential_positions() -> impl FnMut(Rank, usize) -> usize {
+    let mut next = 0;
+    move |_, _| {
+        next += 1;
+        next - 1
+    }
+}
+
+/// The error returned by [`RankedExt::rank_by_scatter`] when two items are given the
+/// same position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScatterConflict {
+    /// The position both items were given.
+    pub position: usize,
+    /// The rank of the item already at the position.
+    pub placed: Rank,
+    /// The rank of the item that was given the position next.
+    pub rejected: Rank,
+}
+
+impl fmt::Display for ScatterConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "position {} is given to items of ranks {} and {}",
+            self.position, self.placed.0, self.rejected.0
+        )
+    }
+}
+
+impl Error for ScatterConflict {}
+
+/// Flattens rank groups back into `(Rank, T)` pairs, pairing every item with the rank
+/// of its group.
+///
+/// This is the inverse of [`RankedExt::rank_by_to_groups`]: flattening its groups gives
+/// the output of [`rank_by`](RankedExt::rank_by). The groups are yielded in the order
+/// given, so flattening [`rank_by_to_groups_desc`](RankedExt::rank_by_to_groups_desc)
+/// lists the highest rank first.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::rank::flatten_groups;
+///
+/// let groups = vec![(Rank(1), vec!['a']), (Rank(2), vec!['b', 'c'])];
+/// let flat: Vec<(Rank, char)> = flatten_groups(groups).collect();
+///
+/// assert_eq!(flat, vec![(Rank(1), 'a'), (Rank(2), 'b'), (Rank(2), 'c')]);
+/// ```
+pub fn flatten_groups<G>(
+    groups: impl IntoIterator<Item = (Rank, G)>,
+) -> impl Iterator<Item = (Rank, G::Item)>
+where
+    G: IntoIterator,
+{
+    groups
+        .into_iter()
+        .flat_map(|(rank, group)| group.into_iter().map(move |item| (rank, item)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lcg;
+
+    #[test]
+    fn test_rank_by_unique_keys() {
+        let data = vec![3, 7, 4, 1, 5, 9, 2, 6];
+        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        let expected = vec![
+            (Rank(1), 1),
+            (Rank(2), 2),
+            (Rank(3), 3),
+            (Rank(4), 4),
+            (Rank(5), 5),
+            (Rank(6), 6),
+            (Rank(7), 7),
+            (Rank(8), 9),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_duplicate_keys() {
+        let data = vec![10, 20, 10, 30, 20, 10];
+        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        let expected = vec![
+            (Rank(1), 10),
+            (Rank(1), 10),
+            (Rank(1), 10),
+            (Rank(2), 20),
+            (Rank(2), 20),
+            (Rank(3), 30),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_empty_iterator() {
+        let ranked: Vec<(Rank, i32)> = std::iter::empty().rank_by(|&x| x).collect();
+        assert!(ranked.is_empty());
+    }
+
+    #[test]
     fn test_rank_by_pre_sorted_data() {
         let data = vec![1, 2, 3, 4, 5];
         let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
         let expected = vec![
-            (Rank(1), 1),
-            (Rank(2), 2),
-            (Rank(3), 3),
-            (Rank(4), 4),
-            (Rank(5), 5),
+            (Rank(1), 1),
+            (Rank(2), 2),
+            (Rank(3), 3),
+            (Rank(4), 4),
+            (Rank(5), 5),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_reverse_sorted_data() {
+        let data = vec![5, 4, 3, 2, 1];
+        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        let expected = vec![
+            (Rank(1), 1),
+            (Rank(2), 2),
+            (Rank(3), 3),
+            (Rank(4), 4),
+            (Rank(5), 5),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_complex_key() {
+        #[derive(Debug, PartialEq)]
+        struct Item {
+            id: usize,
+            value: i32,
+        }
+
+        let data = vec![
+            Item { id: 1, value: 30 },
+            Item { id: 2, value: 10 },
+            Item { id: 3, value: 20 },
+            Item { id: 4, value: 10 },
+            Item { id: 5, value: 30 },
+        ];
+
+        let ranked: Vec<(Rank, Item)> = data.into_iter().rank_by(|item| item.value).collect();
+        let expected = vec![
+            (Rank(1), Item { id: 2, value: 10 }),
+            (Rank(1), Item { id: 4, value: 10 }),
+            (Rank(2), Item { id: 3, value: 20 }),
+            (Rank(3), Item { id: 1, value: 30 }),
+            (Rank(3), Item { id: 5, value: 30 }),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_desc() {
+        let data = vec![(2, 'a'), (3, 'b'), (2, 'c'), (1, 'd')];
+        let ranked: Vec<(Rank, (i32, char))> = data.into_iter().rank_by_desc(|&(k, _)| k).collect();
+        let expected = vec![
+            (Rank(1), (3, 'b')),
+            (Rank(2), (2, 'a')),
+            (Rank(2), (2, 'c')),
+            (Rank(3), (1, 'd')),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_nth_group_matches_filtered_rank_by() {
+        let data = vec![4, 1, 3, 1, 4, 4, 2, 5, 3];
+        for n in 0..=6 {
+            let group = data.clone().into_iter().rank_by_nth_group(n, |&x| x);
+            let expected: Vec<i32> = data
+                .clone()
+                .into_iter()
+                .rank_by(|&x| x)
+                .filter(|(r, _)| r.0 == n)
+                .map(|(_, t)| t)
+                .collect();
+            assert_eq!(group, expected, "group {n}");
+        }
+    }
+
+    #[test]
+    fn test_rank_by_nth_group_keeps_stable_order() {
+        let data = vec![(1, 'a'), (0, 'b'), (1, 'c'), (1, 'd')];
+        let group = data.into_iter().rank_by_nth_group(2, |&(k, _)| k);
+        assert_eq!(group, vec![(1, 'a'), (1, 'c'), (1, 'd')]);
+    }
+
+    #[test]
+    fn test_rank_by_nth_group_empty_iterator() {
+        let group: Vec<i32> = std::iter::empty().rank_by_nth_group(1, |&x| x);
+        assert!(group.is_empty());
+    }
+
+    #[test]
+    fn test_rank_by_ecdf_extremes() {
+        let data = vec![5, 1, 4, 2, 3];
+        let ecdf: Vec<(f64, i32)> = data.into_iter().rank_by_ecdf(|&x| x).collect();
+        let expected = vec![(0.0, 1), (0.25, 2), (0.5, 3), (0.75, 4), (1.0, 5)];
+        assert_eq!(ecdf, expected);
+    }
+
+    #[test]
+    fn test_rank_by_ecdf_ties_share_midpoint() {
+        let data = vec![1, 2, 2, 2, 3];
+        let ecdf: Vec<(f64, i32)> = data.into_iter().rank_by_ecdf(|&x| x).collect();
+        let expected = vec![(0.0, 1), (0.5, 2), (0.5, 2), (0.5, 2), (1.0, 3)];
+        assert_eq!(ecdf, expected);
+    }
+
+    #[test]
+    fn test_rank_by_ecdf_single_and_empty() {
+        let single: Vec<(f64, i32)> = vec![7].into_iter().rank_by_ecdf(|&x| x).collect();
+        assert_eq!(single, vec![(0.5, 7)]);
+        let empty: Vec<(f64, i32)> = std::iter::empty().rank_by_ecdf(|&x| x).collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_rank_by_with_gap_all_tied() {
+        let data = vec![5, 5, 5, 7];
+        let ranked: Vec<(Rank, usize, i32)> = data.into_iter().rank_by_with_gap(|&x| x).collect();
+        let expected = vec![
+            (Rank(1), 0, 5),
+            (Rank(1), 0, 5),
+            (Rank(1), 0, 5),
+            (Rank(2), 3, 7),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_with_gap_unique_keys() {
+        let data = vec![3, 1, 2, 4];
+        let gaps: Vec<usize> = data
+            .into_iter()
+            .rank_by_with_gap(|&x| x)
+            .map(|(_, gap, _)| gap)
+            .collect();
+        assert_eq!(gaps, vec![0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_rank_by_with_gap_reconstructs_competition_ranks() {
+        let data = vec![1, 2, 2, 3, 4, 4, 4, 5];
+        let mut competition = 1;
+        let mut prev_rank = Rank(0);
+        let mut ranks = Vec::new();
+        for (rank, gap, _) in data.into_iter().rank_by_with_gap(|&x| x) {
+            if rank != prev_rank {
+                competition += gap;
+                prev_rank = rank;
+            }
+            ranks.push(competition);
+        }
+        assert_eq!(ranks, vec![1, 2, 2, 4, 5, 5, 5, 8]);
+    }
+
+    #[test]
+    fn test_rank_by_limit_per_group_small_groups_unaffected() {
+        let data = vec![3, 1, 2, 1, 3];
+        let limited: Vec<(Rank, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_limit_per_group(2, |&x| x)
+            .collect();
+        let full: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        assert_eq!(limited, full);
+    }
+
+    #[test]
+    fn test_rank_by_limit_per_group_truncates_large_groups() {
+        let data = vec![(1, 'a'), (2, 'b'), (1, 'c'), (1, 'd'), (1, 'e'), (3, 'f')];
+        let limited: Vec<(Rank, (i32, char))> = data
+            .into_iter()
+            .rank_by_limit_per_group(2, |&(k, _)| k)
+            .collect();
+        let expected = vec![
+            (Rank(1), (1, 'a')),
+            (Rank(1), (1, 'c')),
+            (Rank(2), (2, 'b')),
+            (Rank(3), (3, 'f')),
+        ];
+        assert_eq!(limited, expected);
+    }
+
+    #[test]
+    fn test_rank_by_limit_per_group_one_keeps_first_of_each_group() {
+        let data = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        let limited: Vec<(Rank, (i32, char))> = data
+            .into_iter()
+            .rank_by_limit_per_group(1, |&(k, _)| k)
+            .collect();
+        assert_eq!(limited, vec![(Rank(1), (1, 'b')), (Rank(2), (2, 'a'))]);
+    }
+
+    #[test]
+    fn test_rank_by_with_progress_called_once_per_item() {
+        let data = vec![5, 3, 5, 1, 2];
+        let mut calls = Vec::new();
+        let ranked: Vec<(Rank, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_with_progress(|&x| x, |emitted, total| calls.push((emitted, total)))
+            .collect();
+        assert_eq!(ranked, data.into_iter().rank_by(|&x| x).collect::<Vec<_>>());
+        assert_eq!(calls.len(), 5);
+        assert_eq!(calls.last(), Some(&(5, 5)));
+        assert!(calls.iter().all(|&(_, total)| total == 5));
+    }
+
+    #[test]
+    fn test_rank_by_with_progress_drives_progress_bar() {
+        let mut bar = String::new();
+        let ranked = (0..4).rank_by_with_progress(
+            |&x| x,
+            |emitted, total| {
+                bar = format!("[{}{}]", "#".repeat(emitted), ".".repeat(total - emitted));
+            },
+        );
+        assert_eq!(ranked.count(), 4);
+        assert_eq!(bar, "[####]");
+    }
+
+    #[test]
+    fn test_rank_by_with_progress_empty_iterator() {
+        let mut called = false;
+        let ranked = std::iter::empty::<i32>().rank_by_with_progress(|&x| x, |_, _| called = true);
+        assert_eq!(ranked.count(), 0);
+        assert!(!called);
+    }
+
+    #[test]
+    fn test_rank_by_to_groups_shape() {
+        let data = vec![4, 1, 3, 1, 4, 4, 2, 5, 3];
+        let groups = data.clone().into_iter().rank_by_to_groups(|&x| x);
+        assert_eq!(groups.len(), 5);
+        assert_eq!(
+            groups.iter().map(|(_, g)| g.len()).sum::<usize>(),
+            data.len()
+        );
+        assert!(groups.iter().all(|(_, g)| !g.is_empty()));
+        let ranks: Vec<Rank> = groups.iter().map(|&(r, _)| r).collect();
+        assert_eq!(ranks, vec![Rank(1), Rank(2), Rank(3), Rank(4), Rank(5)]);
+    }
+
+    #[test]
+    fn test_rank_by_to_groups_keeps_stable_order() {
+        let data = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        let groups = data.into_iter().rank_by_to_groups(|&(k, _)| k);
+        let expected = vec![
+            (Rank(1), vec![(1, 'b'), (1, 'd')]),
+            (Rank(2), vec![(2, 'a'), (2, 'c')]),
+        ];
+        assert_eq!(groups, expected);
+    }
+
+    #[test]
+    fn test_rank_by_to_groups_desc() {
+        let data = vec![10, 20, 10, 30];
+        let groups = data.into_iter().rank_by_to_groups_desc(|&x| x);
+        let expected = vec![
+            (Rank(3), vec![30]),
+            (Rank(2), vec![20]),
+            (Rank(1), vec![10, 10]),
+        ];
+        assert_eq!(groups, expected);
+        assert!(
+            std::iter::empty::<i32>()
+                .rank_by_to_groups(|&x| x)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_rank_by_with_fallback_all_none() {
+        let data = vec![1, 2, 3];
+        let ranked: Vec<(Rank, i32)> = data
+            .into_iter()
+            .rank_by_with_fallback(|_| None::<i32>, Rank(9))
+            .collect();
+        assert_eq!(ranked, vec![(Rank(9), 1), (Rank(9), 2), (Rank(9), 3)]);
+    }
+
+    #[test]
+    fn test_rank_by_with_fallback_mixed() {
+        let data = vec!["b", "", "a", "c", "", "a"];
+        let ranked: Vec<(Rank, &str)> = data
+            .into_iter()
+            .rank_by_with_fallback(|s| s.chars().next(), Rank(4))
+            .collect();
+        let expected = vec![
+            (Rank(1), "a"),
+            (Rank(1), "a"),
+            (Rank(2), "b"),
+            (Rank(3), "c"),
+            (Rank(4), ""),
+            (Rank(4), ""),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_with_fallback_rank_zero() {
+        let data = vec![Some(2), None, Some(1)];
+        let ranked: Vec<(Rank, Option<i32>)> = data
+            .into_iter()
+            .rank_by_with_fallback(|&x| x, Rank(0))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![(Rank(1), Some(1)), (Rank(2), Some(2)), (Rank(0), None)]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_with_fallback_calls_f_once_per_item() {
+        let calls = std::cell::Cell::new(0);
+        let ranked: Vec<(Rank, i32)> = [3, -1, 1, 3]
+            .into_iter()
+            .rank_by_with_fallback(
+                |&x| {
+                    calls.set(calls.get() + 1);
+                    (x > 0).then_some(x)
+                },
+                Rank(0),
+            )
+            .collect();
+        assert_eq!(calls.get(), 4);
+        assert_eq!(
+            ranked,
+            vec![(Rank(1), 1), (Rank(2), 3), (Rank(2), 3), (Rank(0), -1)]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_result_key_ok() {
+        let data = vec![3, 1, 2, 1];
+        let ranked: Vec<(Rank, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_result_key(|&x| Ok::<_, ()>(x))
+            .unwrap()
+            .collect();
+        assert_eq!(ranked, data.into_iter().rank_by(|&x| x).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_rank_by_result_key_fails_on_third_item() {
+        let calls = std::cell::Cell::new(0);
+        let result = (1..=5).rank_by_result_key(|&x| {
+            calls.set(calls.get() + 1);
+            if x == 3 {
+                Err(format!("bad item {x}"))
+            } else {
+                Ok(x)
+            }
+        });
+        match result {
+            Ok(ranked) => panic!("expected an error, got {:?}", ranked.collect::<Vec<_>>()),
+            Err(e) => assert_eq!(e, "bad item 3"),
+        }
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_rank_by_result_key_filter_ok() {
+        let data = vec!["3", "x", "1", "3", "y"];
+        let ranked: Vec<(Rank, &str)> = data
+            .into_iter()
+            .rank_by_result_key_filter_ok(|s| s.parse::<u32>())
+            .collect();
+        assert_eq!(ranked, vec![(Rank(1), "1"), (Rank(2), "3"), (Rank(2), "3")]);
+    }
+
+    #[test]
+    fn test_rank_by_display_order_leaderboard() {
+        let data = vec![("Bob", 100), ("Charlie", 90), ("Alice", 100)];
+        let ranked: Vec<(Rank, (&str, i32))> = data
+            .into_iter()
+            .rank_by_display_order(|&(_, score)| -score, |&(name, _)| name)
+            .collect();
+        let expected = vec![
+            (Rank(1), ("Alice", 100)),
+            (Rank(1), ("Bob", 100)),
+            (Rank(2), ("Charlie", 90)),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_display_order_does_not_split_ranks() {
+        let data = vec![(1, 'c'), (1, 'a'), (2, 'b'), (1, 'b')];
+        let ranked: Vec<(Rank, (i32, char))> = data
+            .into_iter()
+            .rank_by_display_order(|&(k, _)| k, |&(_, c)| c)
+            .collect();
+        let expected = vec![
+            (Rank(1), (1, 'a')),
+            (Rank(1), (1, 'b')),
+            (Rank(1), (1, 'c')),
+            (Rank(2), (2, 'b')),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_as_scores_linear() {
+        let data = vec![5, 1, 3, 1, 4];
+        let scores: Vec<(f64, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_as_scores(|&x| x, linear_score)
+            .collect();
+        assert_eq!(scores.len(), data.len());
+        assert_eq!(
+            scores,
+            vec![(1.0, 1), (1.0, 1), (2.0 / 3.0, 3), (1.0 / 3.0, 4), (0.0, 5)]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_as_scores_log() {
+        let scores: Vec<f64> = vec![1, 2, 3]
+            .into_iter()
+            .rank_by_as_scores(|&x| x, log_score)
+            .map(|(s, _)| s)
+            .collect();
+        assert_eq!(scores[0], 1.0);
+        assert!((scores[1] - 1.0 / 3f64.log2()).abs() < 1e-12);
+        assert_eq!(scores[2], 0.5);
+    }
+
+    #[test]
+    fn test_rank_by_as_scores_custom_and_single_rank() {
+        let scores: Vec<(f64, i32)> = vec![7, 7]
+            .into_iter()
+            .rank_by_as_scores(|&x| x, linear_score)
+            .collect();
+        assert_eq!(scores, vec![(1.0, 7), (1.0, 7)]);
+        let custom: Vec<(f64, char)> = vec!['b', 'a']
+            .into_iter()
+            .rank_by_as_scores(|&c| c, |rank, total| (rank.0 * 10 + total) as f64)
+            .collect();
+        assert_eq!(custom, vec![(12.0, 'a'), (22.0, 'b')]);
+    }
+
+    #[test]
+    fn test_track_ties_no_ties() {
+        let mut ranked = vec![3, 1, 2].into_iter().rank_by(|&x| x).track_ties();
+        assert_eq!(ranked.by_ref().count(), 3);
+        assert!(!ranked.has_ties());
+        assert!(ranked.tie_groups().is_empty());
+    }
+
+    #[test]
+    fn test_track_ties_one_big_tie() {
+        let mut ranked = vec![7; 5].into_iter().rank_by(|&x| x).track_ties();
+        assert_eq!(ranked.by_ref().count(), 5);
+        assert_eq!(ranked.tie_groups(), &[(Rank(1), 5)]);
+    }
+
+    #[test]
+    fn test_track_ties_scattered_ties() {
+        let data = vec![1, 2, 2, 3, 4, 4, 4, 5, 6, 6];
+        let mut ranked = data.into_iter().rank_by(|&x| x).track_ties();
+        assert_eq!(ranked.by_ref().take(2).count(), 2);
+        assert!(!ranked.has_ties());
+        ranked.by_ref().for_each(drop);
+        assert_eq!(
+            ranked.tie_groups(),
+            &[(Rank(2), 2), (Rank(4), 3), (Rank(6), 2)]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_nth_percentile_known_dataset() {
+        let data: Vec<i32> = (1..=9).rev().collect();
+        let percentile = |p| data.clone().into_iter().rank_by_nth_percentile(p, |&x| x);
+        assert_eq!(percentile(0.0), Some(1));
+        assert_eq!(percentile(25.0), Some(3));
+        assert_eq!(percentile(50.0), Some(5));
+        assert_eq!(percentile(75.0), Some(7));
+        assert_eq!(percentile(100.0), Some(9));
+    }
+
+    #[test]
+    fn test_rank_by_nth_percentile_ties_return_first_of_group() {
+        let data = vec![(1, 'a'), (2, 'b'), (2, 'c'), (2, 'd'), (3, 'e')];
+        let item = data.into_iter().rank_by_nth_percentile(75.0, |&(k, _)| k);
+        assert_eq!(item, Some((2, 'b')));
+    }
+
+    #[test]
+    fn test_rank_by_nth_percentile_out_of_range_or_empty() {
+        assert_eq!(
+            vec![1].into_iter().rank_by_nth_percentile(-1.0, |&x| x),
+            None
+        );
+        assert_eq!(
+            vec![1].into_iter().rank_by_nth_percentile(100.5, |&x| x),
+            None
+        );
+        assert_eq!(
+            vec![1].into_iter().rank_by_nth_percentile(f64::NAN, |&x| x),
+            None
+        );
+        assert_eq!(
+            std::iter::empty::<i32>().rank_by_nth_percentile(50.0, |&x| x),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rank_by_symmetric_equidistant_ties() {
+        let data = vec![-3, 5, -1, 1, 3, 0];
+        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by_symmetric(|&x| x, 1).collect();
+        let expected = vec![
+            (Rank(1), 1),
+            (Rank(2), 0),
+            (Rank(3), -1),
+            (Rank(3), 3),
+            (Rank(4), -3),
+            (Rank(4), 5),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_symmetric_unsigned_keys() {
+        let data = vec![0u8, 255, 128, 100];
+        let ranked: Vec<(Rank, u8)> = data.into_iter().rank_by_symmetric(|&x| x, 128).collect();
+        let expected = vec![(Rank(1), 128), (Rank(2), 100), (Rank(3), 255), (Rank(4), 0)];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_symmetric_with_custom_distance() {
+        // Hours on a 24-hour clock, by distance from midnight.
+        let data: Vec<i32> = vec![23, 2, 12, 1, 22];
+        let ranked: Vec<(Rank, i32)> = data
+            .into_iter()
+            .rank_by_symmetric_with(
+                |&h| h,
+                0,
+                |&a: &i32, &b: &i32| {
+                    let d = (a - b).rem_euclid(24);
+                    d.min(24 - d)
+                },
+            )
+            .collect();
+        let expected = vec![
+            (Rank(1), 23),
+            (Rank(1), 1),
+            (Rank(2), 2),
+            (Rank(2), 22),
+            (Rank(3), 12),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_normalized_rank_by() {
+        let data = vec![30, 10, 20, 40, 20];
+        let normalized: Vec<(f64, i32)> = data.into_iter().normalized_rank_by(|&x| x).collect();
+        let third = 1.0 / 3.0;
+        let expected = vec![
+            (0.0, 10),
+            (third, 20),
+            (third, 20),
+            (2.0 * third, 30),
+            (1.0, 40),
+        ];
+        assert_eq!(normalized, expected);
+    }
+
+    #[test]
+    fn test_normalized_rank_by_single_rank_and_rev() {
+        let normalized: Vec<(f64, i32)> =
+            vec![5, 5].into_iter().normalized_rank_by(|&x| x).collect();
+        assert_eq!(normalized, vec![(0.0, 5), (0.0, 5)]);
+        let rev: Vec<(f64, i32)> = vec![2, 1, 3]
+            .into_iter()
+            .normalized_rank_by_rev(|&x| x)
+            .collect();
+        assert_eq!(rev, vec![(1.0, 1), (0.5, 2), (0.0, 3)]);
+    }
+
+    #[test]
+    fn test_normalized_rank_by_differs_from_percent_rank_by() {
+        let data = vec![2, 1, 1, 1, 3];
+        let normalized: Vec<f64> = data
+            .clone()
+            .into_iter()
+            .normalized_rank_by(|&x| x)
+            .map(|(r, _)| r)
+            .collect();
+        let percent: Vec<f64> = data
+            .into_iter()
+            .percent_rank_by(|&x| x)
+            .map(|(r, _)| r)
+            .collect();
+        assert_eq!(normalized, vec![0.0, 0.0, 0.0, 0.5, 1.0]);
+        assert_eq!(percent, vec![0.0, 0.0, 0.0, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn test_percent_rank_by_single_item() {
+        let percent: Vec<(f64, i32)> = vec![4].into_iter().percent_rank_by(|&x| x).collect();
+        assert_eq!(percent, vec![(0.0, 4)]);
+    }
+
+    #[test]
+    fn test_rank_by_relative_equal_items_above() {
+        let data = vec![10, 4, 6, 5, 5, 2];
+        let ranked: Vec<(Rank, bool, i32)> = data
+            .into_iter()
+            .rank_by_relative(|&x| x, 5, RelativeSide::Above)
+            .collect();
+        let expected = vec![
+            (Rank(2), false, 2),
+            (Rank(1), false, 4),
+            (Rank(1), true, 5),
+            (Rank(1), true, 5),
+            (Rank(2), true, 6),
+            (Rank(3), true, 10),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_relative_equal_items_below() {
+        let data = vec![(5, 'a'), (3, 'b'), (5, 'c'), (8, 'd'), (3, 'e')];
+        let ranked: Vec<(Rank, bool, (i32, char))> = data
+            .into_iter()
+            .rank_by_relative(|&(k, _)| k, 5, RelativeSide::Below)
+            .collect();
+        let expected = vec![
+            (Rank(2), false, (3, 'b')),
+            (Rank(2), false, (3, 'e')),
+            (Rank(1), false, (5, 'a')),
+            (Rank(1), false, (5, 'c')),
+            (Rank(1), true, (8, 'd')),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_bidirectional_by_ranks_sum_to_distinct_plus_one() {
+        let mut states = lcg(3);
+        for len in 0..40 {
+            let data: Vec<u64> = states
+                .by_ref()
+                .take(len)
+                .map(|state| (state >> 33) % 10)
+                .collect();
+            let mut sorted = data.clone();
+            sorted.sort();
+            sorted.dedup();
+            let distinct = sorted.len();
+            let ranked: Vec<(Rank, Rank, u64)> = data
+                .clone()
+                .into_iter()
+                .rank_bidirectional_by(|&x| x)
+                .collect();
+            let forward: Vec<(Rank, u64)> = data.into_iter().rank_by(|&x| x).collect();
+            assert_eq!(ranked.len(), forward.len());
+            for ((fwd, bwd, item), (expected_rank, expected_item)) in
+                ranked.into_iter().zip(forward)
+            {
+                assert_eq!((fwd, item), (expected_rank, expected_item));
+                assert_eq!(fwd.0 + bwd.0, distinct + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_bidirectional_by_desc_check() {
+        let data = vec![2, 1, 2, 3];
+        let backward: Vec<(Rank, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_bidirectional_by(|&x| x)
+            .map(|(_, bwd, item)| (bwd, item))
+            .collect();
+        let mut desc: Vec<(Rank, i32)> = data.into_iter().rank_by_desc(|&x| x).collect();
+        desc.reverse();
+        assert_eq!(backward, desc);
+    }
+
+    #[test]
+    fn test_rank_by_with_tie_break_independent_of_input_order() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Entry {
+            id: u32,
+            score: u32,
+        }
+
+        let entries = vec![
+            Entry { id: 4, score: 10 },
+            Entry { id: 1, score: 20 },
+            Entry { id: 3, score: 10 },
+            Entry { id: 2, score: 10 },
+            Entry { id: 5, score: 20 },
+        ];
+        let rank = |entries: Vec<Entry>| -> Vec<(Rank, u32)> {
+            entries
+                .into_iter()
+                .rank_by_with_tie_break(|e| e.score, |a, b| a.id.cmp(&b.id))
+                .map(|(r, e)| (r, e.id))
+                .collect()
+        };
+        let expected = vec![
+            (Rank(1), 2),
+            (Rank(1), 3),
+            (Rank(1), 4),
+            (Rank(2), 1),
+            (Rank(2), 5),
+        ];
+        let mut shuffled = entries.clone();
+        assert_eq!(rank(entries), expected);
+        shuffled.reverse();
+        assert_eq!(rank(shuffled.clone()), expected);
+        shuffled.rotate_left(2);
+        assert_eq!(rank(shuffled), expected);
+    }
+
+    #[test]
+    fn test_rank_by_with_tie_break_equal_tie_break_keeps_input_order() {
+        let data = vec![(1, 'b', 0), (1, 'a', 1), (1, 'a', 2)];
+        let ranked: Vec<(Rank, (i32, char, i32))> = data
+            .into_iter()
+            .rank_by_with_tie_break(|&(k, _, _)| k, |a, b| a.1.cmp(&b.1))
+            .collect();
+        let expected = vec![
+            (Rank(1), (1, 'a', 1)),
+            (Rank(1), (1, 'a', 2)),
+            (Rank(1), (1, 'b', 0)),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_within_by_interleaved_groups_and_ties() {
+        let data = vec![
+            ('b', 3),
+            ('a', 5),
+            ('b', 1),
+            ('a', 5),
+            ('c', 7),
+            ('a', 2),
+            ('b', 3),
+        ];
+        let ranked: Vec<(char, Rank, (char, i32))> = data
+            .into_iter()
+            .rank_within_by(|&(g, _)| g, |&(_, k)| k)
+            .collect();
+        let expected = vec![
+            ('a', Rank(1), ('a', 2)),
+            ('a', Rank(2), ('a', 5)),
+            ('a', Rank(2), ('a', 5)),
+            ('b', Rank(1), ('b', 1)),
+            ('b', Rank(2), ('b', 3)),
+            ('b', Rank(2), ('b', 3)),
+            ('c', Rank(1), ('c', 7)),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_within_by_restarts_when_keys_repeat_across_groups() {
+        let data = vec![(1, 10), (2, 10), (1, 20), (2, 20)];
+        let ranks: Vec<(i32, Rank)> = data
+            .into_iter()
+            .rank_within_by(|&(g, _)| g, |&(_, k)| k)
+            .map(|(g, r, _)| (g, r))
+            .collect();
+        assert_eq!(
+            ranks,
+            vec![(1, Rank(1)), (1, Rank(2)), (2, Rank(1)), (2, Rank(2))]
+        );
+    }
+
+    #[test]
+    fn test_rank_within_by_calls_group_fn_once_per_item() {
+        let calls = std::cell::Cell::new(0);
+        let ranked: Vec<(i32, Rank, i32)> = [5, 2, 8, 3, 6]
+            .into_iter()
+            .rank_within_by(
+                |&x| {
+                    calls.set(calls.get() + 1);
+                    x % 2
+                },
+                |&x| x,
+            )
+            .collect();
+        assert_eq!(calls.get(), 5);
+        assert_eq!(
+            ranked,
+            vec![
+                (0, Rank(1), 2),
+                (0, Rank(2), 6),
+                (0, Rank(3), 8),
+                (1, Rank(1), 3),
+                (1, Rank(2), 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_normalize_count_endpoints_and_linearity() {
+        let data = vec![7, 3, 9, 3, 1, 5, 8, 2, 6];
+        let scaled: Vec<(f64, i32)> = data.into_iter().rank_by_normalize_count(|&x| x).collect();
+        assert_eq!(scaled.first(), Some(&(0.0, 1)));
+        assert_eq!(scaled.last(), Some(&(1.0, 9)));
+        for (i, &(value, _)) in scaled.iter().enumerate() {
+            assert_eq!(value, i as f64 / 8.0);
+        }
+        assert_eq!(scaled[2], (0.25, 3));
+        assert_eq!(scaled[3], (0.375, 3));
+    }
+
+    #[test]
+    fn test_rank_by_normalize_count_single_and_empty() {
+        let single: Vec<(f64, u8)> = vec![4]
+            .into_iter()
+            .rank_by_normalize_count(|&x| x)
+            .collect();
+        assert_eq!(single, vec![(0.0, 4)]);
+        let empty = Vec::<u8>::new().into_iter().rank_by_normalize_count(|&x| x);
+        assert_eq!(empty.count(), 0);
+    }
+
+    #[test]
+    fn test_rank_hierarchical_by_tied_groups_rank_members_independently() {
+        let data = vec![
+            ("wolves", 3),
+            ("bears", 6),
+            ("wolves", 6),
+            ("bears", 1),
+            ("wolves", 1),
+            ("bears", 3),
+            ("owls", 20),
+        ];
+        let ranked: Vec<(Rank, &str, Rank, i32)> = data
+            .into_iter()
+            .rank_hierarchical_by(
+                |&(team, _)| team,
+                |members| Reverse(members.iter().map(|&(_, score)| score).sum::<i32>()),
+                |&(_, score)| Reverse(score),
+            )
+            .map(|(team_rank, team, rank, (_, score))| (team_rank, team, rank, score))
+            .collect();
+        let expected = vec![
+            (Rank(1), "owls", Rank(1), 20),
+            (Rank(2), "bears", Rank(1), 6),
+            (Rank(2), "bears", Rank(2), 3),
+            (Rank(2), "bears", Rank(3), 1),
+            (Rank(2), "wolves", Rank(1), 6),
+            (Rank(2), "wolves", Rank(2), 3),
+            (Rank(2), "wolves", Rank(3), 1),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_hierarchical_by_member_ties() {
+        let data = vec![('a', 2), ('b', 1), ('a', 2), ('a', 1)];
+        let ranked: Vec<(Rank, char, Rank)> = data
+            .into_iter()
+            .rank_hierarchical_by(|&(g, _)| g, |members| members.len(), |&(_, k)| k)
+            .map(|(group_rank, g, rank, _)| (group_rank, g, rank))
+            .collect();
+        let expected = vec![
+            (Rank(1), 'b', Rank(1)),
+            (Rank(2), 'a', Rank(1)),
+            (Rank(2), 'a', Rank(2)),
+            (Rank(2), 'a', Rank(2)),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_clamp() {
+        let data = vec![5, 1, 9, 1, 3, 10, 7, 2, 8, 4, 6];
+        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by_clamp(2, 5, |&x| x).collect();
+        assert!(
+            ranked
+                .iter()
+                .all(|&(r, _)| (Rank(2)..=Rank(5)).contains(&r))
+        );
+        assert_eq!(&ranked[..3], &[(Rank(2), 1), (Rank(2), 1), (Rank(2), 2)]);
+        assert_eq!(ranked[3], (Rank(3), 3));
+        assert_eq!(ranked[5], (Rank(5), 5));
+        assert_eq!(ranked.last(), Some(&(Rank(5), 10)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rank_by_clamp_min_greater_than_max() {
+        let _ = vec![1, 2].into_iter().rank_by_clamp(3, 2, |&x| x);
+    }
+
+    #[test]
+    fn test_top_k_by_matches_rank_by() {
+        let data = vec![7, 3, 9, 3, 1, 5, 8, 1, 6];
+        for k in 0..=data.len() + 1 {
+            let expected: Vec<(Rank, i32)> =
+                data.clone().into_iter().rank_by(|&x| x).take(k).collect();
+            let top: Vec<(Rank, i32)> = data.clone().into_iter().top_k_by(k, |&x| x).collect();
+            assert_eq!(top, expected, "k = {k}");
+        }
+    }
+
+    #[test]
+    fn test_top_k_by_tie_at_cutoff_keeps_earliest() {
+        let data = vec![(2, 'a'), (1, 'b'), (2, 'c'), (2, 'd')];
+        let top: Vec<(Rank, char)> = data
+            .into_iter()
+            .top_k_by(3, |&(k, _)| k)
+            .map(|(r, (_, c))| (r, c))
+            .collect();
+        assert_eq!(top, vec![(Rank(1), 'b'), (Rank(2), 'a'), (Rank(2), 'c')]);
+    }
+
+    #[test]
+    fn test_top_k_within_by_small_group_and_cutoff_tie() {
+        let data = vec![
+            ('x', 4, 'a'),
+            ('y', 9, 'b'),
+            ('x', 2, 'c'),
+            ('x', 4, 'd'),
+            ('x', 4, 'e'),
+            ('x', 7, 'f'),
+        ];
+        let top: Vec<(char, Rank, char)> = data
+            .into_iter()
+            .top_k_within_by(3, |&(g, _, _)| g, |&(_, k, _)| k)
+            .map(|(g, r, (_, _, id))| (g, r, id))
+            .collect();
+        let expected = vec![
+            ('x', Rank(1), 'c'),
+            ('x', Rank(2), 'a'),
+            ('x', Rank(2), 'd'),
+            ('y', Rank(1), 'b'),
+        ];
+        assert_eq!(top, expected);
+    }
+
+    #[test]
+    fn test_top_k_within_by_agrees_with_top_k_by_per_group() {
+        let data: Vec<(u64, u64)> = lcg(7)
+            .take(300)
+            .map(|state| ((state >> 33) % 60, (state >> 45) % 5))
+            .collect();
+        let top: Vec<(u64, Rank, (u64, u64))> = data
+            .iter()
+            .copied()
+            .top_k_within_by(2, |&(g, _)| g, |&(_, k)| k)
+            .collect();
+        let mut expected = Vec::new();
+        for g in 0..60 {
+            let members = data.iter().copied().filter(|&(group, _)| group == g);
+            expected.extend(
+                members
+                    .top_k_by(2, |&(_, k)| k)
+                    .map(|(r, item)| (g, r, item)),
+            );
+        }
+        assert_eq!(top, expected);
+    }
+
+    #[test]
+    fn test_rank_by_offset() {
+        let data = vec![3, 1, 2, 1, 3];
+        let ranks: Vec<(Rank, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_offset(10, |&x| x)
+            .collect();
+        let expected = vec![
+            (Rank(10), 1),
+            (Rank(10), 1),
+            (Rank(11), 2),
+            (Rank(12), 3),
+            (Rank(12), 3),
+        ];
+        assert_eq!(ranks, expected);
+
+        let from_one: Vec<(Rank, i32)> =
+            data.clone().into_iter().rank_by_offset(1, |&x| x).collect();
+        let plain: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        assert_eq!(from_one, plain);
+    }
+
+    #[test]
+    fn test_rank_groups_by_folds_each_item_once() {
+        let folds = std::cell::Cell::new(0);
+        let data = vec![('a', 2), ('b', 5), ('a', 3), ('c', 1), ('b', 0), ('c', 4)];
+        let ranked: Vec<(Rank, char, (u32, u32))> = data
+            .into_iter()
+            .rank_groups_by(
+                |&(g, _)| g,
+                (0, 0),
+                |(count, total), (_, v)| {
+                    folds.set(folds.get() + 1);
+                    *count += 1;
+                    *total += v;
+                },
+                |&(_, total)| Reverse(total),
+            )
+            .collect();
+        assert_eq!(folds.get(), 6);
+        let expected = vec![
+            (Rank(1), 'a', (2, 5)),
+            (Rank(1), 'b', (2, 5)),
+            (Rank(1), 'c', (2, 5)),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_groups_by_max_and_empty() {
+        let data = vec![(1, 7), (2, 9), (1, 3), (3, 8)];
+        let ranked: Vec<(Rank, i32, i32)> = data
+            .into_iter()
+            .rank_groups_by(
+                |&(g, _)| g,
+                i32::MIN,
+                |max, (_, v)| *max = (*max).max(v),
+                |&max| max,
+            )
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![(Rank(1), 1, 7), (Rank(2), 3, 8), (Rank(3), 2, 9)]
+        );
+
+        let empty = Vec::<(i32, i32)>::new().into_iter().rank_groups_by(
+            |&(g, _)| g,
+            0,
+            |sum, (_, v)| *sum += v,
+            |&sum| sum,
+        );
+        assert_eq!(empty.count(), 0);
+    }
+
+    #[test]
+    fn test_rank_by_map_rank() {
+        let data = vec![5, 2, 5, 9];
+        let ranks = |rank_map: fn(Rank) -> Rank| -> Vec<Rank> {
+            data.clone()
+                .into_iter()
+                .rank_by_map_rank(|&x| x, rank_map)
+                .map(|(r, _)| r)
+                .collect()
+        };
+        assert_eq!(
+            ranks(|r| Rank(r.0 * 2)),
+            vec![Rank(2), Rank(4), Rank(4), Rank(6)]
+        );
+        assert_eq!(
+            ranks(|r| Rank(r.0 - 1)),
+            vec![Rank(0), Rank(1), Rank(1), Rank(2)]
+        );
+        let plain: Vec<Rank> = data
+            .clone()
+            .into_iter()
+            .rank_by(|&x| x)
+            .map(|(r, _)| r)
+            .collect();
+        assert_eq!(ranks(|r| r), plain);
+    }
+
+    #[test]
+    fn test_rank_by_cross() {
+        let left = vec![1, 2, 3];
+        let right = vec!['x', 'y'];
+        let ranked: Vec<(Rank, i32, char)> = left
+            .clone()
+            .into_iter()
+            .rank_by_cross(right.clone(), |&a, &b| a % 2 + (b == 'y') as i32)
+            .collect();
+        assert_eq!(ranked.len(), 6);
+        let expected = vec![
+            (Rank(1), 2, 'x'),
+            (Rank(2), 1, 'x'),
+            (Rank(2), 2, 'y'),
+            (Rank(2), 3, 'x'),
+            (Rank(3), 1, 'y'),
+            (Rank(3), 3, 'y'),
+        ];
+        assert_eq!(ranked, expected);
+
+        let no_right = left
+            .into_iter()
+            .rank_by_cross(Vec::<char>::new(), |&a, _| a);
+        assert_eq!(no_right.count(), 0);
+        let no_left = Vec::<i32>::new()
+            .into_iter()
+            .rank_by_cross(right, |&a, _| a);
+        assert_eq!(no_left.count(), 0);
+    }
+
+    #[test]
+    fn test_rank_expanding_by_new_maximum_keeps_history() {
+        let data = vec![50, 20, 50, 90, 10, 60];
+        let ranked: Vec<(Rank, i32)> = data
+            .into_iter()
+            .rank_expanding_by(|&x| Reverse(x))
+            .collect();
+        let expected = vec![
+            (Rank(1), 50),
+            (Rank(2), 20),
+            (Rank(1), 50),
+            (Rank(1), 90),
+            (Rank(4), 10),
+            (Rank(2), 60),
         ];
         assert_eq!(ranked, expected);
     }
 
     #[test]
-    fn test_rank_by_reverse_sorted_data() {
-        let data = vec![5, 4, 3, 2, 1];
-        let ranked: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+    fn test_rank_expanding_by_unbounded_stream() {
+        let ranks: Vec<Rank> = (0u64..)
+            .rank_expanding_by(|&x| Reverse(x))
+            .map(|(r, _)| r)
+            .take(1000)
+            .collect();
+        assert!(ranks.iter().all(|&r| r == Rank(1)));
+    }
+
+    #[test]
+    fn test_rank_per_bucket_by_runs_and_reappearing_bucket() {
+        let data = vec![
+            ('a', 2),
+            ('a', 1),
+            ('a', 2),
+            ('b', 7),
+            ('c', 3),
+            ('a', 9),
+            ('a', 4),
+        ];
+        let ranked: Vec<(char, Rank, i32)> = data
+            .into_iter()
+            .rank_per_bucket_by(|&(b, _)| b, |&(_, k)| k)
+            .map(|(b, r, (_, k))| (b, r, k))
+            .collect();
         let expected = vec![
-            (Rank(1), 1),
-            (Rank(2), 2),
-            (Rank(3), 3),
-            (Rank(4), 4),
-            (Rank(5), 5),
+            ('a', Rank(1), 1),
+            ('a', Rank(2), 2),
+            ('a', Rank(2), 2),
+            ('b', Rank(1), 7),
+            ('c', Rank(1), 3),
+            ('a', Rank(1), 4),
+            ('a', Rank(2), 9),
         ];
         assert_eq!(ranked, expected);
     }
 
     #[test]
-    fn test_rank_by_complex_key() {
-        #[derive(Debug, PartialEq)]
-        struct Item {
-            id: usize,
-            value: i32,
+    fn test_rank_per_bucket_by_is_lazy() {
+        let pulled = std::cell::Cell::new(0);
+        let mut ranked = (0..)
+            .inspect(|_| pulled.set(pulled.get() + 1))
+            .rank_per_bucket_by(|&x| x / 3, |&x| Reverse(x));
+        assert_eq!(ranked.next(), Some((0, Rank(1), 2)));
+        // The first run ends when the item after it is peeked.
+        assert_eq!(pulled.get(), 4);
+        assert_eq!(ranked.nth(2), Some((1, Rank(1), 5)));
+        assert_eq!(pulled.get(), 7);
+    }
+
+    #[test]
+    fn test_rank_by_inspect_transitions_counts() {
+        let data = vec![5, 3, 5, 1, 3, 3, 8];
+        let calls = std::cell::Cell::new(0);
+        let ranked: Vec<(Rank, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_inspect_transitions(
+                |&x| x,
+                |old, new| {
+                    assert_eq!(new.0, old.0 + 1);
+                    calls.set(calls.get() + 1);
+                },
+            )
+            .collect();
+        let ranking: Ranking<i32> = data.clone().into_iter().rank_by(|&x| x).collect();
+        assert_eq!(calls.get(), ranking.distinct_ranks() - 1);
+        assert_eq!(ranked, data.into_iter().rank_by(|&x| x).collect::<Vec<_>>());
+
+        calls.set(0);
+        let empty = Vec::<i32>::new()
+            .into_iter()
+            .rank_by_inspect_transitions(|&x| x, |_, _| calls.set(calls.get() + 1));
+        assert_eq!(empty.count(), 0);
+        assert_eq!(calls.get(), 0);
+    }
+
+    #[test]
+    fn test_rank_by_collect() {
+        let data = vec![3, 1, 2, 3, 1];
+        let collected = data.clone().into_iter().rank_by_collect(|&x| x);
+        assert_eq!(
+            collected,
+            data.into_iter().rank_by(|&x| x).collect::<Vec<_>>()
+        );
+        assert_eq!(collected.capacity(), collected.len());
+        assert!(
+            Vec::<u8>::new()
+                .into_iter()
+                .rank_by_collect(|&x| x)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_rank_by_transform_keeps_ranks() {
+        #[derive(Clone)]
+        struct User {
+            name: String,
+            score: u32,
         }
+        let users: Vec<User> = [("ann", 5), ("bob", 8), ("cy", 5), ("di", 1)]
+            .into_iter()
+            .map(|(name, score)| User {
+                name: name.to_string(),
+                score,
+            })
+            .collect();
+        let names: Vec<(Rank, String)> = users
+            .clone()
+            .into_iter()
+            .rank_by_transform(|u| Reverse(u.score), |u| u.name)
+            .collect();
+        let expected = vec![
+            (Rank(1), "bob".to_string()),
+            (Rank(2), "ann".to_string()),
+            (Rank(2), "cy".to_string()),
+            (Rank(3), "di".to_string()),
+        ];
+        assert_eq!(names, expected);
 
-        let data = vec![
-            Item { id: 1, value: 30 },
-            Item { id: 2, value: 10 },
-            Item { id: 3, value: 20 },
-            Item { id: 4, value: 10 },
-            Item { id: 5, value: 30 },
+        let plain: Vec<Rank> = users
+            .into_iter()
+            .rank_by(|u| Reverse(u.score))
+            .map(|(r, _)| r)
+            .collect();
+        assert_eq!(names.iter().map(|&(r, _)| r).collect::<Vec<_>>(), plain);
+    }
+
+    #[test]
+    fn test_rank_presorted_by_matches_rank_by_on_sorted_input() {
+        let data = vec![1, 1, 2, 4, 4, 4, 7];
+        let presorted: Vec<(Rank, i32)> =
+            data.clone().into_iter().rank_presorted_by(|&x| x).collect();
+        let sorted: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        assert_eq!(presorted, sorted);
+    }
+
+    #[test]
+    fn test_rank_by_with_checkpoint_resumes_at_every_split() {
+        let data = vec![1, 1, 2, 4, 4, 4, 7, 9, 9];
+        let expected: Vec<(Rank, i32)> =
+            data.clone().into_iter().rank_presorted_by(|&x| x).collect();
+        for k in 0..=data.len() {
+            let mut ranked = data.clone().into_iter().rank_presorted_by(|&x| x);
+            let mut output: Vec<(Rank, i32)> = ranked.by_ref().take(k).collect();
+            let checkpoint = ranked.checkpoint();
+            assert_eq!(checkpoint.consumed(), k);
+
+            let mut resumed = data[k..]
+                .iter()
+                .copied()
+                .rank_by_with_checkpoint(checkpoint, |&x| x);
+            output.extend(resumed.by_ref());
+            assert_eq!(output, expected);
+            assert_eq!(resumed.checkpoint().consumed(), data.len());
+        }
+        assert_eq!(RankCheckpoint::<i32>::default().rank(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_rank_checkpoint_serde_round_trip() {
+        let data: Vec<(String, u32)> = ["a", "b", "c", "d", "e", "f"]
+            .iter()
+            .zip([3, 3, 5, 5, 5, 8])
+            .map(|(name, score)| (name.to_string(), score))
+            .collect();
+        let key = |(_, score): &(String, u32)| *score;
+        let expected: Vec<(Rank, (String, u32))> =
+            data.clone().into_iter().rank_presorted_by(key).collect();
+
+        let mut ranked = data.clone().into_iter().rank_presorted_by(key);
+        let head: Vec<(Rank, (String, u32))> = ranked.by_ref().take(3).collect();
+        let saved = serde_json::to_string(&ranked.checkpoint()).unwrap();
+        drop(ranked);
+
+        let checkpoint: RankCheckpoint<u32> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(checkpoint.rank(), Some(Rank(2)));
+        let rest = data.into_iter().skip(checkpoint.consumed());
+        let tail: Vec<(Rank, (String, u32))> =
+            rest.rank_by_with_checkpoint(checkpoint, key).collect();
+        assert_eq!(head, expected[..3]);
+        assert_eq!(tail, expected[3..]);
+    }
+
+    #[test]
+    fn test_rank_by_distinct_count() {
+        assert_eq!(
+            Vec::<i32>::new().into_iter().rank_by_distinct_count(|&x| x),
+            0
+        );
+        assert_eq!(vec![4; 5].into_iter().rank_by_distinct_count(|&x| x), 1);
+        assert_eq!((0..9).rank_by_distinct_count(|&x| x), 9);
+        let data = vec![5, 1, 5, 2, 2, 8];
+        let max_rank = data
+            .clone()
+            .into_iter()
+            .rank_by(|&x| x)
+            .map(|(r, _)| r)
+            .max()
+            .unwrap_or(Rank(0));
+        assert_eq!(data.into_iter().rank_by_distinct_count(|&x| x), max_rank.0);
+    }
+
+    #[test]
+    fn test_rank_by_interpose_report() {
+        let data = vec![("cy", 2), ("ann", 1), ("bob", 2), ("di", 4)];
+        let report: Vec<String> = data
+            .into_iter()
+            .rank_by_interpose(|&(_, score)| score, |_| "---")
+            .map(|entry| match entry {
+                RankItem::Item(rank, (name, _)) => format!("{} {name}", rank.0),
+                RankItem::Separator(sep) => sep.to_string(),
+            })
+            .collect();
+        assert_eq!(report, vec!["1 ann", "---", "2 cy", "2 bob", "---", "3 di"]);
+    }
+
+    #[test]
+    fn test_rank_by_interpose_separator_count() {
+        let data = vec![5, 3, 5, 1, 3, 9, 9];
+        let entries: Vec<RankItem<i32, Rank>> =
+            data.into_iter().rank_by_interpose(|&x| x, |r| r).collect();
+        let separators: Vec<Rank> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                RankItem::Separator(rank) => Some(*rank),
+                RankItem::Item(..) => None,
+            })
+            .collect();
+        assert_eq!(separators, vec![Rank(2), Rank(3), Rank(4)]);
+        assert!(matches!(entries.first(), Some(RankItem::Item(Rank(1), 1))));
+        assert!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_interpose(|&x| x, |r| r)
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_iter_groups_matches_rank_by_to_groups() {
+        let data = vec![4, 1, 3, 1, 4, 4, 2];
+        let lazy: Vec<(Rank, Vec<i32>)> = data
+            .clone()
+            .into_iter()
+            .rank_by(|&x| x)
+            .iter_groups()
+            .map(|(rank, items)| (rank, items.collect()))
+            .collect();
+        assert_eq!(lazy, data.into_iter().rank_by_to_groups(|&x| x));
+        assert!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by(|&x| x)
+                .iter_groups()
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_iter_groups_reads_one_group_at_a_time() {
+        let (evaluated, data) = (std::cell::Cell::new(0), vec![1, 1, 2, 3, 3, 3]);
+        let mut groups = data
+            .into_iter()
+            .rank_by(|&x| {
+                evaluated.set(evaluated.get() + 1);
+                x
+            })
+            .iter_groups();
+        let after_sort = evaluated.get();
+        let (_, first) = groups.next().unwrap();
+        // The two items of the group, plus the peeked first item of the next one.
+        assert_eq!(evaluated.get() - after_sort, 3);
+        // Skipping a group's items doesn't affect the next group.
+        drop(first);
+        let (rank, second) = groups.next().unwrap();
+        assert_eq!((rank, second.collect::<Vec<_>>()), (Rank(2), vec![2]));
+    }
+
+    #[test]
+    fn test_rank_by_enumerate_within_group() {
+        let data = vec![30, 10, 20, 10, 30, 10];
+        let ranked: Vec<(Rank, usize, i32)> = data
+            .into_iter()
+            .rank_by_enumerate_within_group(|&x| x)
+            .collect();
+        let expected = vec![
+            (Rank(1), 0, 10),
+            (Rank(1), 1, 10),
+            (Rank(1), 2, 10),
+            (Rank(2), 0, 20),
+            (Rank(3), 0, 30),
+            (Rank(3), 1, 30),
         ];
+        assert_eq!(ranked, expected);
+    }
 
-        let ranked: Vec<(Rank, Item)> = data.into_iter().rank_by(|item| item.value).collect();
+    #[test]
+    fn test_rank_by_with_total() {
+        let data = vec![5, 3, 9, 3, 5, 5, 1];
+        let ranked: Vec<(Rank, usize, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_with_total(|&x| x)
+            .collect();
+        let max_rank = ranked.iter().map(|&(rank, _, _)| rank.0).max();
+        assert!(ranked.iter().all(|&(_, total, _)| Some(total) == max_rank));
+        assert_eq!(ranked[0].1, 4);
+        let plain: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        let stripped: Vec<(Rank, i32)> = ranked.into_iter().map(|(rank, _, x)| (rank, x)).collect();
+        assert_eq!(stripped, plain);
+        assert_eq!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_with_total(|&x| x)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rank_by_cross_validate() {
+        let unique = vec![4, 9, 1, 7].into_iter().rank_by_cross_validate(|&x| x);
+        assert!(
+            unique
+                .iter()
+                .all(|&(dense, competition, _)| dense == competition)
+        );
+        assert_eq!(unique.len(), 4);
+
+        let tied = vec![5, 3, 5, 1, 3, 3, 8]
+            .into_iter()
+            .rank_by_cross_validate(|&x| x);
+        let ranks: Vec<(usize, usize)> = tied.iter().map(|&(d, c, _)| (d.0, c.0)).collect();
+        assert_eq!(
+            ranks,
+            vec![(1, 1), (2, 2), (2, 2), (2, 2), (3, 5), (3, 5), (4, 7)]
+        );
+        assert!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_cross_validate(|&x| x)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_rank_by_stable_hash_tie_break_ignores_input_order() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |&(name, _): &(&str, u32)| {
+            let mut hasher = DefaultHasher::new();
+            name.hash(&mut hasher);
+            hasher.finish()
+        };
+        let players = vec![
+            ("ann", 3),
+            ("bob", 1),
+            ("cy", 3),
+            ("dan", 2),
+            ("eve", 3),
+            ("fay", 1),
+        ];
+        let rank = |input: Vec<(&'static str, u32)>| -> Vec<(Rank, (&str, u32))> {
+            input
+                .into_iter()
+                .rank_by_stable_hash_tie_break(|&(_, score)| score, hash)
+                .collect()
+        };
+        let expected = rank(players.clone());
+        for rotation in 1..players.len() {
+            let mut shuffled = players.clone();
+            shuffled.rotate_left(rotation);
+            assert_eq!(rank(shuffled.clone()), expected);
+            shuffled.reverse();
+            assert_eq!(rank(shuffled), expected);
+        }
+        let ranks: Vec<usize> = expected.iter().map(|&(rank, _)| rank.0).collect();
+        assert_eq!(ranks, vec![1, 1, 2, 3, 3, 3]);
+    }
+
+    #[test]
+    fn test_rank_by_stable_hash_tie_break_equal_hashes_keep_input_order() {
+        let ranked: Vec<(Rank, (u32, char))> = vec![(1, 'x'), (0, 'z'), (1, 'y'), (1, 'w')]
+            .into_iter()
+            .rank_by_stable_hash_tie_break(|&(k, _)| k, |&(_, c)| u64::from(c == 'w'))
+            .collect();
         let expected = vec![
-            (Rank(1), Item { id: 2, value: 10 }),
-            (Rank(1), Item { id: 4, value: 10 }),
-            (Rank(2), Item { id: 3, value: 20 }),
-            (Rank(3), Item { id: 1, value: 30 }),
-            (Rank(3), Item { id: 5, value: 30 }),
+            (Rank(1), (0, 'z')),
+            (Rank(2), (1, 'x')),
+            (Rank(2), (1, 'y')),
+            (Rank(2), (1, 'w')),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_by_cumulative_sum() {
+        let ranked: Vec<(usize, Rank, i32)> = vec![10, 10, 20]
+            .into_iter()
+            .rank_by_cumulative_sum(|&x| x)
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![(1, Rank(1), 10), (2, Rank(1), 10), (3, Rank(2), 20)]
+        );
+
+        let ranked: Vec<(usize, Rank, i32)> = vec![7, 3, 7, 7, 1]
+            .into_iter()
+            .rank_by_cumulative_sum(|&x| x)
+            .collect();
+        let expected = vec![
+            (1, Rank(1), 1),
+            (2, Rank(2), 3),
+            (3, Rank(3), 7),
+            (4, Rank(3), 7),
+            (5, Rank(3), 7),
+        ];
+        assert_eq!(ranked, expected);
+    }
+
+    /// Many items with few distinct keys, each tagged with its input position. This is
+    /// large enough that an unstable sort reorders ties.
+    fn tagged_ties() -> Vec<(u64, usize)> {
+        lcg(17)
+            .take(500)
+            .enumerate()
+            .map(|(i, state)| ((state >> 33) % 4, i))
+            .collect()
+    }
+
+    /// Asserts that the tags of the items sharing a rank are increasing.
+    fn assert_ties_in_input_order<'a>(ranked: impl IntoIterator<Item = (Rank, &'a (u64, usize))>) {
+        let mut prev: Option<(Rank, usize)> = None;
+        for (rank, &(_, tag)) in ranked {
+            if let Some((prev_rank, prev_tag)) = prev
+                && prev_rank == rank
+            {
+                assert!(
+                    prev_tag < tag,
+                    "tie order changed at {rank:?}: {prev_tag} before {tag}"
+                );
+            }
+            prev = Some((rank, tag));
+        }
+    }
+
+    #[test]
+    fn test_sorting_methods_keep_ties_in_input_order() {
+        let data = tagged_ties();
+        let key = |&(k, _): &(u64, usize)| k;
+        let iter = || data.iter().copied();
+
+        let ranked: Vec<(Rank, (u64, usize))> = iter().rank_by(key).collect();
+        assert_ties_in_input_order(ranked.iter().map(|(r, x)| (*r, x)));
+        let ranked: Vec<(Rank, (u64, usize))> = iter().rank_by_desc(key).collect();
+        assert_ties_in_input_order(ranked.iter().map(|(r, x)| (*r, x)));
+        let ranked = iter().rank_by_collect(key);
+        assert_ties_in_input_order(ranked.iter().map(|(r, x)| (*r, x)));
+        let ranked: Vec<(Rank, (u64, usize))> = iter().top_k_by(400, key).collect();
+        assert_ties_in_input_order(ranked.iter().map(|(r, x)| (*r, x)));
+        let ranked: Vec<(Rank, (u64, usize))> = iter()
+            .rank_by_with_tie_break(key, |_, _| Ordering::Equal)
+            .collect();
+        assert_ties_in_input_order(ranked.iter().map(|(r, x)| (*r, x)));
+        let ranked: Vec<(Rank, (u64, usize))> = iter()
+            .rank_within_by(|&(k, _)| k % 2, key)
+            .map(|(_, r, x)| (r, x))
+            .collect();
+        assert_ties_in_input_order(ranked.iter().map(|(r, x)| (*r, x)));
+        for (rank, group) in iter().rank_by_to_groups(key) {
+            assert_ties_in_input_order(group.iter().map(|x| (rank, x)));
+        }
+        let ranking: Ranking<(u64, usize)> = iter().rank_by(key).collect();
+        assert_ties_in_input_order(ranking.iter());
+        let ranked: Vec<(Rank, (u64, usize))> = crate::sql::row_number_by(iter(), key).collect();
+        let tags: Vec<usize> = ranked.iter().map(|&(_, (_, tag))| tag).collect();
+        let stable: Vec<usize> = data
+            .iter()
+            .copied()
+            .rank_by(key)
+            .map(|(_, (_, tag))| tag)
+            .collect();
+        assert_eq!(tags, stable);
+    }
+
+    #[test]
+    fn test_rank_by_unstable_gives_the_same_ranks() {
+        let data = tagged_ties();
+        let key = |&(k, _): &(u64, usize)| k;
+        let mut stable: Vec<(Rank, (u64, usize))> = data.iter().copied().rank_by(key).collect();
+        let mut unstable: Vec<(Rank, (u64, usize))> =
+            data.iter().copied().rank_by_unstable(key).collect();
+        stable.sort();
+        unstable.sort();
+        assert_eq!(unstable, stable);
+    }
+
+    #[test]
+    fn test_rank_by_prefilter_excluded_items_dont_count() {
+        let data = vec![5, 2, 8, 2, 3, 9];
+        let all: Vec<(Option<Rank>, i32)> = data
+            .clone()
+            .into_iter()
+            .rank_by_prefilter(|_| true, |&x| x)
+            .collect();
+        assert_eq!(all[3], (Some(Rank(3)), 5));
+
+        let odd: Vec<(Option<Rank>, i32)> = data
+            .into_iter()
+            .rank_by_prefilter(|&x| x % 2 == 1, |&x| x)
+            .collect();
+        let expected = vec![
+            (Some(Rank(1)), 3),
+            (Some(Rank(2)), 5),
+            (Some(Rank(3)), 9),
+            (None, 2),
+            (None, 8),
+            (None, 2),
         ];
+        assert_eq!(odd, expected);
+    }
+
+    #[test]
+    fn test_rank_by_indirect_matches_rank_by() {
+        let data = tagged_ties();
+        let key = |&(k, _): &(u64, usize)| k;
+        let expected: Vec<(Rank, (u64, usize))> = data.iter().copied().rank_by(key).collect();
+        let ranked: Vec<(Rank, (u64, usize))> =
+            data.iter().copied().rank_by_indirect(key).collect();
         assert_eq!(ranked, expected);
+        assert_eq!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_indirect(|&x| x)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rank_by_indirect_moves_items_without_copies() {
+        struct Tracked<'a> {
+            key: u32,
+            clones: &'a std::cell::Cell<usize>,
+            drops: &'a std::cell::Cell<usize>,
+        }
+        impl Clone for Tracked<'_> {
+            fn clone(&self) -> Self {
+                self.clones.set(self.clones.get() + 1);
+                Tracked { ..*self }
+            }
+        }
+        impl Drop for Tracked<'_> {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        let clones = std::cell::Cell::new(0);
+        let drops = std::cell::Cell::new(0);
+        let items: Vec<Tracked> = [4, 1, 4, 2, 1]
+            .into_iter()
+            .map(|key| Tracked {
+                key,
+                clones: &clones,
+                drops: &drops,
+            })
+            .collect();
+        let mut ranked = items.into_iter().rank_by_indirect(|t| t.key);
+        let (rank, first) = ranked.next().unwrap();
+        assert_eq!((rank, first.key), (Rank(1), 1));
+        drop(first);
+        assert_eq!(drops.get(), 1);
+        let keys: Vec<(Rank, u32)> = ranked.map(|(rank, t)| (rank, t.key)).collect();
+        assert_eq!(
+            keys,
+            vec![(Rank(1), 1), (Rank(2), 2), (Rank(3), 4), (Rank(3), 4)]
+        );
+        assert_eq!(clones.get(), 0);
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn test_rank_by_group_stats() {
+        let data = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd'), (3, 'e'), (1, 'f')];
+        let stats = data.into_iter().rank_by_group_stats(|&(k, _)| k);
+        let summary: Vec<(Rank, usize, char, char)> = stats
+            .iter()
+            .map(|s| (s.rank, s.count, s.first.1, s.last.1))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Rank(1), 2, 'b', 'f'),
+                (Rank(2), 1, 'd', 'd'),
+                (Rank(3), 3, 'a', 'e'),
+            ]
+        );
+        assert_eq!(stats[2].all_items, vec![(3, 'a'), (3, 'c'), (3, 'e')]);
+        assert!(stats.iter().all(|s| s.count == s.all_items.len()));
+        assert!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_group_stats(|&x| x)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_stable_hash_is_fnv1a() {
+        assert_eq!(stable_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(stable_hash(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_order_ties_by_hash_exact_order() {
+        let entries = vec![
+            ("p3", 2),
+            ("p1", 1),
+            ("p5", 2),
+            ("p2", 2),
+            ("p4", 1),
+            ("p6", 3),
+        ];
+        let order = |entries: Vec<(&'static str, u32)>| -> Vec<(Rank, &str)> {
+            entries
+                .into_iter()
+                .rank_by(|&(_, score)| score)
+                .order_ties_by_hash(|&(id, _)| id)
+                .map(|(rank, (id, _))| (rank, id))
+                .collect()
+        };
+        let expected = vec![
+            (Rank(1), "p4"),
+            (Rank(1), "p1"),
+            (Rank(2), "p5"),
+            (Rank(2), "p3"),
+            (Rank(2), "p2"),
+            (Rank(3), "p6"),
+        ];
+        assert_eq!(order(entries.clone()), expected);
+        let mut reversed = entries;
+        reversed.reverse();
+        assert_eq!(order(reversed), expected);
+    }
+
+    #[test]
+    fn test_rank_by_merge_n_matches_chain_rank_by() {
+        let mut states = lcg(5);
+        let mut next = || (states.next().unwrap() >> 33) % 9;
+        for k in [0, 1, 2, 4, 7] {
+            let shards: Vec<Vec<(u64, usize)>> = (0..k)
+                .map(|s| {
+                    let mut shard: Vec<(u64, usize)> =
+                        (0..s * 2 + 3).map(|i| (next(), s * 100 + i)).collect();
+                    shard.sort_by_key(|&(key, _)| key);
+                    shard
+                })
+                .collect();
+            let expected: Vec<(Rank, (u64, usize))> = shards
+                .concat()
+                .into_iter()
+                .rank_by(|&(key, _)| key)
+                .collect();
+            let merged: Vec<(Rank, (u64, usize))> =
+                rank_by_merge_n(shards, |&(key, _)| key).collect();
+            assert_eq!(merged, expected, "k = {k}");
+        }
+    }
+
+    #[test]
+    fn test_rank_eligible_by_matches_filtering_first() {
+        let data = [
+            (4, true),
+            (2, false),
+            (4, true),
+            (1, true),
+            (3, false),
+            (2, true),
+        ];
+        let filtered: Vec<(Rank, (u32, bool))> = data
+            .iter()
+            .copied()
+            .filter(|&(_, ok)| ok)
+            .rank_by(|&(k, _)| k)
+            .collect();
+        for placement in [IneligiblePlacement::End, IneligiblePlacement::InPlace] {
+            let ranked: Vec<(Option<Rank>, (u32, bool))> = data
+                .iter()
+                .copied()
+                .rank_eligible_by(|&(_, ok)| ok, |&(k, _)| k, placement)
+                .collect();
+            let ranked_only: Vec<(Rank, (u32, bool))> = ranked
+                .iter()
+                .filter_map(|&(rank, item)| rank.map(|rank| (rank, item)))
+                .collect();
+            assert_eq!(ranked_only, filtered);
+            assert_eq!(ranked.len(), data.len());
+        }
+    }
+
+    #[test]
+    fn test_rank_eligible_by_placement() {
+        // The ineligible 2 would have tied with the eligible 2.
+        let data = [
+            (4, true),
+            (2, false),
+            (4, true),
+            (1, true),
+            (3, false),
+            (2, true),
+        ];
+        let keys = |placement| -> Vec<(Option<usize>, u32)> {
+            data.iter()
+                .copied()
+                .rank_eligible_by(|&(_, ok)| ok, |&(k, _)| k, placement)
+                .map(|(rank, (k, _))| (rank.map(|r| r.0), k))
+                .collect()
+        };
+        assert_eq!(
+            keys(IneligiblePlacement::End),
+            vec![
+                (Some(1), 1),
+                (Some(2), 2),
+                (Some(3), 4),
+                (Some(3), 4),
+                (None, 2),
+                (None, 3)
+            ]
+        );
+        assert_eq!(
+            keys(IneligiblePlacement::InPlace),
+            vec![
+                (Some(1), 1),
+                (None, 2),
+                (Some(2), 2),
+                (Some(3), 4),
+                (None, 3),
+                (Some(3), 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rank_eligible_by_all_or_none_eligible() {
+        let data = [3, 1, 2];
+        for placement in [IneligiblePlacement::End, IneligiblePlacement::InPlace] {
+            let none: Vec<(Option<Rank>, i32)> = data
+                .iter()
+                .copied()
+                .rank_eligible_by(|_| false, |&x| x, placement)
+                .collect();
+            assert_eq!(none, vec![(None, 3), (None, 1), (None, 2)]);
+            let all: Vec<(Option<Rank>, i32)> = data
+                .iter()
+                .copied()
+                .rank_eligible_by(|_| true, |&x| x, placement)
+                .collect();
+            assert_eq!(
+                all,
+                vec![(Some(Rank(1)), 1), (Some(Rank(2)), 2), (Some(Rank(3)), 3)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_rank_by_strategy_patterns() {
+        let data = vec![30, 10, 20, 20, 40, 20];
+        let ranks = |strategy| -> Vec<f64> {
+            data.iter()
+                .copied()
+                .rank_by_strategy(strategy, |&x| x)
+                .map(|(rank, _)| rank)
+                .collect()
+        };
+        let cases = [
+            (RankingStrategy::Dense, vec![1.0, 2.0, 2.0, 2.0, 3.0, 4.0]),
+            (
+                RankingStrategy::Competition,
+                vec![1.0, 2.0, 2.0, 2.0, 5.0, 6.0],
+            ),
+            (
+                RankingStrategy::Modified,
+                vec![1.0, 4.0, 4.0, 4.0, 5.0, 6.0],
+            ),
+            (RankingStrategy::Ordinal, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+            (
+                RankingStrategy::Fractional,
+                vec![1.0, 3.0, 3.0, 3.0, 5.0, 6.0],
+            ),
+        ];
+        // Alternating strategies between calls must not leak state.
+        for (strategy, expected) in cases.iter().chain(cases.iter().rev()) {
+            assert_eq!(&ranks(*strategy), expected, "{strategy:?}");
+        }
+
+        let dense: Vec<(Rank, i32)> = data.iter().copied().rank_by(|&x| x).collect();
+        let by_strategy: Vec<(Rank, i32)> = data
+            .into_iter()
+            .rank_by_strategy(RankingStrategy::Dense, |&x| x)
+            .map(|(rank, x)| (Rank(rank as usize), x))
+            .collect();
+        assert_eq!(by_strategy, dense);
+    }
+
+    #[test]
+    fn test_annotate_ranks() {
+        let population = vec![20, 50, 10, 40, 20, 30];
+        let table: RankTable<i32> = population.into_iter().collect();
+        let queries = vec![
+            ("hit", 30),
+            ("min", 10),
+            ("below", 5),
+            ("above", 70),
+            ("between", 25),
+        ];
+
+        let exact: Vec<(Option<Rank>, &str)> = queries
+            .iter()
+            .copied()
+            .annotate_ranks(&table, |&(_, k)| k)
+            .map(|(rank, (name, _))| (rank, name))
+            .collect();
+        assert_eq!(
+            exact,
+            vec![
+                (Some(Rank(3)), "hit"),
+                (Some(Rank(1)), "min"),
+                (None, "below"),
+                (None, "above"),
+                (None, "between"),
+            ]
+        );
+
+        let floor: Vec<Option<Rank>> = queries
+            .into_iter()
+            .annotate_ranks_floor(&table, |&(_, k)| k)
+            .map(|(rank, _)| rank)
+            .collect();
+        assert_eq!(
+            floor,
+            vec![
+                Some(Rank(3)),
+                Some(Rank(1)),
+                None,
+                Some(Rank(5)),
+                Some(Rank(2))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_with_eq_ord_epsilon_distance() {
+        let target = 5.0_f64;
+        let points = vec![5.3, 4.0, 4.995, 5.001, 6.5, 3.5];
+        let ranked: Vec<(Rank, f64)> = points
+            .into_iter()
+            .rank_by_with_eq_ord(
+                |&x| (x - target).abs(),
+                f64::total_cmp,
+                |a, b| (a - b).abs() < 1e-2,
+            )
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (Rank(1), 5.001),
+                (Rank(1), 4.995),
+                (Rank(2), 5.3),
+                (Rank(3), 4.0),
+                (Rank(4), 6.5),
+                (Rank(4), 3.5),
+            ]
+        );
+        let empty: Vec<f64> = Vec::new();
+        assert_eq!(
+            empty
+                .into_iter()
+                .rank_by_with_eq_ord(|&x| x, f64::total_cmp, |a, b| a == b)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rank_by_with_eq_ord_collation_with_exact_equality() {
+        let words = vec!["b", "a", "B", "a", "c"];
+        let ranked: Vec<(Rank, &str)> = words
+            .into_iter()
+            .rank_by_with_eq_ord(
+                |&w| w,
+                |a, b| a.to_lowercase().cmp(&b.to_lowercase()),
+                |a, b| a == b,
+            )
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (Rank(1), "a"),
+                (Rank(1), "a"),
+                (Rank(2), "b"),
+                (Rank(3), "B"),
+                (Rank(4), "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_scatter_positions() {
+        let medals = vec![('a', 2), ('b', 1), ('c', 2), ('d', 1), ('e', 1), ('f', 2)];
+        let layout = medals
+            .clone()
+            .into_iter()
+            .rank_by_scatter(|&(_, m)| m, |rank, i| 2 * i + rank.0)
+            .unwrap();
+        let names: Vec<(usize, char)> = (1..=6).map(|p| (p, layout[&p].1.0)).collect();
+        assert_eq!(
+            names,
+            vec![(1, 'b'), (2, 'a'), (3, 'd'), (4, 'c'), (5, 'e'), (6, 'f')]
+        );
+        assert_eq!(layout[&5].0, Rank(1));
+
+        // A default rank_by ordering.
+        let sequential = medals
+            .clone()
+            .into_iter()
+            .rank_by_scatter(|&(_, m)| m, sequential_positions())
+            .unwrap();
+        let expected: Vec<(Rank, (char, i32))> = medals.into_iter().rank_by(|&(_, m)| m).collect();
+        assert_eq!(sequential.len(), expected.len());
+        for (position, ranked) in expected.into_iter().enumerate() {
+            assert_eq!(sequential[&position], ranked);
+        }
+    }
+
+    #[test]
+    fn test_rank_by_scatter_detects_conflicts() {
+        let data = vec![1, 2, 1, 3];
+        // Rank 3's first item lands on rank 1's second position.
+        let conflict = data
+            .into_iter()
+            .rank_by_scatter(|&x| x, |rank, i| 2 * i + rank.0 - 1)
+            .unwrap_err();
+        assert_eq!(
+            conflict,
+            ScatterConflict {
+                position: 2,
+                placed: Rank(1),
+                rejected: Rank(3),
+            }
+        );
+        assert_eq!(
+            conflict.to_string(),
+            "position 2 is given to items of ranks 1 and 3"
+        );
+        let empty = Vec::<i32>::new()
+            .into_iter()
+            .rank_by_scatter(|&x| x, |_, _| 0);
+        assert!(empty.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rank_by_into_btreeset() {
+        let distinct = vec![30, 10, 20];
+        let groups = distinct.into_iter().rank_by_into_btreeset(|&x| x);
+        let expected: BTreeMap<Rank, BTreeSet<i32>> = [
+            (Rank(1), BTreeSet::from([10])),
+            (Rank(2), BTreeSet::from([20])),
+            (Rank(3), BTreeSet::from([30])),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(groups, expected);
+
+        // Rows repeated by a join, ranked by their second field.
+        let rows = vec![(7, 'b'), (3, 'a'), (7, 'b'), (9, 'b'), (3, 'a'), (5, 'c')];
+        let groups = rows.into_iter().rank_by_into_btreeset(|&(_, c)| c);
+        let ranks: Vec<Rank> = groups.keys().copied().collect();
+        assert_eq!(ranks, vec![Rank(1), Rank(2), Rank(3)]);
+        assert_eq!(groups[&Rank(1)], BTreeSet::from([(3, 'a')]));
+        assert_eq!(groups[&Rank(2)], BTreeSet::from([(7, 'b'), (9, 'b')]));
+        assert_eq!(groups[&Rank(3)], BTreeSet::from([(5, 'c')]));
+        assert!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_into_btreeset(|&x| x)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_rank_by_annotate_outputs() {
+        #[derive(Debug, PartialEq)]
+        struct Placed {
+            rank: Rank,
+            position: usize,
+            tied: bool,
+            name: &'static str,
+        }
+
+        let scores = vec![("d", 3), ("a", 1), ("b", 2), ("c", 2)];
+        let placed: Vec<Placed> = scores
+            .clone()
+            .into_iter()
+            .rank_by_annotate(
+                |&(_, s)| s,
+                |rank, position, size, (name, _)| Placed {
+                    rank,
+                    position,
+                    tied: size > 1,
+                    name,
+                },
+            )
+            .collect();
+        assert_eq!(
+            placed[2],
+            Placed {
+                rank: Rank(2),
+                position: 1,
+                tied: true,
+                name: "c",
+            }
+        );
+        assert!(!placed[3].tied);
+
+        let labels: Vec<String> = scores
+            .clone()
+            .into_iter()
+            .rank_by_annotate(
+                |&(_, s)| s,
+                |rank, position, size, (name, _)| {
+                    format!("{}.{}/{} {name}", rank.0, position, size)
+                },
+            )
+            .collect();
+        assert_eq!(labels, vec!["1.0/1 a", "2.0/2 b", "2.1/2 c", "3.0/1 d"]);
+
+        // Fractional ranks: the mean of the positions the group covers.
+        let mut covered = 0;
+        let fractional: Vec<(f64, &str)> = scores
+            .into_iter()
+            .rank_by_annotate(
+                |&(_, s)| s,
+                |_, position, size, (name, _)| {
+                    if position == 0 {
+                        covered += size;
+                    }
+                    ((covered - size + 1 + covered) as f64 / 2.0, name)
+                },
+            )
+            .collect();
+        assert_eq!(
+            fractional,
+            vec![(1.0, "a"), (2.5, "b"), (2.5, "c"), (4.0, "d")]
+        );
+
+        let empty = Vec::<i32>::new();
+        assert_eq!(
+            empty
+                .into_iter()
+                .rank_by_annotate(|&x| x, |_, _, _, x| x)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rank_by_rev_groups() {
+        let data = vec![('a', 2), ('b', 3), ('c', 1), ('d', 2), ('e', 3)];
+        let ranked: Vec<(Rank, char)> = data
+            .clone()
+            .into_iter()
+            .rank_by_rev_groups(|&(_, k)| k)
+            .map(|(rank, (c, _))| (rank, c))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (Rank(3), 'b'),
+                (Rank(3), 'e'),
+                (Rank(2), 'a'),
+                (Rank(2), 'd'),
+                (Rank(1), 'c'),
+            ]
+        );
+
+        // The same pairs as rank_by, with the groups reversed.
+        let mut forward: Vec<(Rank, (char, i32))> =
+            data.clone().into_iter().rank_by(|&(_, k)| k).collect();
+        forward.sort_by_key(|&(rank, _)| Reverse(rank));
+        let reversed: Vec<(Rank, (char, i32))> =
+            data.into_iter().rank_by_rev_groups(|&(_, k)| k).collect();
+        assert_eq!(reversed, forward);
+        assert_eq!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_rev_groups(|&x| x)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_flatten_groups_inverts_rank_by_to_groups() {
+        let data = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd'), (1, 'e')];
+        let groups = data.clone().into_iter().rank_by_to_groups(|&(k, _)| k);
+        let flat: Vec<(Rank, (i32, char))> = flatten_groups(groups).collect();
+        let ranked: Vec<(Rank, (i32, char))> = data.into_iter().rank_by(|&(k, _)| k).collect();
+        assert_eq!(flat, ranked);
+
+        // Any group type that iterates works, including empty groups.
+        let slices: Vec<(Rank, &[u8])> = vec![(Rank(1), &[1, 2]), (Rank(2), &[]), (Rank(3), &[3])];
+        let flat: Vec<(Rank, &u8)> = flatten_groups(slices).collect();
+        assert_eq!(flat, vec![(Rank(1), &1), (Rank(1), &2), (Rank(3), &3)]);
+        assert_eq!(flatten_groups(Vec::<(Rank, Vec<u8>)>::new()).count(), 0);
+    }
+
+    #[test]
+    fn test_rank_by_label_array_five_groups() {
+        let scores = vec![50, 10, 40, 20, 30, 10];
+        let labels: Vec<(&str, i32)> = scores
+            .into_iter()
+            .rank_by_label_array(|&x| x, ["Gold", "Silver", "Bronze"], "Other")
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("Gold", 10),
+                ("Gold", 10),
+                ("Silver", 20),
+                ("Bronze", 30),
+                ("Other", 40),
+                ("Other", 50),
+            ]
+        );
+
+        let grades: Vec<char> = (0..3)
+            .rank_by_label_array(|&x| x, ['A'], '-')
+            .map(|(grade, _)| grade)
+            .collect();
+        assert_eq!(grades, vec!['A', '-', '-']);
+    }
+
+    #[test]
+    fn test_rank_by_bipartite_ranks_each_side() {
+        let data = vec![
+            ('a', 7),
+            ('B', 3),
+            ('c', 2),
+            ('D', 3),
+            ('e', 7),
+            ('F', 1),
+            ('g', 9),
+        ];
+        let ranked: Vec<(bool, Rank, char)> = data
+            .clone()
+            .into_iter()
+            .rank_by_bipartite(|&(_, k)| k, |&(c, _)| c.is_uppercase())
+            .map(|(side, rank, (c, _))| (side, rank, c))
+            .collect();
+        assert_eq!(ranked.len(), data.len());
+        let order: Vec<char> = ranked.iter().map(|&(_, _, c)| c).collect();
+        assert_eq!(order, vec!['a', 'B', 'c', 'D', 'e', 'F', 'g']);
+
+        for side in [false, true] {
+            let mut ranks: Vec<(Rank, char)> = ranked
+                .iter()
+                .filter(|&&(s, _, _)| s == side)
+                .map(|&(_, rank, c)| (rank, c))
+                .collect();
+            ranks.sort();
+            let expected: Vec<(Rank, char)> = data
+                .iter()
+                .filter(|&&(c, _)| c.is_uppercase() == side)
+                .rank_by(|&&(_, k)| k)
+                .map(|(rank, &(c, _))| (rank, c))
+                .collect();
+            assert_eq!(ranks, expected);
+        }
+        assert_eq!(ranked[1], (true, Rank(2), 'B'));
+        assert_eq!(ranked[2], (false, Rank(1), 'c'));
+        assert_eq!(
+            Vec::<i32>::new()
+                .into_iter()
+                .rank_by_bipartite(|&x| x, |_| true)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rank_by_temporal_decay_separates_equal_keys() {
+        let items = vec![('a', 8.0, 3.0), ('b', 8.0, 1.0), ('c', 8.0, 3.0)];
+        let ranked: Vec<(Rank, char)> = items
+            .into_iter()
+            .rank_by_temporal_decay(|&(_, key, _)| key, |&(_, _, time)| time, 0.1)
+            .map(|(rank, (c, _, _))| (rank, c))
+            .collect();
+        // 'b' is two units older, so its key decays below the others'.
+        assert_eq!(ranked, vec![(Rank(1), 'b'), (Rank(2), 'a'), (Rank(2), 'c')]);
+    }
+
+    #[test]
+    fn test_rank_by_temporal_decay_ranks_older_positive_keys_first() {
+        // (score, day), all with the same score.
+        let items = [(10.0, 2.0), (10.0, 0.0), (10.0, 1.0)];
+        let days = |negate: bool| -> Vec<f64> {
+            items
+                .iter()
+                .rank_by_temporal_decay(
+                    |&&(score, _)| if negate { -score } else { score },
+                    |&&(_, day)| day,
+                    0.5,
+                )
+                .map(|(_, &(_, day))| day)
+                .collect()
+        };
+        // Positive keys shrink toward 0 with age, so the oldest ranks first.
+        assert_eq!(days(false), vec![0.0, 1.0, 2.0]);
+        // Negated scores rank the freshest first.
+        assert_eq!(days(true), vec![2.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_rank_by_temporal_decay_without_decay_matches_rank_by() {
+        let items: Vec<(u32, u32)> = lcg(7)
+            .take(50)
+            .map(|state| ((state >> 33) as u32 % 10, (state >> 40) as u32 % 100))
+            .collect();
+        let decayed: Vec<(Rank, (u32, u32))> = items
+            .clone()
+            .into_iter()
+            .rank_by_temporal_decay(|&(key, _)| key as f64, |&(_, time)| time as f64, 0.0)
+            .collect();
+        let plain: Vec<(Rank, (u32, u32))> = items.into_iter().rank_by(|&(key, _)| key).collect();
+        assert_eq!(decayed, plain);
+        assert_eq!(
+            std::iter::empty::<u32>()
+                .rank_by_temporal_decay(|&x| x as f64, |&x| x as f64, 1.0)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rank_by_coalesce_within_epsilon() {
+        let ranks: Vec<(Rank, f64)> = vec![1.005, 1.0, 1.001]
+            .into_iter()
+            .rank_by_coalesce(|&x| x, 0.01, |a, b| a - b)
+            .collect();
+        assert_eq!(
+            ranks,
+            vec![(Rank(1), 1.0), (Rank(1), 1.001), (Rank(1), 1.005)]
+        );
+
+        let ranks: Vec<(Rank, f64)> = vec![1.02, 1.0]
+            .into_iter()
+            .rank_by_coalesce(|&x| x, 0.01, |a, b| a - b)
+            .collect();
+        assert_eq!(ranks, vec![(Rank(1), 1.0), (Rank(2), 1.02)]);
+    }
+
+    #[test]
+    fn test_rank_by_coalesce_does_not_chain() {
+        // Each step is within epsilon, but the run is measured from its first key.
+        let ids = vec![(0, 10), (1, 13), (2, 16), (3, 19), (4, 22)];
+        let ranks: Vec<(Rank, u32)> = ids
+            .into_iter()
+            .rank_by_coalesce(|&(_, k)| k, 5, |a, b| a - b)
+            .map(|(rank, (id, _))| (rank, id))
+            .collect();
+        assert_eq!(
+            ranks,
+            vec![
+                (Rank(1), 0),
+                (Rank(1), 1),
+                (Rank(2), 2),
+                (Rank(2), 3),
+                (Rank(3), 4)
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rank_by_coalesce_rejects_nan() {
+        vec![1.0, f64::NAN, 0.5]
+            .into_iter()
+            .rank_by_coalesce(|&x| x, 0.1, |a, b| a - b)
+            .for_each(drop);
+    }
+
+    #[test]
+    fn test_rank_by_with_progress_iter() {
+        let mut ranked = vec![5, 3, 5, 1, 4]
+            .into_iter()
+            .rank_by_with_progress_iter(|&x| x);
+        assert_eq!(ranked.progress(), (0, 5));
+        for k in 1..=5 {
+            assert!(ranked.next().is_some());
+            assert_eq!(ranked.progress(), (k, 5));
+        }
+        assert_eq!(ranked.next(), None);
+        assert_eq!(ranked.progress(), (5, 5));
+
+        let ranks: Vec<(Rank, i32)> = vec![2, 1, 2]
+            .into_iter()
+            .rank_by_with_progress_iter(|&x| x)
+            .collect();
+        assert_eq!(ranks, vec![(Rank(1), 1), (Rank(2), 2), (Rank(2), 2)]);
+        let empty = std::iter::empty::<i32>().rank_by_with_progress_iter(|&x| x);
+        assert_eq!(empty.progress(), (0, 0));
     }
 }
@@ -0,0 +1,831 @@
+//! A collected ranking that keeps its items grouped by rank.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::rank::Rank;
+
+/// A fully collected dense ranking.
+///
+/// The items are stored in rank order together with the position where each rank group
+/// starts, so group-level queries don't need to touch the items themselves.
+/// A `Ranking` is usually built by collecting a ranked iterator: a new rank group starts
+/// wherever the incoming rank changes, and the stored ranks are dense (1, 2, 3, ...).
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::RankedExt;
+/// use librank::Ranking;
+///
+/// let data = vec![10, 20, 10];
+/// let ranking: Ranking<i32> = data.into_iter().rank_by(|&x| x).collect();
+///
+/// assert_eq!(ranking.len(), 3);
+/// assert_eq!(ranking.distinct_ranks(), 2);
+/// assert_eq!(
+///     ranking.iter().collect::<Vec<_>>(),
+///     vec![(Rank(1), &10), (Rank(1), &10), (Rank(2), &20)]
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ranking<T> {
+    items: Vec<T>,
+    starts: Vec<usize>,
+}
+
+impl<T> Ranking<T> {
+    /// Builds a ranking from items in rank order and the start index of every group.
+    ///
+    /// `starts` must be strictly increasing, begin with 0 when `items` is non-empty and
+    /// only contain indices within `items`.
+    pub(crate) fn from_parts(items: Vec<T>, starts: Vec<usize>) -> Self {
+        debug_assert!(starts.first().is_none_or(|&s| s == 0));
+        debug_assert!(starts.windows(2).all(|w| w[0] < w[1]));
+        debug_assert!(starts.last().is_none_or(|&s| s < items.len()));
+        debug_assert_eq!(items.is_empty(), starts.is_empty());
+        Ranking { items, starts }
+    }
+
+    /// Builds a ranking from groups of items already in rank order.
+    ///
+    /// The first group gets rank 1, the next rank 2, and so on; no sorting takes place.
+    /// Empty groups are skipped and don't use up a rank. This is the inverse of
+    /// [`into_groups`](Ranking::into_groups).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, Ranking};
+    ///
+    /// let ranking = Ranking::from_groups(vec![vec!['b', 'd'], vec![], vec!['a']]);
+    ///
+    /// assert_eq!(
+    ///     ranking.into_iter().collect::<Vec<_>>(),
+    ///     vec![(Rank(1), 'b'), (Rank(1), 'd'), (Rank(2), 'a')]
+    /// );
+    /// ```
+    pub fn from_groups(groups: impl IntoIterator<Item = Vec<T>>) -> Self {
+        let mut items = Vec::new();
+        let mut starts = Vec::new();
+        for group in groups {
+            if !group.is_empty() {
+                starts.push(items.len());
+                items.extend(group);
+            }
+        }
+        Ranking::from_parts(items, starts)
+    }
+
+    /// Consumes the ranking and returns its rank groups, in ascending rank order.
+    pub fn into_groups(self) -> Vec<Vec<T>> {
+        let mut items = self.items;
+        let mut groups = Vec::with_capacity(self.starts.len());
+        for &start in self.starts.iter().rev() {
+            groups.push(items.split_off(start));
+        }
+        groups.reverse();
+        groups
+    }
+
+    /// Merges rankings of separate parts of the data, each sorted by the same key, into
+    /// one ranking by that key.
+    ///
+    /// The parts are merged a whole group at a time, with `key_fn` called once on the
+    /// first item of every group; groups with equal keys in different parts are joined
+    /// into one. The result is the same as ranking all the items at once with
+    /// [`rank_by`](crate::RankedExt::rank_by), as if the parts had been concatenated in
+    /// order: tied items from earlier parts come first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankedExt, Ranking};
+    ///
+    /// let left: Ranking<i32> = vec![3, 1, 3].into_iter().rank_by(|&x| x).collect();
+    /// let right: Ranking<i32> = vec![2, 3].into_iter().rank_by(|&x| x).collect();
+    /// let merged = Ranking::merge(vec![left, right], |&x| x);
+    ///
+    /// assert_eq!(
+    ///     merged.into_iter().collect::<Vec<_>>(),
+    ///     vec![(Rank(1), 1), (Rank(2), 2), (Rank(3), 3), (Rank(3), 3), (Rank(3), 3)]
+    /// );
+    /// ```
+    pub fn merge<K, F>(parts: Vec<Ranking<T>>, mut key_fn: F) -> Self
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        let mut parts: Vec<std::vec::IntoIter<Vec<T>>> = parts
+            .into_iter()
+            .map(|part| part.into_groups().into_iter())
+            .collect();
+        let mut heads: Vec<Option<Vec<T>>> = Vec::with_capacity(parts.len());
+        // Ordered by key and then by part, so that ties keep the order of the parts.
+        let mut heap = BinaryHeap::with_capacity(parts.len());
+        for (i, part) in parts.iter_mut().enumerate() {
+            let head = part.next();
+            if let Some(group) = &head {
+                heap.push(Reverse((key_fn(&group[0]), i)));
+            }
+            heads.push(head);
+        }
+
+        let mut items = Vec::new();
+        let mut starts = Vec::new();
+        while let Some(Reverse((key, i))) = heap.pop() {
+            starts.push(items.len());
+            let mut next = Some(i);
+            while let Some(i) = next {
+                items.extend(heads[i].take().expect("every part in the heap has a head"));
+                heads[i] = parts[i].next();
+                if let Some(group) = &heads[i] {
+                    heap.push(Reverse((key_fn(&group[0]), i)));
+                }
+                next = match heap.peek() {
+                    Some(Reverse((next_key, j))) if *next_key == key => Some(*j),
+                    _ => None,
+                };
+                if next.is_some() {
+                    heap.pop();
+                }
+            }
+        }
+        Ranking::from_parts(items, starts)
+    }
+
+    /// Combines this ranking with another ranking by the same key, giving the ranking of
+    /// all their items.
+    ///
+    /// This is [`merge`](Ranking::merge) of the two: the sorted groups are merged in one
+    /// pass without re-sorting, and groups with equal keys fuse into one rank, with this
+    /// ranking's items before `other`'s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankedExt, Ranking};
+    ///
+    /// let quarter: Ranking<(&str, u32)> =
+    ///     vec![("ann", 7), ("bob", 3)].into_iter().rank_by(|&(_, t)| t).collect();
+    /// let late: Ranking<(&str, u32)> =
+    ///     vec![("cy", 7), ("dee", 5)].into_iter().rank_by(|&(_, t)| t).collect();
+    /// let names: Vec<(Rank, &str)> = quarter
+    ///     .merged_with(late, |&(_, t)| t)
+    ///     .into_iter()
+    ///     .map(|(rank, (name, _))| (rank, name))
+    ///     .collect();
+    ///
+    /// assert_eq!(names, vec![(Rank(1), "bob"), (Rank(2), "dee"), (Rank(3), "ann"), (Rank(3), "cy")]);
+    /// ```
+    pub fn merged_with<K, F>(self, other: Ranking<T>, key_fn: F) -> Self
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        Ranking::merge(vec![self, other], key_fn)
+    }
+
+    /// Keeps the items matching a predicate and ranks them afresh, pairing each with its
+    /// rank in the original ranking.
+    ///
+    /// The items are already in order, so nothing is sorted: every group with at least
+    /// one remaining item becomes a group of the new ranking, and the new ranks are dense
+    /// however many groups were removed. The old ranks keep their gaps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    ///
+    /// use librank::{Rank, RankedExt, Ranking};
+    ///
+    /// let shops = vec![("eu", 90), ("us", 95), ("eu", 70), ("us", 80)];
+    /// let ranking: Ranking<(&str, u32)> = shops.into_iter().rank_by(|&(_, s)| Reverse(s)).collect();
+    /// let europe: Vec<(Rank, Rank, u32)> = ranking
+    ///     .filter_rerank(|&(region, _)| region == "eu")
+    ///     .into_iter()
+    ///     .map(|(new, (old, (_, sales)))| (new, old, sales))
+    ///     .collect();
+    ///
+    /// // #1 in the region, #2 overall.
+    /// assert_eq!(europe, vec![(Rank(1), Rank(2), 90), (Rank(2), Rank(4), 70)]);
+    /// ```
+    pub fn filter_rerank<P>(self, mut predicate: P) -> Ranking<(Rank, T)>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let groups = self
+            .into_groups()
+            .into_iter()
+            .enumerate()
+            .map(|(i, group)| {
+                group
+                    .into_iter()
+                    .filter(|item| predicate(item))
+                    .map(|item| (Rank(i + 1), item))
+                    .collect()
+            });
+        Ranking::from_groups(groups)
+    }
+
+    /// Returns the number of ranked items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the ranking contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the number of distinct ranks, which is also the highest rank.
+    pub fn distinct_ranks(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns the items in rank order.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Consumes the ranking and returns the items in rank order.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Returns an iterator over the ranked items.
+    pub fn iter(&self) -> impl Iterator<Item = (Rank, &T)> {
+        self.groups()
+            .flat_map(|(rank, group)| group.iter().map(move |item| (rank, item)))
+    }
+
+    /// Returns an iterator over the rank groups, in ascending rank order.
+    pub fn groups(&self) -> impl Iterator<Item = (Rank, &[T])> {
+        self.starts.iter().enumerate().map(|(i, &start)| {
+            let end = self.group_end(i);
+            (Rank(i + 1), &self.items[start..end])
+        })
+    }
+
+    /// Returns `true` if any rank is shared by more than one item.
+    pub fn has_ties(&self) -> bool {
+        self.distinct_ranks() < self.len()
+    }
+
+    /// Returns the ranks shared by more than one item, with the number of items sharing
+    /// each, in ascending rank order.
+    pub fn tie_groups(&self) -> impl Iterator<Item = (Rank, usize)> {
+        self.groups()
+            .map(|(rank, group)| (rank, group.len()))
+            .filter(|&(_, size)| size > 1)
+    }
+
+    /// Summarizes the sizes of the rank groups.
+    ///
+    /// This only looks at the group boundaries, not at the items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{RankedExt, Ranking};
+    ///
+    /// let ranking: Ranking<i32> = vec![1, 2, 2, 3, 3, 3].into_iter().rank_by(|&x| x).collect();
+    /// assert_eq!(
+    ///     ranking.summary().to_string(),
+    ///     "6 items, 3 ranks, largest group 3 at rank 3, group sizes 1:1 2:1 3+:1"
+    /// );
+    /// ```
+    pub fn summary(&self) -> RankSummary {
+        let mut summary = RankSummary {
+            items: self.len(),
+            distinct_ranks: self.distinct_ranks(),
+            largest_group: None,
+            groups_of_one: 0,
+            groups_of_two: 0,
+            groups_of_three_or_more: 0,
+        };
+        for (i, &start) in self.starts.iter().enumerate() {
+            let size = self.group_end(i) - start;
+            match size {
+                1 => summary.groups_of_one += 1,
+                2 => summary.groups_of_two += 1,
+                _ => summary.groups_of_three_or_more += 1,
+            }
+            if summary
+                .largest_group
+                .is_none_or(|(_, largest)| size > largest)
+            {
+                summary.largest_group = Some((Rank(i + 1), size));
+            }
+        }
+        summary
+    }
+
+    /// Compares this ranking with a previous one and reports how every item moved.
+    ///
+    /// Items are matched by `id_fn` rather than by equality, so their other fields may
+    /// change between the two rankings. The changes are ordered by new rank, in this
+    /// ranking's order, followed by the items that dropped out, in their previous rank
+    /// order. If an id appears more than once in a ranking, its first occurrence is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use librank::{Rank, RankDelta, RankedExt, Ranking};
+    ///
+    /// let previous: Ranking<(&str, u32)> =
+    ///     vec![("a", 3), ("b", 2), ("c", 1)].into_iter().rank_by_desc(|p| p.1).collect();
+    /// let current: Ranking<(&str, u32)> =
+    ///     vec![("a", 1), ("b", 5), ("d", 4)].into_iter().rank_by_desc(|p| p.1).collect();
+    ///
+    /// let deltas: Vec<(&str, RankDelta)> = current
+    ///     .diff(&previous, |p| p.0)
+    ///     .into_iter()
+    ///     .map(|change| (change.id, change.delta))
+    ///     .collect();
+    /// assert_eq!(
+    ///     deltas,
+    ///     vec![
+    ///         ("b", RankDelta::Up(1)),
+    ///         ("d", RankDelta::Entered),
+    ///         ("a", RankDelta::Down(2)),
+    ///         ("c", RankDelta::Dropped),
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff<Id, F>(&self, previous: &Ranking<T>, mut id_fn: F) -> Vec<RankChange<Id>>
+    where
+        Id: Hash + Eq + Clone,
+        F: FnMut(&T) -> Id,
+    {
+        let mut old_ranks: HashMap<Id, (usize, Rank)> = HashMap::new();
+        for (i, (rank, item)) in previous.iter().enumerate() {
+            old_ranks.entry(id_fn(item)).or_insert((i, rank));
+        }
+
+        let mut seen = HashSet::new();
+        let mut changes = Vec::with_capacity(self.len());
+        for (rank, item) in self.iter() {
+            let id = id_fn(item);
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            let old = old_ranks.remove(&id).map(|(_, rank)| rank);
+            changes.push(RankChange::new(id, old, Some(rank)));
+        }
+
+        let mut dropped: Vec<(Id, (usize, Rank))> = old_ranks.into_iter().collect();
+        dropped.sort_by_key(|&(_, (i, _))| i);
+        changes.extend(
+            dropped
+                .into_iter()
+                .map(|(id, (_, rank))| RankChange::new(id, Some(rank), None)),
+        );
+        changes
+    }
+
+    fn group_end(&self, group: usize) -> usize {
+        self.starts
+            .get(group + 1)
+            .copied()
+            .unwrap_or(self.items.len())
+    }
+}
+
+impl<T> FromIterator<(Rank, T)> for Ranking<T> {
+    fn from_iter<It: IntoIterator<Item = (Rank, T)>>(iter: It) -> Self {
+        let mut items = Vec::new();
+        let mut starts = Vec::new();
+        let mut prev_rank = None;
+        for (rank, item) in iter {
+            if prev_rank != Some(rank) {
+                starts.push(items.len());
+                prev_rank = Some(rank);
+            }
+            items.push(item);
+        }
+        Ranking { items, starts }
+    }
+}
+
+impl<T> IntoIterator for Ranking<T> {
+    type Item = (Rank, T);
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut starts = self.starts.into_iter();
+        IntoIter {
+            next_start: starts.next(),
+            items: self.items.into_iter(),
+            starts,
+            pos: 0,
+            rank: Rank(0),
+        }
+    }
+}
+
+/// An owning iterator over the items of a [`Ranking`].
+pub struct IntoIter<T> {
+    items: std::vec::IntoIter<T>,
+    starts: std::vec::IntoIter<usize>,
+    next_start: Option<usize>,
+    pos: usize,
+    rank: Rank,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (Rank, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.items.next()?;
+        if self.next_start == Some(self.pos) {
+            self.rank = Rank(self.rank.0 + 1);
+            self.next_start = self.starts.next();
+        }
+        self.pos += 1;
+        Some((self.rank, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+/// A summary of the rank group sizes of a [`Ranking`], returned by [`Ranking::summary`].
+///
+/// The `Display` impl prints a compact one-line form suitable for logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RankSummary {
+    /// The number of ranked items.
+    pub items: usize,
+    /// The number of distinct ranks.
+    pub distinct_ranks: usize,
+    /// The rank and size of the largest rank group, the lowest rank if several are
+    /// equally large, or `None` for an empty ranking.
+    pub largest_group: Option<(Rank, usize)>,
+    /// The number of ranks held by a single item.
+    pub groups_of_one: usize,
+    /// The number of ranks shared by exactly two items.
+    pub groups_of_two: usize,
+    /// The number of ranks shared by three or more items.
+    pub groups_of_three_or_more: usize,
+}
+
+impl fmt::Display for RankSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} items, {} ranks", self.items, self.distinct_ranks)?;
+        if let Some((rank, size)) = self.largest_group {
+            write!(
+                f,
+                ", largest group {} at rank {}, group sizes 1:{} 2:{} 3+:{}",
+                size, rank.0, self.groups_of_one, self.groups_of_two, self.groups_of_three_or_more
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How an item's rank changed between two rankings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RankDelta {
+    /// The item moved to a better (lower) rank by the given number of places.
+    Up(usize),
+    /// The item moved to a worse (higher) rank by the given number of places.
+    Down(usize),
+    /// The item kept its rank.
+    Unchanged,
+    /// The item is new to the ranking.
+    Entered,
+    /// The item is no longer in the ranking.
+    Dropped,
+}
+
+/// A single entry of [`Ranking::diff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RankChange<Id> {
+    /// The id of the item.
+    pub id: Id,
+    /// The item's rank in the previous ranking, if it was present.
+    pub old_rank: Option<Rank>,
+    /// The item's rank in the current ranking, if it is present.
+    pub new_rank: Option<Rank>,
+    /// The change between the two ranks.
+    pub delta: RankDelta,
+}
+
+impl<Id> RankChange<Id> {
+    fn new(id: Id, old_rank: Option<Rank>, new_rank: Option<Rank>) -> Self {
+        let delta = match (old_rank, new_rank) {
+            (Some(old), Some(new)) if new < old => RankDelta::Up(old.0 - new.0),
+            (Some(old), Some(new)) if new > old => RankDelta::Down(new.0 - old.0),
+            (Some(_), Some(_)) => RankDelta::Unchanged,
+            (None, _) => RankDelta::Entered,
+            (Some(_), None) => RankDelta::Dropped,
+        };
+        RankChange {
+            id,
+            old_rank,
+            new_rank,
+            delta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+    use crate::test_support::lcg;
+
+    #[test]
+    fn test_ranking_from_rank_by() {
+        let data = vec![10, 20, 10, 30, 20, 10];
+        let ranking: Ranking<i32> = data.into_iter().rank_by(|&x| x).collect();
+        assert_eq!(ranking.len(), 6);
+        assert_eq!(ranking.distinct_ranks(), 3);
+        let groups: Vec<(Rank, &[i32])> = ranking.groups().collect();
+        assert_eq!(
+            groups,
+            vec![
+                (Rank(1), &[10, 10, 10][..]),
+                (Rank(2), &[20, 20][..]),
+                (Rank(3), &[30][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranking_into_iter_round_trips() {
+        let ranked: Vec<(Rank, char)> = vec![(Rank(1), 'a'), (Rank(2), 'b'), (Rank(2), 'c')];
+        let ranking: Ranking<char> = ranked.clone().into_iter().collect();
+        assert_eq!(ranking.into_iter().collect::<Vec<_>>(), ranked);
+    }
+
+    #[test]
+    fn test_ranking_from_groups_round_trips_through_into_groups() {
+        let data = vec![4, 1, 3, 1, 4, 4, 2, 9];
+        let groups: Vec<Vec<i32>> = data
+            .clone()
+            .into_iter()
+            .rank_by_to_groups(|&x| x)
+            .into_iter()
+            .map(|(_, group)| group)
+            .collect();
+        let ranking = Ranking::from_groups(groups.clone());
+        let expected: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        assert_eq!(ranking.clone().into_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(ranking.into_groups(), groups);
+    }
+
+    #[test]
+    fn test_ranking_from_groups_skips_empty_groups() {
+        let ranking = Ranking::from_groups(vec![
+            vec![],
+            vec!['a'],
+            vec![],
+            vec![],
+            vec!['b'],
+            vec!['c'],
+        ]);
+        assert_eq!(
+            ranking.iter().collect::<Vec<_>>(),
+            vec![(Rank(1), &'a'), (Rank(2), &'b'), (Rank(3), &'c')]
+        );
+        assert_eq!(ranking.into_groups(), vec![vec!['a'], vec!['b'], vec!['c']]);
+        assert!(Ranking::<u8>::from_groups(vec![vec![], vec![]]).is_empty());
+    }
+
+    #[test]
+    fn test_ranking_renumbers_ranks_densely() {
+        let ranked = vec![
+            (Rank(1), 'a'),
+            (Rank(1), 'b'),
+            (Rank(3), 'c'),
+            (Rank(7), 'd'),
+        ];
+        let ranking: Ranking<char> = ranked.into_iter().collect();
+        let ranks: Vec<Rank> = ranking.iter().map(|(r, _)| r).collect();
+        assert_eq!(ranks, vec![Rank(1), Rank(1), Rank(2), Rank(3)]);
+    }
+
+    #[test]
+    fn test_ranking_empty() {
+        let ranking: Ranking<i32> = std::iter::empty().collect();
+        assert!(ranking.is_empty());
+        assert_eq!(ranking.distinct_ranks(), 0);
+        assert_eq!(ranking.into_iter().next(), None);
+    }
+
+    #[test]
+    fn test_ranking_diff() {
+        // (id, score), ranked by descending score.
+        let previous: Ranking<(char, u32)> =
+            vec![('a', 90), ('b', 80), ('c', 70), ('d', 60), ('e', 50)]
+                .into_iter()
+                .rank_by_desc(|p| p.1)
+                .collect();
+        let current: Ranking<(char, u32)> =
+            vec![('a', 91), ('b', 80), ('d', 80), ('c', 70), ('f', 65)]
+                .into_iter()
+                .rank_by_desc(|p| p.1)
+                .collect();
+
+        let changes = current.diff(&previous, |p| p.0);
+        let expected = vec![
+            RankChange::new('a', Some(Rank(1)), Some(Rank(1))),
+            RankChange::new('b', Some(Rank(2)), Some(Rank(2))),
+            RankChange::new('d', Some(Rank(4)), Some(Rank(2))),
+            RankChange::new('c', Some(Rank(3)), Some(Rank(3))),
+            RankChange::new('f', None, Some(Rank(4))),
+            RankChange::new('e', Some(Rank(5)), None),
+        ];
+        assert_eq!(changes, expected);
+        let deltas: Vec<RankDelta> = changes.iter().map(|c| c.delta).collect();
+        assert_eq!(
+            deltas,
+            vec![
+                RankDelta::Unchanged,
+                RankDelta::Unchanged,
+                RankDelta::Up(2),
+                RankDelta::Unchanged,
+                RankDelta::Entered,
+                RankDelta::Dropped,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranking_tie_groups() {
+        let unique: Ranking<i32> = vec![3, 1, 2].into_iter().rank_by(|&x| x).collect();
+        assert!(!unique.has_ties());
+        assert_eq!(unique.tie_groups().count(), 0);
+
+        let all_tied: Ranking<i32> = vec![5; 4].into_iter().rank_by(|&x| x).collect();
+        assert!(all_tied.has_ties());
+        assert_eq!(
+            all_tied.tie_groups().collect::<Vec<_>>(),
+            vec![(Rank(1), 4)]
+        );
+
+        let scattered: Ranking<i32> = vec![1, 2, 2, 3, 4, 4, 4, 5]
+            .into_iter()
+            .rank_by(|&x| x)
+            .collect();
+        assert_eq!(
+            scattered.tie_groups().collect::<Vec<_>>(),
+            vec![(Rank(2), 2), (Rank(4), 3)]
+        );
+    }
+
+    #[test]
+    fn test_ranking_summary_all_unique() {
+        let ranking: Ranking<i32> = vec![4, 2, 3, 1].into_iter().rank_by(|&x| x).collect();
+        let summary = ranking.summary();
+        assert_eq!(
+            summary,
+            RankSummary {
+                items: 4,
+                distinct_ranks: 4,
+                largest_group: Some((Rank(1), 1)),
+                groups_of_one: 4,
+                groups_of_two: 0,
+                groups_of_three_or_more: 0,
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "4 items, 4 ranks, largest group 1 at rank 1, group sizes 1:4 2:0 3+:0"
+        );
+    }
+
+    #[test]
+    fn test_ranking_summary_all_tied() {
+        let ranking: Ranking<i32> = vec![9; 5].into_iter().rank_by(|&x| x).collect();
+        let summary = ranking.summary();
+        assert_eq!(summary.distinct_ranks, 1);
+        assert_eq!(summary.largest_group, Some((Rank(1), 5)));
+        assert_eq!(summary.groups_of_three_or_more, 1);
+
+        let empty: Ranking<i32> = std::iter::empty().collect();
+        assert_eq!(empty.summary().largest_group, None);
+        assert_eq!(empty.summary().to_string(), "0 items, 0 ranks");
+    }
+
+    #[test]
+    fn test_merge_matches_ranking_concatenated_input() {
+        let mut states = lcg(7);
+        let mut next = || (states.next().unwrap() >> 33) % 12;
+        for shards in [1, 2, 3, 5] {
+            let parts: Vec<Vec<(u64, usize)>> = (0..shards)
+                .map(|s| (0..s * 3 + 4).map(|i| (next(), s * 100 + i)).collect())
+                .collect();
+            let expected: Ranking<(u64, usize)> =
+                parts.concat().into_iter().rank_by(|&(k, _)| k).collect();
+            let ranked = parts
+                .into_iter()
+                .map(|part| part.into_iter().rank_by(|&(k, _)| k).collect())
+                .collect();
+            assert_eq!(Ranking::merge(ranked, |&(k, _)| k), expected);
+        }
+    }
+
+    #[test]
+    fn test_merged_with_matches_ranking_concatenation() {
+        let left = vec![(4, 'a'), (1, 'b'), (4, 'c'), (2, 'd')];
+        let right = vec![(3, 'e'), (4, 'f'), (1, 'g'), (9, 'h')];
+        let rank = |items: Vec<(i32, char)>| -> Ranking<(i32, char)> {
+            items.into_iter().rank_by(|&(k, _)| k).collect()
+        };
+        let expected = rank([left.clone(), right.clone()].concat());
+        let merged = rank(left.clone()).merged_with(rank(right.clone()), |&(k, _)| k);
+        assert_eq!(merged, expected);
+
+        // Fused groups keep the left items first.
+        let fours: Vec<char> = merged
+            .groups()
+            .nth(3)
+            .unwrap()
+            .1
+            .iter()
+            .map(|&(_, c)| c)
+            .collect();
+        assert_eq!(fours, vec!['a', 'c', 'f']);
+
+        let empty = || rank(Vec::new());
+        assert_eq!(
+            rank(left.clone()).merged_with(empty(), |&(k, _)| k),
+            rank(left)
+        );
+        assert_eq!(
+            empty().merged_with(rank(right.clone()), |&(k, _)| k),
+            rank(right)
+        );
+        assert!(empty().merged_with(empty(), |&(k, _)| k).is_empty());
+    }
+
+    #[test]
+    fn test_merge_joins_keys_duplicated_across_shards() {
+        let shard = |keys: Vec<i32>| -> Ranking<i32> { keys.into_iter().rank_by(|&x| x).collect() };
+        let merged = Ranking::merge(
+            vec![
+                shard(vec![1, 5, 5]),
+                Ranking::from_groups(Vec::new()),
+                shard(vec![5, 9, 1]),
+            ],
+            |&x| x,
+        );
+        let groups: Vec<(Rank, &[i32])> = merged.groups().collect();
+        assert_eq!(
+            groups,
+            vec![
+                (Rank(1), &[1, 1][..]),
+                (Rank(2), &[5, 5, 5][..]),
+                (Rank(3), &[9][..]),
+            ]
+        );
+        assert!(Ranking::<i32>::merge(Vec::new(), |&x| x).is_empty());
+    }
+
+    #[test]
+    fn test_filter_rerank_removes_whole_groups() {
+        let data = vec![(1, 'a'), (2, 'b'), (2, 'c'), (3, 'd'), (4, 'e'), (4, 'f')];
+        let ranking: Ranking<(u32, char)> = data.into_iter().rank_by(|&(k, _)| k).collect();
+        let odd = ranking.filter_rerank(|&(k, _)| k % 2 == 1);
+        let ranks: Vec<(Rank, Rank, char)> = odd
+            .into_iter()
+            .map(|(new, (old, (_, c)))| (new, old, c))
+            .collect();
+        assert_eq!(
+            ranks,
+            vec![(Rank(1), Rank(1), 'a'), (Rank(2), Rank(3), 'd')]
+        );
+    }
+
+    #[test]
+    fn test_filter_rerank_splits_tie_groups() {
+        let data = vec![(5, 'a'), (5, 'b'), (5, 'c'), (7, 'd'), (7, 'e'), (9, 'f')];
+        let ranking: Ranking<(u32, char)> = data.into_iter().rank_by(|&(k, _)| k).collect();
+        let subset = ranking
+            .clone()
+            .filter_rerank(|&(_, c)| c != 'b' && c != 'd');
+        let groups: Vec<(Rank, Vec<(Rank, char)>)> = subset
+            .groups()
+            .map(|(rank, group)| (rank, group.iter().map(|&(old, (_, c))| (old, c)).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                (Rank(1), vec![(Rank(1), 'a'), (Rank(1), 'c')]),
+                (Rank(2), vec![(Rank(2), 'e')]),
+                (Rank(3), vec![(Rank(3), 'f')]),
+            ]
+        );
+        assert!(ranking.filter_rerank(|_| false).is_empty());
+    }
+}
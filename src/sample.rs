@@ -0,0 +1,405 @@
+//! Randomness for ranked items: sampling weighted by rank and random tie-breaking.
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngExt};
+
+use crate::rank::{Rank, RankedExt};
+use crate::ranking::Ranking;
+use crate::selection::{SelectionScheme, selection_probabilities};
+
+/// How the sampling weight of an item decreases with its rank.
+///
+/// Items sharing a rank always get the same weight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RankWeights {
+    /// `1 / rank`.
+    Reciprocal,
+    /// `exp(-alpha * (rank - 1))`, so rank 1 has weight 1. `alpha` must be finite and
+    /// not negative; 0 gives every item the same weight.
+    Exponential(f64),
+}
+
+impl RankWeights {
+    /// Returns the weight of an item with the given rank.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `alpha` of [`RankWeights::Exponential`] is negative or not finite.
+    pub fn weight(self, rank: Rank) -> f64 {
+        match self {
+            RankWeights::Reciprocal => 1.0 / rank.0 as f64,
+            RankWeights::Exponential(alpha) => {
+                assert!(
+                    alpha.is_finite() && alpha >= 0.0,
+                    "RankWeights::Exponential: alpha must be finite and not negative, got {alpha}"
+                );
+                (-alpha * (rank.0 - 1) as f64).exp()
+            }
+        }
+    }
+}
+
+/// Samples `k` distinct items, each draw picking an item with probability proportional
+/// to its weight among the items not yet drawn.
+///
+/// The items are returned in the order they were drawn. If `k` is at least the number
+/// of items, every item is returned. The result only depends on the ranking and the
+/// state of `rng`, so a seeded generator gives reproducible samples.
+///
+/// # Examples
+///
+/// ```
+/// use librank::sample::{RankWeights, sample_by_rank};
+/// use librank::{RankedExt, Ranking};
+///
+/// let ranking: Ranking<char> = vec!['a', 'b', 'c'].into_iter().rank_by(|&c| c).collect();
+/// let sample = sample_by_rank(&ranking, &mut rand::rng(), RankWeights::Reciprocal, 2);
+///
+/// assert_eq!(sample.len(), 2);
+/// assert_ne!(sample[0], sample[1]);
+/// ```
+pub fn sample_by_rank<'a, T, R>(
+    ranking: &'a Ranking<T>,
+    rng: &mut R,
+    weights: RankWeights,
+    k: usize,
+) -> Vec<&'a T>
+where
+    R: Rng + ?Sized,
+{
+    // Efraimidis-Spirakis: drawing in decreasing order of u^(1/w), or equivalently of
+    // ln(u) / w, for uniform u is the same as sequential weighted draws.
+    let mut keyed: Vec<(f64, &T)> = ranking
+        .iter()
+        .map(|(rank, item)| {
+            let u: f64 = rng.random();
+            ((1.0 - u).ln() / weights.weight(rank), item)
+        })
+        .collect();
+    // Weights that underflow to 0 give infinite or NaN keys, so the order must be total.
+    keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    keyed.into_iter().take(k).map(|(_, item)| item).collect()
+}
+
+/// Samples `k` items with replacement, each draw picking an item with probability
+/// proportional to its weight.
+///
+/// Returns no items if the ranking is empty. The result only depends on the ranking
+/// and the state of `rng`.
+pub fn sample_by_rank_with_replacement<'a, T, R>(
+    ranking: &'a Ranking<T>,
+    rng: &mut R,
+    weights: RankWeights,
+    k: usize,
+) -> Vec<&'a T>
+where
+    R: Rng + ?Sized,
+{
+    let weights = ranking.iter().map(|(rank, _)| weights.weight(rank));
+    draw_with_replacement(ranking.items(), weights, rng, k)
+}
+
+/// Draws `k` individuals of a ranking with replacement, with the probabilities of a
+/// rank-based [`SelectionScheme`].
+///
+/// The probabilities are those of [`selection_probabilities`], so tied individuals are
+/// equally likely. Returns no items if the ranking is empty. The result only depends
+/// on the ranking and the state of `rng`.
+///
+/// # Panics
+///
+/// Panics on the same invalid schemes as
+/// [`selection_weights`](crate::selection::selection_weights).
+///
+/// # Examples
+///
+/// ```
+/// use librank::sample::select_by_rank_scheme;
+/// use librank::selection::SelectionScheme;
+/// use librank::{RankedExt, Ranking};
+///
+/// let population: Ranking<u32> = vec![3, 1, 2].into_iter().rank_by(|&x| x).collect();
+/// let parents = select_by_rank_scheme(&population, &mut rand::rng(), SelectionScheme::Linear(2.0), 4);
+///
+/// // With full pressure the last individual is never selected.
+/// assert_eq!(parents.len(), 4);
+/// assert!(!parents.contains(&&3));
+/// ```
+pub fn select_by_rank_scheme<'a, T, R>(
+    ranking: &'a Ranking<T>,
+    rng: &mut R,
+    scheme: SelectionScheme,
+    k: usize,
+) -> Vec<&'a T>
+where
+    R: Rng + ?Sized,
+{
+    let probabilities = selection_probabilities(ranking, scheme);
+    draw_with_replacement(ranking.items(), probabilities, rng, k)
+}
+
+/// Draws `k` items with replacement, each with probability proportional to its weight.
+fn draw_with_replacement<'a, T, R>(
+    items: &'a [T],
+    weights: impl IntoIterator<Item = f64>,
+    rng: &mut R,
+    k: usize,
+) -> Vec<&'a T>
+where
+    R: Rng + ?Sized,
+{
+    let mut cumulative = Vec::with_capacity(items.len());
+    let mut total = 0.0;
+    for weight in weights {
+        total += weight;
+        cumulative.push(total);
+    }
+    if items.is_empty() {
+        return Vec::new();
+    }
+    (0..k)
+        .map(|_| {
+            let target = rng.random::<f64>() * total;
+            let i = cumulative.partition_point(|&c| c <= target);
+            &items[i.min(items.len() - 1)]
+        })
+        .collect()
+}
+
+/// Ranks the items by a key like [`rank_by`](crate::RankedExt::rank_by), but yields the
+/// items of each tie group in a random order.
+///
+/// Only the order within each group is shuffled: the ranks and the order of the groups
+/// are unchanged. Each group is shuffled as it is reached, so the order only depends on
+/// the items and the state of `rng`.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::sample::rank_by_random_ties;
+///
+/// let ranked: Vec<(Rank, i32)> = rank_by_random_ties(vec![2, 1, 2], |&x| x, &mut rand::rng()).collect();
+///
+/// assert_eq!(ranked, vec![(Rank(1), 1), (Rank(2), 2), (Rank(2), 2)]);
+/// ```
+pub fn rank_by_random_ties<I, F, K, R>(
+    items: I,
+    f: F,
+    rng: &mut R,
+) -> impl Iterator<Item = (Rank, I::Item)>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+    R: Rng + ?Sized,
+{
+    items
+        .into_iter()
+        .rank_by(f)
+        .iter_groups()
+        .flat_map(move |(rank, group)| {
+            let mut group: Vec<I::Item> = group.collect();
+            group.shuffle(rng);
+            group.into_iter().map(move |item| (rank, item))
+        })
+}
+
+/// Ranks the items by a key with ordinal ranks, breaking ties in a random order.
+///
+/// Every item gets its own rank, from 1 to the number of items. Items with smaller keys
+/// always get smaller ranks, and the ranks within a tie group are given out in the
+/// order of [`rank_by_random_ties`], so the result only depends on the items and the
+/// state of `rng` rather than on the input order.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::sample::rank_by_ordinal_rng;
+///
+/// let ranked: Vec<(Rank, i32)> = rank_by_ordinal_rng(vec![2, 1, 2], |&x| x, &mut rand::rng()).collect();
+///
+/// assert_eq!(ranked, vec![(Rank(1), 1), (Rank(2), 2), (Rank(3), 2)]);
+/// ```
+pub fn rank_by_ordinal_rng<I, F, K, R>(
+    items: I,
+    f: F,
+    rng: &mut R,
+) -> impl Iterator<Item = (Rank, I::Item)>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+    R: Rng + ?Sized,
+{
+    rank_by_random_ties(items, f, rng)
+        .enumerate()
+        .map(|(i, (_, item))| (Rank(i + 1), item))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::RankedExt;
+
+    /// Ranks 1, 2, 2, 3 for the items 0 to 3.
+    fn ranking() -> Ranking<usize> {
+        vec![(0, 10), (1, 20), (2, 20), (3, 30)]
+            .into_iter()
+            .rank_by(|&(_, k)| k)
+            .map(|(rank, (i, _))| (rank, i))
+            .collect()
+    }
+
+    fn frequencies(draws: &[&usize]) -> Vec<f64> {
+        let mut counts = [0.0; 4];
+        for &&i in draws {
+            counts[i] += 1.0;
+        }
+        counts.iter().map(|c| c / draws.len() as f64).collect()
+    }
+
+    #[test]
+    fn test_sample_with_replacement_matches_weights() {
+        let ranking = ranking();
+        let mut rng = StdRng::seed_from_u64(7);
+        let draws =
+            sample_by_rank_with_replacement(&ranking, &mut rng, RankWeights::Reciprocal, 60_000);
+        let total = 1.0 + 0.5 + 0.5 + 1.0 / 3.0;
+        let expected = [1.0 / total, 0.5 / total, 0.5 / total, 1.0 / 3.0 / total];
+        for (freq, p) in frequencies(&draws).iter().zip(expected) {
+            assert!((freq - p).abs() < 0.01, "{freq} vs {p}");
+        }
+    }
+
+    #[test]
+    fn test_select_by_rank_scheme_matches_probabilities() {
+        let ranking = ranking();
+        let mut rng = StdRng::seed_from_u64(3);
+        let draws = select_by_rank_scheme(&ranking, &mut rng, SelectionScheme::Linear(1.5), 60_000);
+        // Positions get 0.375, 0.2917, 0.2083 and 0.125; the tie splits its two.
+        let expected = [0.375, 0.25, 0.25, 0.125];
+        for (freq, p) in frequencies(&draws).iter().zip(expected) {
+            assert!((freq - p).abs() < 0.01, "{freq} vs {p}");
+        }
+        let empty = Ranking::<usize>::from_groups(Vec::new());
+        assert!(
+            select_by_rank_scheme(&empty, &mut rng, SelectionScheme::Linear(1.5), 3).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_sample_without_replacement() {
+        let ranking = ranking();
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut all = sample_by_rank(&ranking, &mut rng, RankWeights::Exponential(1.0), 10);
+        all.sort();
+        assert_eq!(all, vec![&0, &1, &2, &3]);
+
+        let weights = RankWeights::Exponential(0.7);
+        let first: Vec<&usize> = (0..40_000)
+            .map(|_| sample_by_rank(&ranking, &mut rng, weights, 2)[0])
+            .collect();
+        let w: Vec<f64> = [1, 2, 2, 3]
+            .iter()
+            .map(|&r| weights.weight(Rank(r)))
+            .collect();
+        let total: f64 = w.iter().sum();
+        for (freq, wi) in frequencies(&first).iter().zip(&w) {
+            assert!((freq - wi / total).abs() < 0.01, "{freq} vs {}", wi / total);
+        }
+    }
+
+    #[test]
+    fn test_sample_with_underflowing_weights() {
+        // Past rank 75 or so the weights are 0, so those keys are infinite.
+        let ranking: Ranking<usize> = (0..2000).rank_by(|&i| i).collect();
+        let mut rng = StdRng::seed_from_u64(5);
+        let weights = RankWeights::Exponential(10.0);
+        assert_eq!(weights.weight(Rank(2000)), 0.0);
+        let top = sample_by_rank(&ranking, &mut rng, weights, 3);
+        assert!(top.iter().all(|&&i| i < 10), "{top:?}");
+
+        let mut all = sample_by_rank(&ranking, &mut rng, weights, 2000);
+        all.sort();
+        assert!(all.iter().copied().eq(&(0..2000).collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn test_sample_is_deterministic_given_seed() {
+        let ranking = ranking();
+        let draw = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (
+                sample_by_rank(&ranking, &mut rng, RankWeights::Reciprocal, 3),
+                sample_by_rank_with_replacement(&ranking, &mut rng, RankWeights::Reciprocal, 5),
+            )
+        };
+        assert_eq!(draw(3), draw(3));
+        let empty = Ranking::<u8>::from_groups(Vec::new());
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(
+            sample_by_rank_with_replacement(&empty, &mut rng, RankWeights::Reciprocal, 3)
+                .is_empty()
+        );
+        assert!(sample_by_rank(&empty, &mut rng, RankWeights::Reciprocal, 3).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_exponential_weights_reject_negative_alpha() {
+        RankWeights::Exponential(-1.0).weight(Rank(1));
+    }
+
+    #[test]
+    fn test_rank_by_random_ties_shuffles_within_groups() {
+        let data = vec![(2, 'x'), (1, 'a'), (2, 'y'), (1, 'b'), (1, 'c'), (0, 'z')];
+        let ranked = |seed| -> Vec<(Rank, (u32, char))> {
+            let mut rng = StdRng::seed_from_u64(seed);
+            rank_by_random_ties(data.clone(), |&(k, _)| k, &mut rng).collect()
+        };
+        assert_eq!(ranked(42), ranked(42));
+
+        let mut seen = std::collections::BTreeSet::new();
+        for seed in 0..200 {
+            let ranked = ranked(seed);
+            let ranks: Vec<(Rank, u32)> = ranked.iter().map(|&(r, (k, _))| (r, k)).collect();
+            let expected = vec![
+                (Rank(1), 0),
+                (Rank(2), 1),
+                (Rank(2), 1),
+                (Rank(2), 1),
+                (Rank(3), 2),
+                (Rank(3), 2),
+            ];
+            assert_eq!(ranks, expected);
+            let tie: String = ranked[1..4].iter().map(|&(_, (_, c))| c).collect();
+            seen.insert(tie);
+        }
+        // Every order of the three-way tie shows up.
+        let all: Vec<&str> = vec!["abc", "acb", "bac", "bca", "cab", "cba"];
+        assert_eq!(seen.iter().map(String::as_str).collect::<Vec<_>>(), all);
+    }
+
+    #[test]
+    fn test_rank_by_ordinal_rng() {
+        let data: Vec<(u32, usize)> = (0..12).map(|i| (i as u32 % 3, i)).collect();
+        let ranked = |seed| -> Vec<(Rank, (u32, usize))> {
+            let mut rng = StdRng::seed_from_u64(seed);
+            rank_by_ordinal_rng(data.clone(), |&(k, _)| k, &mut rng).collect()
+        };
+        assert_eq!(ranked(5), ranked(5));
+        assert_ne!(ranked(5), ranked(6));
+
+        for seed in 0..20 {
+            let ranked = ranked(seed);
+            let ranks: Vec<usize> = ranked.iter().map(|&(rank, _)| rank.0).collect();
+            assert_eq!(ranks, (1..=12).collect::<Vec<_>>());
+            assert!(ranked.windows(2).all(|w| w[0].1.0 <= w[1].1.0));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Tournament seeding from a ranking: bracket pairings and snake groups.
+//!
+//! The ranking's items are the seeds, in the order [`Ranking::iter`] yields them, so
+//! the first item is the first seed. Tied items get consecutive seeds in that order,
+//! which for a ranking built with [`rank_by`](crate::RankedExt::rank_by) is their
+//! input order.
+
+use crate::ranking::Ranking;
+
+/// Pairs the seeds for the first round of a single-elimination bracket: the first seed
+/// against the last, the second against the second to last, and so on.
+///
+/// The bracket has room for the next power of two of seeds, at least two. The missing
+/// seeds are byes, which fall to the top seeds: a pairing whose second entry is `None`
+/// is a bye for its first. The first entry is `Some` unless the ranking is empty, in
+/// which case there are no pairings. The pairings are in order of their top seed.
+///
+/// # Examples
+///
+/// ```
+/// use librank::seeding::bracket_pairings;
+/// use librank::{RankedExt, Ranking};
+///
+/// let ranking: Ranking<u32> = vec![1, 2, 3, 4, 5, 6].into_iter().rank_by(|&x| x).collect();
+///
+/// assert_eq!(
+///     bracket_pairings(&ranking),
+///     vec![
+///         (Some(&1), None),
+///         (Some(&2), None),
+///         (Some(&3), Some(&6)),
+///         (Some(&4), Some(&5)),
+///     ]
+/// );
+/// ```
+pub fn bracket_pairings<T>(ranking: &Ranking<T>) -> Vec<(Option<&T>, Option<&T>)> {
+    let seeds = ranking.items();
+    if seeds.is_empty() {
+        return Vec::new();
+    }
+    let size = seeds.len().next_power_of_two().max(2);
+    (0..size / 2)
+        .map(|i| (seeds.get(i), seeds.get(size - 1 - i)))
+        .collect()
+}
+
+/// Allocates the seeds to `k` groups in snake order, as in a draft: the first `k` seeds
+/// go to groups 1 to `k`, the next `k` to groups `k` down to 1, and so on.
+///
+/// When the seeds don't divide evenly, the groups that come first in the last pass get
+/// one seed more. Groups are empty if there are fewer seeds than groups.
+///
+/// # Panics
+///
+/// Panics if `k` is 0.
+///
+/// # Examples
+///
+/// ```
+/// use librank::seeding::snake_groups;
+/// use librank::{RankedExt, Ranking};
+///
+/// let ranking: Ranking<u32> = (1..=6).rank_by(|&x| x).collect();
+///
+/// assert_eq!(snake_groups(&ranking, 3), vec![vec![&1, &6], vec![&2, &5], vec![&3, &4]]);
+/// ```
+pub fn snake_groups<T>(ranking: &Ranking<T>, k: usize) -> Vec<Vec<&T>> {
+    assert!(k > 0, "cannot allocate seeds to zero groups");
+    let mut groups: Vec<Vec<&T>> = (0..k).map(|_| Vec::new()).collect();
+    for (i, seed) in ranking.items().iter().enumerate() {
+        let (pass, offset) = (i / k, i % k);
+        let group = if pass % 2 == 0 {
+            offset
+        } else {
+            k - 1 - offset
+        };
+        groups[group].push(seed);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+
+    fn seeds(n: u32) -> Ranking<u32> {
+        (1..=n).rank_by(|&x| x).collect()
+    }
+
+    fn numbers(pairings: Vec<(Option<&u32>, Option<&u32>)>) -> Vec<(Option<u32>, Option<u32>)> {
+        pairings
+            .into_iter()
+            .map(|(a, b)| (a.copied(), b.copied()))
+            .collect()
+    }
+
+    #[test]
+    fn test_bracket_pairings() {
+        assert_eq!(
+            numbers(bracket_pairings(&seeds(8))),
+            vec![
+                (Some(1), Some(8)),
+                (Some(2), Some(7)),
+                (Some(3), Some(6)),
+                (Some(4), Some(5)),
+            ]
+        );
+        assert_eq!(
+            numbers(bracket_pairings(&seeds(6))),
+            vec![
+                (Some(1), None),
+                (Some(2), None),
+                (Some(3), Some(6)),
+                (Some(4), Some(5))
+            ]
+        );
+        assert_eq!(numbers(bracket_pairings(&seeds(1))), vec![(Some(1), None)]);
+        assert!(bracket_pairings(&seeds(0)).is_empty());
+    }
+
+    #[test]
+    fn test_ties_are_seeded_in_ranking_order() {
+        let players = vec![("ann", 2), ("bob", 1), ("cy", 2)];
+        let ranking: Ranking<(&str, u32)> = players.into_iter().rank_by(|&(_, r)| r).collect();
+        let pairings: Vec<(&str, Option<&str>)> = bracket_pairings(&ranking)
+            .into_iter()
+            .map(|(a, b)| (a.unwrap().0, b.map(|b| b.0)))
+            .collect();
+        assert_eq!(pairings, vec![("bob", None), ("ann", Some("cy"))]);
+    }
+
+    #[test]
+    fn test_snake_groups_uneven() {
+        let groups: Vec<Vec<u32>> = snake_groups(&seeds(7), 3)
+            .into_iter()
+            .map(|group| group.into_iter().copied().collect())
+            .collect();
+        assert_eq!(groups, vec![vec![1, 6, 7], vec![2, 5], vec![3, 4]]);
+
+        let two = seeds(2);
+        assert_eq!(snake_groups(&two, 3), vec![vec![&1], vec![&2], vec![]]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_snake_groups_rejects_zero_groups() {
+        snake_groups(&seeds(3), 0);
+    }
+}
@@ -0,0 +1,156 @@
+//! Rank-based selection probabilities, as used by evolutionary algorithms.
+//!
+//! Rank-based selection gives each individual a probability that only depends on its
+//! position in the ranking, not on how far apart the fitness values are. With the
+//! `rand` feature, `sample::select_by_rank_scheme` draws individuals with these
+//! probabilities.
+
+use crate::ranking::Ranking;
+
+/// How the selection probability decreases from the first position to the last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionScheme {
+    /// Linear ranking with selection pressure `s` between 1 and 2: the first position
+    /// is `s` times as likely as the average and the last `2 - s` times, with the
+    /// positions in between on a straight line. 1 is uniform.
+    Linear(f64),
+    /// Exponential ranking with base `c` between 0 (exclusive) and 1: each position is
+    /// `c` times as likely as the one before it. 1 is uniform.
+    Exponential(f64),
+}
+
+/// Returns the selection probability of each of `len` positions, from the first to
+/// the last, summing to 1.
+///
+/// # Panics
+///
+/// Panics if the pressure of [`SelectionScheme::Linear`] isn't between 1 and 2 or the
+/// base of [`SelectionScheme::Exponential`] isn't in `(0, 1]`.
+///
+/// # Examples
+///
+/// ```
+/// use librank::selection::{SelectionScheme, selection_weights};
+///
+/// assert_eq!(selection_weights(3, SelectionScheme::Linear(2.0)), vec![2.0 / 3.0, 1.0 / 3.0, 0.0]);
+/// assert_eq!(selection_weights(2, SelectionScheme::Exponential(0.5)), vec![2.0 / 3.0, 1.0 / 3.0]);
+/// ```
+pub fn selection_weights(len: usize, scheme: SelectionScheme) -> Vec<f64> {
+    match scheme {
+        SelectionScheme::Linear(s) => {
+            assert!(
+                (1.0..=2.0).contains(&s),
+                "SelectionScheme::Linear: pressure must be between 1 and 2, got {s}"
+            );
+            if len == 1 {
+                return vec![1.0];
+            }
+            let n = len as f64;
+            (0..len)
+                .map(|i| (s - 2.0 * (s - 1.0) * i as f64 / (n - 1.0)) / n)
+                .collect()
+        }
+        SelectionScheme::Exponential(c) => {
+            assert!(
+                c > 0.0 && c <= 1.0,
+                "SelectionScheme::Exponential: base must be in (0, 1], got {c}"
+            );
+            let weights: Vec<f64> = (0..len).map(|i| c.powi(i as i32)).collect();
+            let total: f64 = weights.iter().sum();
+            weights.into_iter().map(|w| w / total).collect()
+        }
+    }
+}
+
+/// Returns the selection probability of each item of a ranking, in the ranking's order.
+///
+/// The items take the probabilities of their positions, from
+/// [`selection_weights`], except that the items of a tie group split the probability of
+/// the positions they cover evenly, so tied items are equally likely.
+///
+/// # Panics
+///
+/// Panics on the same invalid schemes as [`selection_weights`].
+///
+/// # Examples
+///
+/// ```
+/// use librank::selection::{SelectionScheme, selection_probabilities};
+/// use librank::{RankedExt, Ranking};
+///
+/// let ranking: Ranking<u32> = vec![1, 2, 2].into_iter().rank_by(|&x| x).collect();
+///
+/// assert_eq!(
+///     selection_probabilities(&ranking, SelectionScheme::Linear(2.0)),
+///     vec![2.0 / 3.0, 1.0 / 6.0, 1.0 / 6.0]
+/// );
+/// ```
+pub fn selection_probabilities<T>(ranking: &Ranking<T>, scheme: SelectionScheme) -> Vec<f64> {
+    let mut weights = selection_weights(ranking.len(), scheme);
+    let mut start = 0;
+    for (_, group) in ranking.groups() {
+        let positions = &mut weights[start..start + group.len()];
+        let shared = positions.iter().sum::<f64>() / group.len() as f64;
+        positions.fill(shared);
+        start += group.len();
+    }
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{actual:?} vs {expected:?}");
+        }
+    }
+
+    #[test]
+    fn test_selection_weights_sum_to_one() {
+        let schemes = [
+            SelectionScheme::Linear(1.0),
+            SelectionScheme::Linear(1.5),
+            SelectionScheme::Linear(2.0),
+            SelectionScheme::Exponential(0.3),
+            SelectionScheme::Exponential(1.0),
+        ];
+        for scheme in schemes {
+            for len in [1, 2, 7, 100] {
+                let weights = selection_weights(len, scheme);
+                assert_eq!(weights.len(), len);
+                assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+                assert!(weights.windows(2).all(|w| w[0] >= w[1]));
+            }
+        }
+        assert!(selection_weights(0, SelectionScheme::Linear(1.5)).is_empty());
+    }
+
+    #[test]
+    fn test_linear_ranking_without_pressure_is_uniform() {
+        assert_close(
+            &selection_weights(4, SelectionScheme::Linear(1.0)),
+            &[0.25; 4],
+        );
+        assert_close(
+            &selection_weights(5, SelectionScheme::Linear(1.5)),
+            &[0.3, 0.25, 0.2, 0.15, 0.1],
+        );
+    }
+
+    #[test]
+    fn test_selection_probabilities_split_ties() {
+        let ranking: Ranking<u32> = vec![5, 1, 5, 5, 9].into_iter().rank_by(|&x| x).collect();
+        let probabilities = selection_probabilities(&ranking, SelectionScheme::Linear(1.5));
+        assert_close(&probabilities, &[0.3, 0.2, 0.2, 0.2, 0.1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_linear_ranking_rejects_pressure_above_two() {
+        selection_weights(3, SelectionScheme::Linear(2.5));
+    }
+}
@@ -0,0 +1,153 @@
+//! Ranking functions named after the SQL window functions they match.
+//!
+//! Each function sorts the items by a key in ascending order, like
+//! `OVER (ORDER BY key)`, and numbers them the way its SQL namesake does. On the keys
+//! `[10, 20, 20, 30]`:
+//!
+//! | SQL            | librank                                       | Ranks     |
+//! |----------------|-----------------------------------------------|-----------|
+//! | `ROW_NUMBER()` | [`row_number_by`]                             | `1 2 3 4` |
+//! | `RANK()`       | [`rank_by`]                                   | `1 2 2 4` |
+//! | `DENSE_RANK()` | [`dense_rank_by`], [`RankedExt::rank_by`]     | `1 2 2 3` |
+//!
+//! Note that [`RankedExt::rank_by`], the crate's main entry point, is `DENSE_RANK()`,
+//! while [`sql::rank_by`](rank_by) is `RANK()`. Tied items keep their input order, so
+//! unlike in SQL the row numbers of tied items are deterministic. Wrap the key in
+//! [`Reverse`](std::cmp::Reverse) for `ORDER BY key DESC`.
+
+use crate::rank::{Rank, RankedExt, RankingStrategy};
+
+/// Numbers the items by a key like `ROW_NUMBER() OVER (ORDER BY key)`: every item gets
+/// its own position, with tied items in input order.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::sql::row_number_by;
+///
+/// // SELECT name, ROW_NUMBER() OVER (ORDER BY score) FROM scores
+/// let scores = vec![("ann", 10), ("bob", 20), ("cy", 20), ("dan", 30)];
+/// let numbered: Vec<(Rank, &str)> = row_number_by(scores, |&(_, score)| score)
+///     .map(|(rank, (name, _))| (rank, name))
+///     .collect();
+///
+/// assert_eq!(numbered, vec![(Rank(1), "ann"), (Rank(2), "bob"), (Rank(3), "cy"), (Rank(4), "dan")]);
+/// ```
+pub fn row_number_by<I, F, K>(items: I, f: F) -> impl Iterator<Item = (Rank, I::Item)>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+{
+    items
+        .into_iter()
+        .rank_by(f)
+        .enumerate()
+        .map(|(i, (_, item))| (Rank(i + 1), item))
+}
+
+/// Ranks the items by a key like `RANK() OVER (ORDER BY key)`: tied items share the
+/// position of the first of them, and the ranks after a tie are skipped.
+///
+/// This is [`RankedExt::rank_by_strategy`] with [`RankingStrategy::Competition`], with
+/// the ranks as [`Rank`]s.
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::sql::rank_by;
+///
+/// // SELECT name, RANK() OVER (ORDER BY score) FROM scores
+/// let scores = vec![("ann", 10), ("bob", 20), ("cy", 20), ("dan", 30)];
+/// let ranked: Vec<(Rank, &str)> = rank_by(scores, |&(_, score)| score)
+///     .map(|(rank, (name, _))| (rank, name))
+///     .collect();
+///
+/// assert_eq!(ranked, vec![(Rank(1), "ann"), (Rank(2), "bob"), (Rank(2), "cy"), (Rank(4), "dan")]);
+/// ```
+pub fn rank_by<I, F, K>(items: I, f: F) -> impl Iterator<Item = (Rank, I::Item)>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+{
+    items
+        .into_iter()
+        .rank_by_strategy(RankingStrategy::Competition, f)
+        .map(|(rank, item)| (Rank(rank as usize), item))
+}
+
+/// Ranks the items by a key like `DENSE_RANK() OVER (ORDER BY key)`: tied items share a
+/// rank and no rank is skipped.
+///
+/// This is the same as [`RankedExt::rank_by`].
+///
+/// # Examples
+///
+/// ```
+/// use librank::Rank;
+/// use librank::sql::dense_rank_by;
+///
+/// // SELECT name, DENSE_RANK() OVER (ORDER BY score) FROM scores
+/// let scores = vec![("ann", 10), ("bob", 20), ("cy", 20), ("dan", 30)];
+/// let ranked: Vec<(Rank, &str)> = dense_rank_by(scores, |&(_, score)| score)
+///     .map(|(rank, (name, _))| (rank, name))
+///     .collect();
+///
+/// assert_eq!(ranked, vec![(Rank(1), "ann"), (Rank(2), "bob"), (Rank(2), "cy"), (Rank(3), "dan")]);
+/// ```
+pub fn dense_rank_by<I, F, K>(items: I, f: F) -> impl Iterator<Item = (Rank, I::Item)>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item) -> K,
+    K: Ord + Eq,
+{
+    items.into_iter().rank_by(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranks(ranked: impl Iterator<Item = (Rank, (char, i32))>) -> Vec<usize> {
+        ranked.map(|(rank, _)| rank.0).collect()
+    }
+
+    #[test]
+    fn test_sql_functions_on_tied_fixture() {
+        let fixture = vec![('d', 30), ('b', 20), ('a', 10), ('c', 20)];
+        let key = |&(_, score): &(char, i32)| score;
+        assert_eq!(ranks(row_number_by(fixture.clone(), key)), vec![1, 2, 3, 4]);
+        assert_eq!(ranks(rank_by(fixture.clone(), key)), vec![1, 2, 2, 4]);
+        assert_eq!(ranks(dense_rank_by(fixture.clone(), key)), vec![1, 2, 2, 3]);
+
+        // Tied rows keep their input order.
+        let order: Vec<char> = row_number_by(fixture, key).map(|(_, (c, _))| c).collect();
+        assert_eq!(order, vec!['a', 'b', 'c', 'd']);
+    }
+
+    #[test]
+    fn test_sql_rank_by_skips_after_each_tie() {
+        let fixture = vec![
+            ('a', 1),
+            ('b', 1),
+            ('c', 1),
+            ('d', 2),
+            ('e', 3),
+            ('f', 3),
+            ('g', 4),
+        ];
+        let key = |&(_, k): &(char, i32)| k;
+        assert_eq!(
+            ranks(rank_by(fixture.clone(), key)),
+            vec![1, 1, 1, 4, 5, 5, 7]
+        );
+        assert_eq!(
+            ranks(dense_rank_by(fixture, key)),
+            vec![1, 1, 1, 2, 3, 3, 4]
+        );
+        assert_eq!(rank_by(Vec::<(char, i32)>::new(), key).count(), 0);
+    }
+}
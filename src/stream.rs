@@ -0,0 +1,227 @@
+//! Ranking the items of an asynchronous [`Stream`].
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec;
+
+use futures_core::{Stream, ready};
+use pin_project_lite::pin_project;
+
+use crate::rank::{Rank, RankedBy};
+
+pin_project! {
+    /// A stream that ranks the items of a stream already sorted by a key.
+    /// Created by [`RankedStreamExt::rank_presorted_by`].
+    pub struct RankedPresorted<S, F, K> {
+        #[pin]
+        stream: S,
+        f: F,
+        rank: Rank,
+        prev_key: Option<K>,
+    }
+}
+
+impl<S, F, K> Stream for RankedPresorted<S, F, K>
+where
+    S: Stream,
+    F: FnMut(&S::Item) -> K,
+    K: Ord + Eq,
+{
+    type Item = (Rank, S::Item);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let Some(item) = ready!(this.stream.poll_next(cx)) else {
+            return Poll::Ready(None);
+        };
+        let key = (this.f)(&item);
+        if this.prev_key.as_ref() != Some(&key) {
+            *this.rank = Rank(this.rank.0 + 1);
+            *this.prev_key = Some(key);
+        }
+        Poll::Ready(Some((*this.rank, item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+pin_project! {
+    /// A stream that collects a whole stream, then yields its items ranked by a key.
+    /// Created by [`RankedStreamExt::rank_by_collected`].
+    pub struct RankedCollected<S, F, K>
+    where
+        S: Stream,
+    {
+        #[pin]
+        stream: S,
+        f: Option<F>,
+        items: Vec<S::Item>,
+        ranked: Option<RankedBy<vec::IntoIter<S::Item>, F, K>>,
+    }
+}
+
+impl<S, F, K> Stream for RankedCollected<S, F, K>
+where
+    S: Stream,
+    F: FnMut(&S::Item) -> K,
+    K: Ord + Eq,
+{
+    type Item = (Rank, S::Item);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(ranked) = this.ranked.as_mut() {
+                return Poll::Ready(ranked.next());
+            }
+            match ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(item) => this.items.push(item),
+                None => {
+                    let mut f = this.f.take().expect("the key function is taken only once");
+                    let mut items = std::mem::take(this.items);
+                    items.sort_by_key(&mut f);
+                    *this.ranked = Some(RankedBy::new(items.into_iter(), f));
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.ranked {
+            Some(ranked) => ranked.size_hint(),
+            None => {
+                let (lower, upper) = self.stream.size_hint();
+                let buffered = self.items.len();
+                (
+                    lower.saturating_add(buffered),
+                    upper.and_then(|upper| upper.checked_add(buffered)),
+                )
+            }
+        }
+    }
+}
+
+/// An extension trait for ranking the items of a [`Stream`].
+pub trait RankedStreamExt: Stream {
+    /// Ranks items that already arrive sorted by a key, yielding each as it arrives.
+    ///
+    /// This is the stream counterpart of
+    /// [`RankedExt::rank_presorted_by`](crate::RankedExt::rank_presorted_by): the input
+    /// must be sorted by `f` in ascending order, which is not checked. The returned
+    /// stream is `Send` when the inner stream, `f` and the key are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use futures::executor::block_on;
+    /// use futures::stream;
+    /// use librank::Rank;
+    /// use librank::stream::RankedStreamExt;
+    ///
+    /// let ranked: Vec<(Rank, i32)> =
+    ///     block_on(stream::iter(vec![1, 1, 3]).rank_presorted_by(|&x| x).collect());
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), 1), (Rank(1), 1), (Rank(2), 3)]);
+    /// ```
+    fn rank_presorted_by<F, K>(self, f: F) -> RankedPresorted<Self, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        RankedPresorted {
+            stream: self,
+            f,
+            rank: Rank(0),
+            prev_key: None,
+        }
+    }
+
+    /// Collects the whole stream and then yields its items ranked by a key.
+    ///
+    /// This is the stream counterpart of [`RankedExt::rank_by`](crate::RankedExt::rank_by)
+    /// for unsorted sources: nothing is yielded until the inner stream ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use futures::executor::block_on;
+    /// use futures::stream;
+    /// use librank::Rank;
+    /// use librank::stream::RankedStreamExt;
+    ///
+    /// let ranked: Vec<(Rank, i32)> =
+    ///     block_on(stream::iter(vec![3, 1, 3]).rank_by_collected(|&x| x).collect());
+    ///
+    /// assert_eq!(ranked, vec![(Rank(1), 1), (Rank(2), 3), (Rank(2), 3)]);
+    /// ```
+    fn rank_by_collected<F, K>(self, f: F) -> RankedCollected<Self, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: Ord + Eq,
+    {
+        RankedCollected {
+            stream: self,
+            f: Some(f),
+            items: Vec::new(),
+            ranked: None,
+        }
+    }
+}
+
+impl<S: Stream> RankedStreamExt for S {}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    use super::*;
+    use crate::RankedExt;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn test_rank_presorted_by_matches_iterator() {
+        let data = vec![2, 2, 5, 6, 6, 6, 9];
+        let ranked = stream::iter(data.clone()).rank_presorted_by(|&x| x);
+        assert_send(&ranked);
+        assert_eq!(ranked.size_hint(), (7, Some(7)));
+        let ranked: Vec<(Rank, i32)> = block_on(ranked.collect());
+        let expected: Vec<(Rank, i32)> = data.into_iter().rank_presorted_by(|&x| x).collect();
+        assert_eq!(ranked, expected);
+    }
+
+    #[test]
+    fn test_rank_presorted_by_yields_as_items_arrive() {
+        let mut ranked = stream::iter(0..).rank_presorted_by(|&x| x / 3);
+        let first: Vec<(Rank, u32)> = block_on((&mut ranked).take(4).collect());
+        assert_eq!(
+            first,
+            vec![(Rank(1), 0), (Rank(1), 1), (Rank(1), 2), (Rank(2), 3)]
+        );
+        assert_eq!(block_on(ranked.next()), Some((Rank(2), 4)));
+    }
+
+    #[test]
+    fn test_rank_by_collected_matches_rank_by() {
+        let data = vec![7, 3, 9, 3, 1, 7];
+        let ranked = stream::iter(data.clone()).rank_by_collected(|&x| x);
+        assert_send(&ranked);
+        let ranked: Vec<(Rank, i32)> = block_on(ranked.collect());
+        let expected: Vec<(Rank, i32)> = data.into_iter().rank_by(|&x| x).collect();
+        assert_eq!(ranked, expected);
+
+        let empty: Vec<(Rank, i32)> = block_on(
+            stream::iter(Vec::<i32>::new())
+                .rank_by_collected(|&x| x)
+                .collect(),
+        );
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+//! Formatting ranked items as aligned plain-text or Markdown tables.
+
+use std::fmt::{self, Write};
+use std::io;
+
+use crate::rank::Rank;
+
+/// The layout of a table written by [`write_table`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Columns separated by two spaces, with a dashed line under the header.
+    #[default]
+    Plain,
+    /// A GitHub-flavored Markdown table.
+    Markdown,
+}
+
+/// How the rank column shows the items after the first of a tie group.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TieDisplay {
+    /// Leave the rank blank.
+    #[default]
+    Collapse,
+    /// Show `=` instead of the rank.
+    Equals,
+    /// Show the rank again.
+    Repeat,
+}
+
+/// The options of [`write_table`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableOptions {
+    /// The layout of the table.
+    pub style: TableStyle,
+    /// How the rank column shows tied items.
+    pub ties: TieDisplay,
+    /// The header of the rank column.
+    pub rank_header: String,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions {
+            style: TableStyle::Plain,
+            ties: TieDisplay::Collapse,
+            rank_header: "Rank".to_string(),
+        }
+    }
+}
+
+/// Writes ranked items as a table with a rank column followed by `headers`.
+///
+/// `cells` turns each item into the cells of its row; missing cells are left empty.
+/// The column widths depend on every row, so all the cells are buffered before the
+/// first line is written; the table itself is written line by line. Widths are counted
+/// in characters, so wide or combining characters may misalign a plain table.
+///
+/// # Examples
+///
+/// ```
+/// use librank::RankedExt;
+/// use librank::table::{TableOptions, TableStyle, write_table};
+///
+/// let scores = vec![("ann", 9), ("bob", 7), ("cy", 7)];
+/// let ranked = scores.into_iter().rank_by(|&(_, score)| std::cmp::Reverse(score));
+/// let options = TableOptions { style: TableStyle::Markdown, ..Default::default() };
+///
+/// let mut out = String::new();
+/// write_table(&mut out, &["Name", "Score"], ranked, |&(name, score)| vec![name.to_string(), score.to_string()], &options).unwrap();
+///
+/// assert_eq!(
+///     out,
+///     "\
+/// | Rank | Name | Score |
+/// |------|------|-------|
+/// | 1    | ann  | 9     |
+/// | 2    | bob  | 7     |
+/// |      | cy   | 7     |
+/// "
+/// );
+/// ```
+pub fn write_table<W, T, F>(
+    out: &mut W,
+    headers: &[&str],
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    mut cells: F,
+    options: &TableOptions,
+) -> fmt::Result
+where
+    W: Write,
+    F: FnMut(&T) -> Vec<String>,
+{
+    let escape = |cell: String| match options.style {
+        TableStyle::Markdown => cell.replace('|', "\\|"),
+        TableStyle::Plain => cell,
+    };
+    let header: Vec<String> = std::iter::once(options.rank_header.clone())
+        .chain(headers.iter().map(|h| h.to_string()))
+        .map(escape)
+        .collect();
+    let mut rows = Vec::new();
+    let mut prev_rank = None;
+    for (rank, item) in ranked {
+        let rank_cell = match options.ties {
+            TieDisplay::Collapse if prev_rank == Some(rank) => String::new(),
+            TieDisplay::Equals if prev_rank == Some(rank) => "=".to_string(),
+            _ => rank.0.to_string(),
+        };
+        prev_rank = Some(rank);
+        let row: Vec<String> = std::iter::once(rank_cell)
+            .chain(cells(&item).into_iter().map(escape))
+            .collect();
+        rows.push(row);
+    }
+
+    let columns = rows.iter().map(Vec::len).fold(header.len(), usize::max);
+    let mut widths = vec![0; columns];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    if options.style == TableStyle::Markdown {
+        // A Markdown delimiter row needs at least three dashes.
+        widths.iter_mut().for_each(|width| *width = (*width).max(3));
+    }
+
+    write_row(out, &header, &widths, options.style)?;
+    match options.style {
+        TableStyle::Plain => {
+            let dashes: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+            write_row(out, &dashes, &widths, options.style)?;
+        }
+        TableStyle::Markdown => {
+            out.write_char('|')?;
+            for &width in &widths {
+                write!(out, "{}|", "-".repeat(width + 2))?;
+            }
+            out.write_char('\n')?;
+        }
+    }
+    for row in &rows {
+        write_row(out, row, &widths, options.style)?;
+    }
+    Ok(())
+}
+
+/// Like [`write_table`], but writes to an [`io::Write`] sink such as a file or standard
+/// output.
+///
+/// The lines are written to `out` as they are formatted, so wrapping a file in a
+/// [`BufWriter`](std::io::BufWriter) avoids many small writes. The first I/O error
+/// stops the table and is returned.
+///
+/// # Examples
+///
+/// ```
+/// use librank::RankedExt;
+/// use librank::table::{TableOptions, write_table_io};
+///
+/// let ranked = vec![("ann", 9), ("bob", 7)].into_iter().rank_by(|&(_, score)| std::cmp::Reverse(score));
+///
+/// let mut out = Vec::new();
+/// write_table_io(&mut out, &["Name"], ranked, |&(name, _)| vec![name.to_string()], &TableOptions::default()).unwrap();
+///
+/// assert_eq!(String::from_utf8(out).unwrap(), "Rank  Name\n----  ----\n1     ann\n2     bob\n");
+/// ```
+pub fn write_table_io<W, T, F>(
+    out: &mut W,
+    headers: &[&str],
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    cells: F,
+    options: &TableOptions,
+) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+    F: FnMut(&T) -> Vec<String>,
+{
+    let mut adapter = IoAdapter { out, error: None };
+    write_table(&mut adapter, headers, ranked, cells, options).map_err(|fmt::Error| {
+        adapter
+            .error
+            .unwrap_or_else(|| io::Error::other(fmt::Error))
+    })
+}
+
+/// Writes formatted text to an [`io::Write`], keeping the I/O error that `fmt::Error`
+/// can't carry.
+struct IoAdapter<'a, W: ?Sized> {
+    out: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write + ?Sized> Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+/// Like [`write_table`], but returns the table as a `String`.
+pub fn format_table<T, F>(
+    headers: &[&str],
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    cells: F,
+    options: &TableOptions,
+) -> String
+where
+    F: FnMut(&T) -> Vec<String>,
+{
+    let mut out = String::new();
+    write_table(&mut out, headers, ranked, cells, options)
+        .expect("writing to a String never fails");
+    out
+}
+
+fn write_row<W: Write>(
+    out: &mut W,
+    row: &[String],
+    widths: &[usize],
+    style: TableStyle,
+) -> fmt::Result {
+    let cell = |i: usize| row.get(i).map_or("", String::as_str);
+    match style {
+        TableStyle::Plain => {
+            let mut line = String::new();
+            for (i, &width) in widths.iter().enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                write!(line, "{:width$}", cell(i))?;
+            }
+            writeln!(out, "{}", line.trim_end())
+        }
+        TableStyle::Markdown => {
+            out.write_char('|')?;
+            for (i, &width) in widths.iter().enumerate() {
+                write!(out, " {:width$} |", cell(i))?;
+            }
+            out.write_char('\n')
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Reverse;
+
+    use super::*;
+    use crate::RankedExt;
+
+    fn table(style: TableStyle, ties: TieDisplay) -> String {
+        let players = vec![
+            ("ann", 12),
+            ("bob", 30),
+            ("cy", 12),
+            ("dominique", 5),
+            ("eve", 12),
+        ];
+        let options = TableOptions {
+            style,
+            ties,
+            ..Default::default()
+        };
+        format_table(
+            &["Player", "Points"],
+            players.into_iter().rank_by(|&(_, points)| Reverse(points)),
+            |&(name, points)| vec![name.to_string(), points.to_string()],
+            &options,
+        )
+    }
+
+    #[test]
+    fn test_format_table_plain() {
+        let expected = "\
+Rank  Player     Points
+----  ---------  ------
+1     bob        30
+2     ann        12
+      cy         12
+      eve        12
+3     dominique  5
+";
+        assert_eq!(table(TableStyle::Plain, TieDisplay::Collapse), expected);
+    }
+
+    #[test]
+    fn test_format_table_markdown_equals() {
+        let expected = "\
+| Rank | Player    | Points |
+|------|-----------|--------|
+| 1    | bob       | 30     |
+| 2    | ann       | 12     |
+| =    | cy        | 12     |
+| =    | eve       | 12     |
+| 3    | dominique | 5      |
+";
+        assert_eq!(table(TableStyle::Markdown, TieDisplay::Equals), expected);
+    }
+
+    #[test]
+    fn test_format_table_repeat_escapes_and_short_rows() {
+        let ranked = vec![(Rank(1), "a|b"), (Rank(1), "c")];
+        let options = TableOptions {
+            style: TableStyle::Markdown,
+            ties: TieDisplay::Repeat,
+            rank_header: "#".to_string(),
+        };
+        let out = format_table(
+            &["Name", "Note"],
+            ranked,
+            |&name| vec![name.to_string()],
+            &options,
+        );
+        let expected = "\
+| #   | Name | Note |
+|-----|------|------|
+| 1   | a\\|b |      |
+| 1   | c    |      |
+";
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_write_table_io() {
+        let players = [("ann", 12), ("bob", 30), ("cy", 12)];
+        let options = TableOptions {
+            style: TableStyle::Markdown,
+            ..Default::default()
+        };
+        let ranked = || {
+            players
+                .iter()
+                .copied()
+                .rank_by(|&(_, points)| Reverse(points))
+        };
+        let cells = |&(name, points): &(&str, u32)| vec![name.to_string(), points.to_string()];
+
+        let mut out = Vec::new();
+        write_table_io(&mut out, &["Player", "Points"], ranked(), cells, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format_table(&["Player", "Points"], ranked(), cells, &options)
+        );
+
+        // Room for the header line only.
+        let mut buffer = [0u8; 30];
+        let mut sink = &mut buffer[..];
+        let error = write_table_io(&mut sink, &["Player", "Points"], ranked(), cells, &options)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+    }
+}
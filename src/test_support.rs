@@ -0,0 +1,15 @@
+//! Helpers shared by the unit tests.
+
+/// Returns the successive states of a linear congruential generator started at
+/// `seed`, for deterministic pseudo-random test data. The high bits are the most
+/// random, so callers shift them down, as in `(state >> 33) % n`.
+pub(crate) fn lcg(seed: u64) -> impl Iterator<Item = u64> {
+    std::iter::successors(Some(seed), |state| {
+        Some(
+            state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407),
+        )
+    })
+    .skip(1)
+}
@@ -0,0 +1,380 @@
+//! Labeling ranked items with named tiers, such as Gold, Silver and Bronze.
+//!
+//! A [`Tiering`] is declared with [`Tiering::builder`] as an ordered list of tiers, each
+//! covering a range of positions in the ranking: 1-based positions like `1..=3`, or
+//! fractions of the ranking like `0.0..0.1` for the top 10%. The positions are those of
+//! the items in rank order, so tied items have different positions and a tie group can
+//! straddle the boundary between two tiers; the [`BoundaryPolicy`] decides where it
+//! goes.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+
+use crate::rank::Rank;
+use crate::ranking::Ranking;
+
+/// Where a tie group straddling the boundary between two tiers goes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoundaryPolicy {
+    /// The whole group goes into the tier of its first item, the higher one.
+    Promote,
+    /// The whole group goes into the tier of its last item, the lower one.
+    Demote,
+    /// Each item goes into the tier of its own position, splitting the group.
+    #[default]
+    Split,
+}
+
+/// The positions covered by one tier.
+#[derive(Clone, Debug, PartialEq)]
+enum TierBound {
+    Positions(RangeInclusive<usize>),
+    Fraction(Range<f64>),
+}
+
+/// The error returned by [`TieringBuilder::build`] for an invalid list of tiers.
+///
+/// `tier` is the 0-based index of the offending tier in declaration order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TierError {
+    /// The tier covers no position, or a fraction outside `0.0..=1.0`.
+    InvalidBound {
+        /// The index of the tier.
+        tier: usize,
+    },
+    /// The tier starts before the previous one ends.
+    Overlap {
+        /// The index of the tier.
+        tier: usize,
+    },
+    /// The tier starts after the position right after the previous one, or the first
+    /// tier doesn't start at the top.
+    Gap {
+        /// The index of the tier.
+        tier: usize,
+    },
+    /// The tier is declared by positions and another by fractions, or the other way
+    /// around.
+    MixedBounds {
+        /// The index of the tier.
+        tier: usize,
+    },
+}
+
+impl fmt::Display for TierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TierError::InvalidBound { tier } => {
+                write!(f, "tier {tier} has an empty or invalid range")
+            }
+            TierError::Overlap { tier } => write!(f, "tier {tier} overlaps the previous tier"),
+            TierError::Gap { tier } => write!(f, "tier {tier} leaves a gap before it"),
+            TierError::MixedBounds { tier } => {
+                write!(
+                    f,
+                    "tier {tier} mixes positions and fractions with the other tiers"
+                )
+            }
+        }
+    }
+}
+
+impl Error for TierError {}
+
+/// Declares the tiers of a [`Tiering`], from the top of the ranking down.
+#[derive(Clone, Debug)]
+pub struct TieringBuilder<L> {
+    tiers: Vec<(TierBound, L)>,
+    policy: BoundaryPolicy,
+}
+
+impl<L> TieringBuilder<L> {
+    /// Adds a tier covering the 1-based positions in `positions`.
+    pub fn ranks(mut self, positions: RangeInclusive<usize>, label: L) -> Self {
+        self.tiers.push((TierBound::Positions(positions), label));
+        self
+    }
+
+    /// Adds a tier covering a fraction of the ranking, from the top: `0.0..0.1` is the
+    /// top 10% and `0.1..0.3` the next 20%.
+    ///
+    /// The ends are multiplied by the number of ranked items and rounded to the nearest
+    /// position.
+    pub fn fraction(mut self, range: Range<f64>, label: L) -> Self {
+        self.tiers.push((TierBound::Fraction(range), label));
+        self
+    }
+
+    /// Sets where tie groups straddling a boundary go; the default is
+    /// [`BoundaryPolicy::Split`].
+    pub fn policy(mut self, policy: BoundaryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Checks the tiers and builds the tiering.
+    ///
+    /// The tiers must all be declared the same way, and each must start right where the
+    /// previous one ends, the first one at the top. The last tier may end before the
+    /// bottom of the ranking, leaving the remaining items without a tier.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TierError`] naming the first tier that breaks these rules.
+    pub fn build(self) -> Result<Tiering<L>, TierError> {
+        let mut previous: Option<&TierBound> = None;
+        for (tier, (bound, _)) in self.tiers.iter().enumerate() {
+            match (previous, bound) {
+                (_, TierBound::Positions(range)) if *range.start() == 0 || range.is_empty() => {
+                    return Err(TierError::InvalidBound { tier });
+                }
+                (_, TierBound::Fraction(range))
+                    if !(0.0 <= range.start && range.start < range.end && range.end <= 1.0) =>
+                {
+                    return Err(TierError::InvalidBound { tier });
+                }
+                (None, TierBound::Positions(range)) if *range.start() != 1 => {
+                    return Err(TierError::Gap { tier });
+                }
+                (None, TierBound::Fraction(range)) if range.start != 0.0 => {
+                    return Err(TierError::Gap { tier });
+                }
+                (None, _) => {}
+                (Some(TierBound::Positions(prev)), TierBound::Positions(range)) => {
+                    let expected = prev.end() + 1;
+                    if *range.start() < expected {
+                        return Err(TierError::Overlap { tier });
+                    } else if *range.start() > expected {
+                        return Err(TierError::Gap { tier });
+                    }
+                }
+                (Some(TierBound::Fraction(prev)), TierBound::Fraction(range)) => {
+                    if range.start < prev.end {
+                        return Err(TierError::Overlap { tier });
+                    } else if range.start > prev.end {
+                        return Err(TierError::Gap { tier });
+                    }
+                }
+                (Some(_), _) => return Err(TierError::MixedBounds { tier }),
+            }
+            previous = Some(bound);
+        }
+        Ok(Tiering {
+            tiers: self.tiers,
+            policy: self.policy,
+        })
+    }
+}
+
+/// An ordered list of named tiers to label ranked items with.
+///
+/// # Examples
+///
+/// ```
+/// use librank::tier::{BoundaryPolicy, Tiering};
+/// use librank::{Rank, RankedExt, Ranking};
+///
+/// let tiers = Tiering::builder()
+///     .ranks(1..=2, "Gold")
+///     .ranks(3..=4, "Silver")
+///     .policy(BoundaryPolicy::Promote)
+///     .build()
+///     .unwrap();
+///
+/// let points = vec![("ann", 9), ("bob", 7), ("cy", 7), ("dee", 4), ("eve", 2)];
+/// let ranking: Ranking<(&str, u32)> =
+///     points.into_iter().rank_by_desc(|&(_, p)| p).collect();
+/// let labeled: Vec<(Option<&&str>, Rank, &str)> = tiers
+///     .apply(ranking)
+///     .into_iter()
+///     .map(|(tier, rank, (name, _))| (tier, rank, name))
+///     .collect();
+///
+/// // bob and cy are tied across the Gold/Silver boundary and both get Gold.
+/// assert_eq!(
+///     labeled,
+///     vec![
+///         (Some(&"Gold"), Rank(1), "ann"),
+///         (Some(&"Gold"), Rank(2), "bob"),
+///         (Some(&"Gold"), Rank(2), "cy"),
+///         (Some(&"Silver"), Rank(3), "dee"),
+///         (None, Rank(4), "eve"),
+///     ]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tiering<L> {
+    tiers: Vec<(TierBound, L)>,
+    policy: BoundaryPolicy,
+}
+
+impl<L> Tiering<L> {
+    /// Starts declaring a tiering with no tiers and [`BoundaryPolicy::Split`].
+    pub fn builder() -> TieringBuilder<L> {
+        TieringBuilder {
+            tiers: Vec::new(),
+            policy: BoundaryPolicy::Split,
+        }
+    }
+
+    /// Labels every item of a ranking with its tier, or `None` if it is past the last
+    /// tier, keeping the ranking's order and ranks.
+    pub fn apply<T>(&self, ranking: Ranking<T>) -> Vec<(Option<&L>, Rank, T)> {
+        let n = ranking.len();
+        // The last 1-based position of each tier.
+        let ends: Vec<usize> = self
+            .tiers
+            .iter()
+            .map(|(bound, _)| match bound {
+                TierBound::Positions(range) => *range.end(),
+                TierBound::Fraction(range) => (range.end * n as f64).round() as usize,
+            })
+            .collect();
+        let tier_at = |position: usize| {
+            let tier = ends.partition_point(|&end| end < position);
+            self.tiers.get(tier).map(|(_, label)| label)
+        };
+
+        let mut labeled = Vec::with_capacity(n);
+        let mut start = 0;
+        for (i, group) in ranking.into_groups().into_iter().enumerate() {
+            let len = group.len();
+            for (offset, item) in group.into_iter().enumerate() {
+                let position = match self.policy {
+                    BoundaryPolicy::Promote => start + 1,
+                    BoundaryPolicy::Demote => start + len,
+                    BoundaryPolicy::Split => start + offset + 1,
+                };
+                labeled.push((tier_at(position), Rank(i + 1), item));
+            }
+            start += len;
+        }
+        labeled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+
+    /// Ranks 1 to 3 with a tie group of three at positions 2 to 4.
+    fn straddling() -> Ranking<(char, u32)> {
+        vec![('a', 1), ('b', 2), ('c', 2), ('d', 2), ('e', 3)]
+            .into_iter()
+            .rank_by(|&(_, k)| k)
+            .collect()
+    }
+
+    fn labels(tiering: &Tiering<&'static str>) -> Vec<Option<&'static str>> {
+        tiering
+            .apply(straddling())
+            .into_iter()
+            .map(|(tier, _, _)| tier.copied())
+            .collect()
+    }
+
+    fn gold_silver(policy: BoundaryPolicy) -> Tiering<&'static str> {
+        Tiering::builder()
+            .ranks(1..=3, "Gold")
+            .ranks(4..=5, "Silver")
+            .policy(policy)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_boundary_policies() {
+        let (g, s) = (Some("Gold"), Some("Silver"));
+        assert_eq!(
+            labels(&gold_silver(BoundaryPolicy::Promote)),
+            vec![g, g, g, g, s]
+        );
+        assert_eq!(
+            labels(&gold_silver(BoundaryPolicy::Demote)),
+            vec![g, s, s, s, s]
+        );
+        assert_eq!(
+            labels(&gold_silver(BoundaryPolicy::Split)),
+            vec![g, g, g, s, s]
+        );
+
+        let ranks: Vec<Rank> = gold_silver(BoundaryPolicy::Split)
+            .apply(straddling())
+            .into_iter()
+            .map(|(_, rank, _)| rank)
+            .collect();
+        assert_eq!(ranks, vec![Rank(1), Rank(2), Rank(2), Rank(2), Rank(3)]);
+    }
+
+    #[test]
+    fn test_fraction_tiers() {
+        let tiering = Tiering::builder()
+            .fraction(0.0..0.2, "Gold")
+            .fraction(0.2..0.6, "Silver")
+            .build()
+            .unwrap();
+        // 0.2 and 0.6 of 5 items end the tiers at positions 1 and 3.
+        let (g, s) = (Some("Gold"), Some("Silver"));
+        assert_eq!(labels(&tiering), vec![g, s, s, None, None]);
+
+        let ranking: Ranking<u32> = (0..20).rank_by(|&x| x).collect();
+        let gold = tiering
+            .apply(ranking)
+            .into_iter()
+            .filter(|(tier, _, _)| *tier == Some(&"Gold"))
+            .count();
+        assert_eq!(gold, 4);
+        assert!(
+            tiering
+                .apply(Ranking::<u32>::from_groups(Vec::new()))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_tiers() {
+        let build = |builder: TieringBuilder<&str>| builder.build().map(|_| ());
+        let builder = Tiering::builder;
+        assert_eq!(
+            build(builder().ranks(1..=3, "a").ranks(3..=5, "b")),
+            Err(TierError::Overlap { tier: 1 })
+        );
+        assert_eq!(
+            build(builder().ranks(1..=3, "a").ranks(5..=6, "b")),
+            Err(TierError::Gap { tier: 1 })
+        );
+        assert_eq!(
+            build(builder().ranks(2..=3, "a")),
+            Err(TierError::Gap { tier: 0 })
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 3..=2;
+        assert_eq!(
+            build(builder().ranks(1..=2, "a").ranks(empty, "b")),
+            Err(TierError::InvalidBound { tier: 1 })
+        );
+        assert_eq!(
+            build(builder().fraction(0.0..0.1, "a").fraction(0.05..0.3, "b")),
+            Err(TierError::Overlap { tier: 1 })
+        );
+        assert_eq!(
+            build(builder().fraction(0.0..0.1, "a").fraction(0.2..0.3, "b")),
+            Err(TierError::Gap { tier: 1 })
+        );
+        assert_eq!(
+            build(builder().fraction(0.0..1.5, "a")),
+            Err(TierError::InvalidBound { tier: 0 })
+        );
+        assert_eq!(
+            build(builder().ranks(1..=3, "a").fraction(0.5..1.0, "b")),
+            Err(TierError::MixedBounds { tier: 1 })
+        );
+        assert_eq!(
+            TierError::Gap { tier: 1 }.to_string(),
+            "tier 1 leaves a gap before it"
+        );
+        assert!(build(builder()).is_ok());
+    }
+}
@@ -0,0 +1,295 @@
+//! Validation of externally produced `(Rank, T)` sequences.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::rank::Rank;
+
+/// The rule broken by a ranked sequence, reported by [`RankingViolation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The first item's rank is not 1.
+    FirstRankNotOne {
+        /// The rank found on the first item.
+        found: Rank,
+    },
+    /// The rank is lower than the previous item's.
+    RankDecreased {
+        /// The previous item's rank.
+        previous: Rank,
+        /// The rank found.
+        found: Rank,
+    },
+    /// The rank jumped over one or more ranks.
+    RankSkipped {
+        /// The highest rank allowed at this position.
+        expected: Rank,
+        /// The rank found.
+        found: Rank,
+    },
+    /// The key is less than the previous item's key.
+    KeyDecreased {
+        /// The rank found.
+        found: Rank,
+    },
+    /// The key differs from the previous item's key, but the rank doesn't change.
+    KeyChangedWithinRank {
+        /// The rank shared with the previous item.
+        rank: Rank,
+    },
+    /// The rank changes, but the key equals the previous item's key.
+    RankChangedWithinKey {
+        /// The previous item's rank, which the item was expected to share.
+        expected: Rank,
+        /// The rank found.
+        found: Rank,
+    },
+}
+
+/// The error returned when a ranked sequence breaks a rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RankingViolation {
+    /// The 0-based position of the offending item.
+    pub index: usize,
+    /// The rule that was broken.
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for RankingViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "item {}: ", self.index)?;
+        match self.kind {
+            ViolationKind::FirstRankNotOne { found } => {
+                write!(f, "expected the first rank to be 1, found {}", found.0)
+            }
+            ViolationKind::RankDecreased { previous, found } => {
+                write!(f, "rank decreased from {} to {}", previous.0, found.0)
+            }
+            ViolationKind::RankSkipped { expected, found } => {
+                write!(f, "expected rank {} at most, found {}", expected.0, found.0)
+            }
+            ViolationKind::KeyDecreased { found } => {
+                write!(f, "key decreased at rank {}", found.0)
+            }
+            ViolationKind::KeyChangedWithinRank { rank } => {
+                write!(f, "key changed within rank {}", rank.0)
+            }
+            ViolationKind::RankChangedWithinKey { expected, found } => {
+                write!(
+                    f,
+                    "expected rank {} for an unchanged key, found {}",
+                    expected.0, found.0
+                )
+            }
+        }
+    }
+}
+
+impl Error for RankingViolation {}
+
+/// Checks that a sequence is a dense ranking of its items by `key_fn`.
+///
+/// This is what [`rank_by`](crate::RankedExt::rank_by) produces: ranks start at 1,
+/// never decrease and never skip, keys never decrease, and the rank changes exactly
+/// when the key changes. The first violation found is returned.
+///
+/// # Examples
+///
+/// ```
+/// use librank::verify::{ViolationKind, verify_dense_ranking};
+/// use librank::{Rank, RankedExt};
+///
+/// let ranked = vec![3, 1, 3].into_iter().rank_by(|&x| x);
+/// assert!(verify_dense_ranking(ranked, |&x| x).is_ok());
+///
+/// let skipped = vec![(Rank(1), 1), (Rank(3), 3)];
+/// let violation = verify_dense_ranking(skipped, |&x| x).unwrap_err();
+/// assert_eq!(violation.index, 1);
+/// assert_eq!(
+///     violation.kind,
+///     ViolationKind::RankSkipped { expected: Rank(2), found: Rank(3) }
+/// );
+/// ```
+pub fn verify_dense_ranking<T, K, F>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+    mut key_fn: F,
+) -> Result<(), RankingViolation>
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    let mut prev: Option<(Rank, K)> = None;
+    for (index, (rank, item)) in ranked.into_iter().enumerate() {
+        let key = key_fn(&item);
+        let violation = |kind| Err(RankingViolation { index, kind });
+        match &prev {
+            None if rank != Rank(1) => {
+                return violation(ViolationKind::FirstRankNotOne { found: rank });
+            }
+            None => {}
+            Some((prev_rank, prev_key)) => {
+                let prev_rank = *prev_rank;
+                if rank < prev_rank {
+                    return violation(ViolationKind::RankDecreased {
+                        previous: prev_rank,
+                        found: rank,
+                    });
+                }
+                if rank.0 > prev_rank.0 + 1 {
+                    return violation(ViolationKind::RankSkipped {
+                        expected: Rank(prev_rank.0 + 1),
+                        found: rank,
+                    });
+                }
+                if key < *prev_key {
+                    return violation(ViolationKind::KeyDecreased { found: rank });
+                }
+                if rank == prev_rank && key != *prev_key {
+                    return violation(ViolationKind::KeyChangedWithinRank { rank });
+                }
+                if rank != prev_rank && key == *prev_key {
+                    return violation(ViolationKind::RankChangedWithinKey {
+                        expected: prev_rank,
+                        found: rank,
+                    });
+                }
+            }
+        }
+        prev = Some((rank, key));
+    }
+    Ok(())
+}
+
+/// Checks only that the ranks of a sequence never decrease.
+///
+/// Use this when no key function is available; see [`verify_dense_ranking`] for the
+/// full check.
+pub fn verify_monotonic<T>(
+    ranked: impl IntoIterator<Item = (Rank, T)>,
+) -> Result<(), RankingViolation> {
+    let mut prev_rank = None;
+    for (index, (rank, _)) in ranked.into_iter().enumerate() {
+        if let Some(previous) = prev_rank
+            && rank < previous
+        {
+            return Err(RankingViolation {
+                index,
+                kind: ViolationKind::RankDecreased {
+                    previous,
+                    found: rank,
+                },
+            });
+        }
+        prev_rank = Some(rank);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedExt;
+
+    fn check(ranked: &[(usize, i32)]) -> Result<(), RankingViolation> {
+        verify_dense_ranking(ranked.iter().map(|&(r, k)| (Rank(r), k)), |&k| k)
+    }
+
+    #[test]
+    fn test_verify_dense_ranking_accepts_rank_by_output() {
+        let data = vec![4, 1, 3, 1, 4, 4, 2];
+        assert_eq!(
+            verify_dense_ranking(data.into_iter().rank_by(|&x| x), |&x| x),
+            Ok(())
+        );
+        assert_eq!(check(&[]), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_dense_ranking_first_rank_not_one() {
+        let violation = check(&[(2, 10), (3, 20)]).unwrap_err();
+        assert_eq!(violation.index, 0);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::FirstRankNotOne { found: Rank(2) }
+        );
+    }
+
+    #[test]
+    fn test_verify_dense_ranking_rank_decreased() {
+        let violation = check(&[(1, 10), (2, 20), (1, 30)]).unwrap_err();
+        assert_eq!(violation.index, 2);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::RankDecreased {
+                previous: Rank(2),
+                found: Rank(1)
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_dense_ranking_rank_skipped() {
+        let violation = check(&[(1, 10), (1, 10), (3, 20)]).unwrap_err();
+        assert_eq!(violation.index, 2);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::RankSkipped {
+                expected: Rank(2),
+                found: Rank(3)
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "item 2: expected rank 2 at most, found 3"
+        );
+    }
+
+    #[test]
+    fn test_verify_dense_ranking_key_decreased() {
+        let violation = check(&[(1, 10), (2, 5)]).unwrap_err();
+        assert_eq!(violation.index, 1);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::KeyDecreased { found: Rank(2) }
+        );
+    }
+
+    #[test]
+    fn test_verify_dense_ranking_key_changed_within_rank() {
+        let violation = check(&[(1, 10), (1, 20)]).unwrap_err();
+        assert_eq!(violation.index, 1);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::KeyChangedWithinRank { rank: Rank(1) }
+        );
+    }
+
+    #[test]
+    fn test_verify_dense_ranking_rank_changed_within_key() {
+        let violation = check(&[(1, 10), (2, 10)]).unwrap_err();
+        assert_eq!(violation.index, 1);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::RankChangedWithinKey {
+                expected: Rank(1),
+                found: Rank(2)
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_monotonic() {
+        let ok = vec![(Rank(1), 'a'), (Rank(3), 'b'), (Rank(3), 'c')];
+        assert_eq!(verify_monotonic(ok), Ok(()));
+        let bad = vec![(Rank(2), 'a'), (Rank(1), 'b')];
+        let violation = verify_monotonic(bad).unwrap_err();
+        assert_eq!(violation.index, 1);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::RankDecreased {
+                previous: Rank(2),
+                found: Rank(1)
+            }
+        );
+    }
+}
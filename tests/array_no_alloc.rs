@@ -0,0 +1,53 @@
+//! Checks that the fixed-size array rankers don't allocate.
+//!
+//! This lives in its own test binary because it installs a counting global allocator,
+//! which would otherwise apply to every unit test of the crate.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use librank::Rank;
+use librank::array::{rank_array_by, rank_array_const, rank_array_ref_by};
+
+/// Counts the allocations made by each thread, so that tests running in parallel don't
+/// disturb each other's counts.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn test_rank_array_does_not_allocate() {
+    let words = [
+        "pear", "fig", "apple", "kiwi", "plum", "date", "lime", "yuzu",
+    ];
+    let before = allocations();
+    let ranked = rank_array_by(words, |w| w.len());
+    let ranks = rank_array_ref_by(&words, |w| w.len());
+    let const_ranks = rank_array_const([3, 1, 3, 2]);
+    assert_eq!(allocations(), before);
+
+    assert_eq!(ranked[0], (Rank(1), "fig"));
+    assert_eq!(ranked[7], (Rank(3), "apple"));
+    assert_eq!(ranks[2], Rank(3));
+    assert_eq!(const_ranks, [Rank(3), Rank(1), Rank(3), Rank(2)]);
+}